@@ -0,0 +1,77 @@
+//! Documents shaped like the inputs the parser sees in practice. They are
+//! generated rather than checked in so their size can be tuned here.
+
+/// A typical hand-written configuration file.
+pub fn small_config() -> String {
+    String::from(
+        r#"{
+  "name": "json-parser",
+  "version": 3,
+  "debug": false,
+  "output": null,
+  "server": {"host": "localhost", "port": 8080, "workers": 4},
+  "features": ["gzip", "zstd", "archive"],
+  "limits": {"max_depth": 64, "max_bytes": 1048576}
+}"#,
+    )
+}
+
+/// One object with many members.
+pub fn wide_object() -> String {
+    let members: Vec<String> = (0..10_000)
+        .map(|idx| format!("\"key_{}\": {}", idx, idx))
+        .collect();
+    format!("{{{}}}", members.join(", "))
+}
+
+/// Objects and arrays nested a few hundred levels deep.
+pub fn deep_nesting() -> String {
+    let depth = 500;
+    let mut content = String::new();
+    for idx in 0..depth {
+        if idx % 2 == 0 {
+            content.push_str("{\"child\": ");
+        } else {
+            content.push('[');
+        }
+    }
+    content.push_str("null");
+    for idx in (0..depth).rev() {
+        content.push(if idx % 2 == 0 { '}' } else { ']' });
+    }
+    content
+}
+
+/// A root array of small, uniformly shaped records.
+pub fn large_array() -> String {
+    let records: Vec<String> = (0..20_000)
+        .map(|idx| {
+            format!(
+                "{{\"id\": {}, \"active\": {}, \"score\": {}, \"tags\": [\"a\", \"b\"]}}",
+                idx,
+                idx % 3 == 0,
+                idx * 7 % 1000
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(",\n"))
+}
+
+/// Long strings with a sprinkling of escapes.
+pub fn string_heavy() -> String {
+    let text = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    let strings: Vec<String> = (0..2_000)
+        .map(|idx| format!("\"{} \\\"{}\\\" \\u00e9\\n\"", text, idx))
+        .collect();
+    format!("{{\"lines\": [{}]}}", strings.join(", "))
+}
+
+pub fn all() -> Vec<(&'static str, String)> {
+    vec![
+        ("small_config", small_config()),
+        ("wide_object", wide_object()),
+        ("deep_nesting", deep_nesting()),
+        ("large_array", large_array()),
+        ("string_heavy", string_heavy()),
+    ]
+}
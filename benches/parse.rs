@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use json_parser::{
+    parser::{ParseOptions, Parser, JSON},
+    printer::Style,
+};
+
+mod corpus;
+
+fn parse(c: &mut Criterion) {
+    let options = ParseOptions::default();
+    let mut group = c.benchmark_group("parse");
+    for (name, content) in corpus::all() {
+        assert!(
+            JSON::parse_from_string(&content, &options).is_ok(),
+            "{}",
+            name
+        );
+
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_function(name, |b| {
+            let mut parser = Parser::default();
+            b.iter(|| parser.parse_from_string(black_box(&content), &options))
+        });
+    }
+    group.finish();
+}
+
+fn serialize(c: &mut Criterion) {
+    let options = ParseOptions::default();
+    let style = Style::default();
+    let mut group = c.benchmark_group("serialize");
+    for (name, content) in corpus::all() {
+        let Ok(value) = JSON::parse_from_string(&content, &options) else {
+            panic!("{}", name);
+        };
+
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_function(name, |b| b.iter(|| value.try_to_styled_string(&style)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, serialize);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! Parses the same corpora with this crate and with `serde_json`, side by
+//! side. Run with `cargo bench --bench serde_baseline`; it is kept out of the
+//! main suite so day-to-day runs only measure this crate.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use json_parser::parser::{ParseOptions, Parser};
+
+mod corpus;
+
+fn compare(c: &mut Criterion) {
+    let options = ParseOptions::default();
+    let mut group = c.benchmark_group("baseline");
+    for (name, content) in corpus::all() {
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("json_parser", name),
+            &content,
+            |b, content| {
+                let mut parser = Parser::default();
+                b.iter(|| parser.parse_from_string(black_box(content), &options))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("serde_json", name),
+            &content,
+            |b, content| b.iter(|| serde_json::from_str::<serde_json::Value>(black_box(content))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compare);
+criterion_main!(benches);
@@ -0,0 +1,181 @@
+//! `#[derive(FromJson, ToJson)]` for `json_parser`, enabled there with the
+//! `derive` feature.
+//!
+//! Only structs with named fields are supported. Fields take a `#[json(..)]`
+//! attribute:
+//!
+//! - `rename = "name"` reads and writes the member under another key.
+//! - `default` uses `Default::default()` when the member is missing or null.
+//! - `default = "path::to::fn"` calls that function instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Generics, Ident, LitStr,
+    Path, Type,
+};
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_json(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_json(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum FieldDefault {
+    None,
+    Trait,
+    Function(Path),
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    key: String,
+    default: FieldDefault,
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "only structs with named fields are supported",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "only structs with named fields are supported",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    for field in named {
+        let ident = field.ident.clone().expect("named field");
+        let mut key = ident.to_string();
+        let mut default = FieldDefault::None;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("json"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    default = match meta.value() {
+                        Ok(value) => FieldDefault::Function(value.parse::<LitStr>()?.parse()?),
+                        Err(_) => FieldDefault::Trait,
+                    };
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename` or `default`"))
+                }
+            })?;
+        }
+
+        fields.push(Field {
+            ident,
+            ty: field.ty.clone(),
+            key,
+            default,
+        });
+    }
+    Ok(fields)
+}
+
+fn with_bound(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+fn from_json(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = fields(&input)?;
+    let generics = with_bound(&input.generics, quote!(::json_parser::convert::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let reads = fields.iter().map(|field| {
+        let Field { ident, ty, key, .. } = field;
+        let read = match &field.default {
+            FieldDefault::None => quote! {
+                ::json_parser::convert::read_field::<#ty>(json, #key)?
+            },
+            FieldDefault::Trait => quote! {
+                ::json_parser::convert::read_field_or::<#ty>(
+                    json, #key, ::core::default::Default::default,
+                )?
+            },
+            FieldDefault::Function(path) => quote! {
+                ::json_parser::convert::read_field_or::<#ty>(json, #key, #path)?
+            },
+        };
+        quote!(#ident: #read)
+    });
+
+    let type_name = name.to_string();
+    Ok(quote! {
+        impl #impl_generics ::json_parser::convert::FromJson for #name #ty_generics #where_clause {
+            fn from_json(
+                value: &::json_parser::parser::JSONValue,
+            ) -> ::core::result::Result<Self, ::json_parser::convert::ConvertError> {
+                let json = ::json_parser::convert::expect_object(value, #type_name)?;
+                ::core::result::Result::Ok(#name {
+                    #(#reads,)*
+                })
+            }
+        }
+    })
+}
+
+fn to_json(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = fields(&input)?;
+    let generics = with_bound(&input.generics, quote!(::json_parser::convert::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let writes = fields.iter().map(|Field { ident, key, .. }| {
+        quote! {
+            json.insert(
+                #key,
+                ::json_parser::convert::ToJson::to_json(&self.#ident)
+                    .map_err(|err| err.at_key(#key))?,
+            );
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser::convert::ToJson for #name #ty_generics #where_clause {
+            fn to_json(
+                &self,
+            ) -> ::core::result::Result<
+                ::json_parser::parser::JSONValue,
+                ::json_parser::convert::ConvertError,
+            > {
+                let mut json = ::json_parser::parser::JSON::new();
+                #(#writes)*
+                ::core::result::Result::Ok(::json_parser::parser::JSONValue::Object(json))
+            }
+        }
+    })
+}
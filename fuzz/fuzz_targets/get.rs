@@ -0,0 +1,42 @@
+#![no_main]
+
+use json_parser::{
+    lazy::Lazy,
+    parser::{ParseOptions, JSON},
+    path::Path,
+};
+use libfuzzer_sys::fuzz_target;
+
+// The first line is a `--get` path and the rest a document. Looking the path
+// up lazily must agree with parsing the whole document and walking it.
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Some((path, content)) = text.split_once('\n') else {
+        return;
+    };
+    let Ok(path) = Path::parse(path) else {
+        return;
+    };
+
+    let lazy = Lazy::new(content).at(&path);
+    let Ok(parsed) = JSON::parse_from_string(content, &ParseOptions::default()) else {
+        return;
+    };
+    let found = match lazy {
+        Ok(found) => found.map(|value| value.value().cloned()),
+        Err(_) => panic!("{} parses, but the lazy lookup of {} fails", content, path),
+    };
+    let mut expected = None;
+    parsed.clone().transform(|at, value| {
+        if *at == path {
+            expected = Some(value.clone());
+        }
+    });
+    match (found, expected) {
+        (None, None) => {}
+        (Some(Ok(found)), Some(expected)) => assert_eq!(found, expected),
+        (found, expected) => panic!("{} at {}: {:?} != {:?}", content, path, found, expected),
+    }
+});
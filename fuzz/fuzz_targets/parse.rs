@@ -0,0 +1,22 @@
+#![no_main]
+
+use json_parser::parser::{ParseOptions, JSON};
+use libfuzzer_sys::fuzz_target;
+
+// Any input, valid or not, must come back as Ok or Err without panicking,
+// in the strict, lenient and multithreaded parsers alike.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(content) = std::str::from_utf8(data) {
+        let _ = JSON::parse_from_string(content, &ParseOptions::default());
+        let lenient = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let _ = JSON::parse_from_string(content, &lenient);
+        let threaded = ParseOptions {
+            threads: 2,
+            ..ParseOptions::default()
+        };
+        let _ = JSON::parse_from_string(content, &threaded);
+    }
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use json_parser::parser::{ParseOptions, JSON};
+use libfuzzer_sys::fuzz_target;
+
+// Whatever parses must print, compact and pretty, to text that parses back
+// to the same value.
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    let options = ParseOptions::default();
+    let Ok(value) = JSON::parse_from_string(content, &options) else {
+        return;
+    };
+
+    for printed in [format!("{}", value), format!("{:#}", value)] {
+        match JSON::parse_from_string(&printed, &options) {
+            Ok(reparsed) => assert_eq!(value, reparsed, "printed as {}", printed),
+            Err(err) => panic!("printed as {}, which fails to parse: {}", printed, err),
+        }
+    }
+});
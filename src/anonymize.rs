@@ -0,0 +1,136 @@
+use crate::parser::JSONValue;
+
+impl JSONValue {
+    /// Replaces the data in a document while keeping its shape, so it can be
+    /// shared without exposing what it holds. Keys, booleans and nulls are
+    /// kept. In strings, letters become random letters of the same case and
+    /// digits random digits, leaving punctuation in place, so an email still
+    /// looks like an email. Numbers keep their sign and number of digits.
+    ///
+    /// Replacements depend only on `seed` and the value replaced: equal
+    /// values stay equal to each other, and the same seed always gives the
+    /// same output.
+    pub fn anonymize(&mut self, seed: u64) {
+        match self {
+            JSONValue::String(text) => *text = anonymize_string(text, seed),
+            JSONValue::Number(val) => *val = anonymize_number(*val, seed),
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(val) => *val = anonymize_wide(&val.to_string(), seed),
+            #[cfg(feature = "i128")]
+            JSONValue::UInt128(val) => *val = anonymize_wide(&val.to_string(), seed),
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(val) => *val = anonymize_decimal(val, seed),
+            JSONValue::Array(values) => {
+                for value in values {
+                    value.anonymize(seed);
+                }
+            }
+            JSONValue::Object(json) => {
+                for value in json.object.values_mut() {
+                    value.anonymize(seed);
+                }
+            }
+            JSONValue::Bool(_) | JSONValue::Null => {}
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::NonFinite(_) => {}
+        }
+    }
+}
+
+fn anonymize_string(text: &str, seed: u64) -> String {
+    let mut rng = Rng::new(seed, text.as_bytes());
+    text.chars()
+        .map(|ch| match ch {
+            'a'..='z' => (b'a' + rng.below(26) as u8) as char,
+            'A'..='Z' => (b'A' + rng.below(26) as u8) as char,
+            '0'..='9' => (b'0' + rng.below(10) as u8) as char,
+            _ if ch.is_alphanumeric() => 'x',
+            _ => ch,
+        })
+        .collect()
+}
+
+fn anonymize_number(val: i32, seed: u64) -> i32 {
+    if val == 0 {
+        return 0;
+    }
+
+    let mut rng = Rng::new(seed, &val.to_le_bytes());
+    let magnitude = val.unsigned_abs();
+    let low = 10u32.pow(magnitude.ilog10());
+    let high = low.saturating_mul(10).min(i32::MAX as u32 + 1);
+    let replaced = (low + rng.below((high - low) as u64) as u32) as i32;
+    if val < 0 {
+        -replaced
+    } else {
+        replaced
+    }
+}
+
+/// Replaces every digit of a decimal, keeping its sign, number of digits
+/// and scale, so `12.50` might become `83.07`.
+#[cfg(feature = "bigdecimal")]
+fn anonymize_decimal(val: &bigdecimal::BigDecimal, seed: u64) -> bigdecimal::BigDecimal {
+    let (digits, scale) = val.as_bigint_and_exponent();
+    let digits = anonymize_digits(&digits.to_string(), val.to_string().as_bytes(), seed);
+    bigdecimal::BigDecimal::new(digits.parse().unwrap_or_default(), scale)
+}
+
+/// Replaces every digit of a nonzero integer written out in `digits`,
+/// keeping its sign and number of digits, with random digits drawn for
+/// `value`.
+#[cfg(any(feature = "bigdecimal", feature = "i128"))]
+fn anonymize_digits(digits: &str, value: &[u8], seed: u64) -> String {
+    if digits == "0" {
+        return digits.to_string();
+    }
+    let mut rng = Rng::new(seed, value);
+    let mut leading = true;
+    digits
+        .chars()
+        .map(|ch| match ch {
+            '0'..='9' if std::mem::take(&mut leading) => (b'1' + rng.below(9) as u8) as char,
+            '0'..='9' => (b'0' + rng.below(10) as u8) as char,
+            _ => ch,
+        })
+        .collect()
+}
+
+/// Replaces the digits of a 128-bit integer. A replacement with as many
+/// digits as the widest values can overflow, and then loses its last digit.
+#[cfg(feature = "i128")]
+fn anonymize_wide<T: std::str::FromStr + Default>(digits: &str, seed: u64) -> T {
+    let replaced = anonymize_digits(digits, digits.as_bytes(), seed);
+    replaced
+        .parse()
+        .or_else(|_| replaced[..replaced.len() - 1].parse())
+        .unwrap_or_default()
+}
+
+/// A xorshift generator seeded from the seed and the value being replaced.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64, bytes: &[u8]) -> Rng {
+        // FNV-1a, which unlike `DefaultHasher` is the same in every build.
+        let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        Rng {
+            // xorshift gets stuck on zero.
+            state: hash.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % n
+    }
+}
@@ -0,0 +1,100 @@
+pub struct Entry {
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+pub fn is_archive(file_name: &str) -> bool {
+    file_name.ends_with(".zip")
+        || file_name.ends_with(".tar")
+        || file_name.ends_with(".tar.gz")
+        || file_name.ends_with(".tgz")
+}
+
+/// Reads every `*.json` entry out of a zip or tar archive, in archive order.
+/// No more than one byte past `limit` is read of each entry, so an entry
+/// over `--max-bytes` is still caught without inflating all of it.
+pub fn read_json_entries(file_name: &str, limit: Option<usize>) -> Result<Vec<Entry>, String> {
+    let file = match std::fs::File::open(file_name) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("{}: {}", file_name, err)),
+    };
+
+    let limit = limit.map_or(u64::MAX, |limit| limit as u64 + 1);
+    let entries = if file_name.ends_with(".zip") {
+        read_zip(file, limit)
+    } else {
+        read_tar(file, file_name.ends_with(".tar"), limit)
+    };
+
+    match entries {
+        Ok(entries) => Ok(entries),
+        Err(err) => Err(format!("{}: {}", file_name, err)),
+    }
+}
+
+#[cfg(feature = "archive")]
+fn read_zip(file: std::fs::File, limit: u64) -> Result<Vec<Entry>, String> {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(file).map_err(|err| err.to_string())?;
+    let mut entries = vec![];
+    for idx in 0..archive.len() {
+        let entry = archive.by_index(idx).map_err(|err| err.to_string())?;
+        if !entry.is_file() || !entry.name().ends_with(".json") {
+            continue;
+        }
+
+        let path = entry.name().to_string();
+        let mut bytes = vec![];
+        entry
+            .take(limit)
+            .read_to_end(&mut bytes)
+            .map_err(|err| err.to_string())?;
+        entries.push(Entry { path, bytes });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(feature = "archive")]
+fn read_tar(file: std::fs::File, uncompressed: bool, limit: u64) -> Result<Vec<Entry>, String> {
+    use std::io::Read;
+
+    let reader: Box<dyn Read> = if uncompressed {
+        Box::new(file)
+    } else {
+        Box::new(flate2::read::GzDecoder::new(file))
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = vec![];
+    for entry in archive.entries().map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let path = match entry.path() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(err) => return Err(err.to_string()),
+        };
+        if !entry.header().entry_type().is_file() || !path.ends_with(".json") {
+            continue;
+        }
+
+        let mut bytes = vec![];
+        entry
+            .take(limit)
+            .read_to_end(&mut bytes)
+            .map_err(|err| err.to_string())?;
+        entries.push(Entry { path, bytes });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(not(feature = "archive"))]
+fn read_zip(_: std::fs::File, _: u64) -> Result<Vec<Entry>, String> {
+    Err(String::from("archive support is not enabled"))
+}
+
+#[cfg(not(feature = "archive"))]
+fn read_tar(_: std::fs::File, _: bool, _: u64) -> Result<Vec<Entry>, String> {
+    Err(String::from("archive support is not enabled"))
+}
@@ -0,0 +1,136 @@
+use crate::utils::Args;
+use json_parser::{
+    input::Input,
+    parser::{ArgsParseError, Parser, JSON},
+    printer::Style,
+};
+use std::time::{Duration, Instant};
+#[cfg(feature = "count-allocations")]
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Wraps the system allocator to count allocations, so `bench` can report
+/// allocations per iteration alongside throughput. Every allocation pays for
+/// the count, so it is only installed with the `count-allocations` feature.
+#[cfg(feature = "count-allocations")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "count-allocations")]
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "count-allocations")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+struct Measurement {
+    elapsed: Duration,
+    /// `None` unless allocations are being counted.
+    allocations: Option<usize>,
+}
+
+impl Measurement {
+    fn report(&self, label: &str, bytes: usize, iterations: usize) {
+        let secs = self.elapsed.as_secs_f64();
+        let throughput = if secs > 0.0 {
+            (bytes * iterations) as f64 / secs / 1_000_000.0
+        } else {
+            f64::INFINITY
+        };
+        let allocations = match self.allocations {
+            Some(allocations) => format!("  {:>8} allocations/iter", allocations / iterations),
+            None => String::new(),
+        };
+        println!(
+            "  {:<10} {:>10.2} MB/s  {:>10.3} ms/iter{}",
+            label,
+            throughput,
+            secs * 1000.0 / iterations as f64,
+            allocations
+        );
+    }
+}
+
+fn measure<F: FnMut()>(iterations: usize, mut f: F) -> Measurement {
+    let allocations = allocations();
+    let started = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    Measurement {
+        elapsed: started.elapsed(),
+        allocations: allocations
+            .zip(self::allocations())
+            .map(|(before, after)| after - before),
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+fn allocations() -> Option<usize> {
+    Some(ALLOCATIONS.load(Ordering::Relaxed))
+}
+
+#[cfg(not(feature = "count-allocations"))]
+fn allocations() -> Option<usize> {
+    None
+}
+
+/// Parses and serializes each file `args.iterations` times and prints the
+/// parse and serialization throughput.
+pub fn run(args: &Args) -> i32 {
+    let mut status_code = 0;
+    for file in &args.files {
+        if let Err(err) = bench_file(file, args) {
+            status_code = 1;
+            eprintln!("{}", err);
+        }
+    }
+    status_code
+}
+
+fn bench_file(file: &str, args: &Args) -> Result<(), ArgsParseError> {
+    let input = Input::from_file(file, args.extension(), args.mmap)?;
+    let content = JSON::decode_input(&input, &args.options)?;
+    let json = match JSON::parse_from_string(&content, &args.options) {
+        Ok(json) => json,
+        Err(err) => return Err(ArgsParseError(format!("{}:{}", file, err))),
+    };
+
+    let style = Style::default();
+    let output_len = json
+        .serialized_len(&style)
+        .map_err(|err| ArgsParseError(format!("{}: {}", file, err)))?;
+    let iterations = args.iterations.max(1);
+
+    let mut parser = Parser::default();
+    let parse = measure(iterations, || {
+        let _ = parser.parse_from_string(&content, &args.options);
+    });
+    let serialize = measure(iterations, || {
+        let _ = json.try_to_styled_string(&style);
+    });
+
+    println!(
+        "{}: {} bytes, {} iterations",
+        file,
+        content.len(),
+        iterations
+    );
+    parse.report("parse", content.len(), iterations);
+    serialize.report("serialize", output_len, iterations);
+    Ok(())
+}
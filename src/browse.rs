@@ -0,0 +1,402 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use json_parser::{
+    parser::JSONValue,
+    path::{Path, Segment},
+};
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+const HINT: &str =
+    "arrows/hjkl move, enter toggles, / searches, n/N next/previous, y copies path, q quits";
+
+/// A value shown on screen, with where it is in the document.
+struct Row<'a> {
+    path: Path,
+    value: &'a JSONValue,
+}
+
+/// The tree being browsed: which containers are open, the rows that makes
+/// visible and where the cursor is among them. Object members are listed by
+/// key, so a document always looks the same and the rows are in the order
+/// their paths sort in.
+struct Browser<'a> {
+    name: &'a str,
+    root: &'a JSONValue,
+    expanded: BTreeSet<Vec<Segment>>,
+    rows: Vec<Row<'a>>,
+    cursor: usize,
+    scroll: usize,
+    /// What is being typed after `/`, until enter or escape.
+    prompt: Option<String>,
+    query: String,
+    /// Shown in place of the cursor's path until the next key.
+    message: Option<String>,
+}
+
+/// Opens `value` in the terminal until the user quits. Only the root starts
+/// open, so large documents show up at once.
+pub fn run(name: &str, value: &JSONValue) -> io::Result<()> {
+    let mut browser = Browser::new(name, value);
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    let _restore = Restore;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    loop {
+        browser.draw(&mut out)?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Release && !browser.handle(key, &mut out)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Gives the terminal back as it was, however `run` ends.
+struct Restore;
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl<'a> Browser<'a> {
+    fn new(name: &'a str, root: &'a JSONValue) -> Browser<'a> {
+        let mut browser = Browser {
+            name,
+            root,
+            expanded: BTreeSet::from([vec![]]),
+            rows: vec![],
+            cursor: 0,
+            scroll: 0,
+            prompt: None,
+            query: String::new(),
+            message: None,
+        };
+        browser.rebuild();
+        browser
+    }
+
+    /// Lists the visible rows again, keeping the cursor on the same value
+    /// or, when that was closed away, on the row now in its place.
+    fn rebuild(&mut self) {
+        let current = self.rows.get(self.cursor).map(|row| row.path.clone());
+        self.rows.clear();
+        let mut rows = std::mem::take(&mut self.rows);
+        self.visible(self.root, &mut Path::default(), &mut rows);
+        self.rows = rows;
+
+        if let Some(current) = current {
+            match self.rows.binary_search_by(|row| row.path.0.cmp(&current.0)) {
+                Ok(idx) => self.cursor = idx,
+                Err(idx) => self.cursor = idx.saturating_sub(1),
+            }
+        }
+        self.cursor = self.cursor.min(self.rows.len() - 1);
+    }
+
+    fn visible(&self, value: &'a JSONValue, path: &mut Path, rows: &mut Vec<Row<'a>>) {
+        rows.push(Row {
+            path: path.clone(),
+            value,
+        });
+        if self.expanded.contains(&path.0) {
+            for (segment, child) in children(value) {
+                path.push(segment);
+                self.visible(child, path, rows);
+                path.pop();
+            }
+        }
+    }
+
+    fn current(&self) -> &Row<'a> {
+        &self.rows[self.cursor]
+    }
+
+    /// Acts on a key press, returning false to quit.
+    fn handle(&mut self, key: KeyEvent, out: &mut impl Write) -> io::Result<bool> {
+        self.message = None;
+        if let Some(prompt) = self.prompt.as_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    self.query = self.prompt.take().unwrap_or_default();
+                    self.search(true);
+                }
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Char(ch) => prompt.push(ch),
+                _ => {}
+            }
+            return Ok(true);
+        }
+
+        let page = terminal::size()?.1.saturating_sub(1).max(1) as usize;
+        let last = self.rows.len() - 1;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.cursor = (self.cursor + 1).min(last),
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(page),
+            KeyCode::PageDown => self.cursor = (self.cursor + page).min(last),
+            KeyCode::Home | KeyCode::Char('g') => self.cursor = 0,
+            KeyCode::End | KeyCode::Char('G') => self.cursor = last,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let path = self.current().path.0.clone();
+                if !self.expanded.remove(&path) && is_container(self.current().value) {
+                    self.expanded.insert(path);
+                }
+                self.rebuild();
+            }
+            KeyCode::Right | KeyCode::Char('l') if is_container(self.current().value) => {
+                let path = self.current().path.0.clone();
+                if self.expanded.insert(path) {
+                    self.rebuild();
+                } else {
+                    self.cursor = (self.cursor + 1).min(last);
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                let mut path = self.current().path.clone();
+                if !self.expanded.remove(&path.0) && path.pop().is_some() {
+                    self.cursor = self.rows[..self.cursor]
+                        .iter()
+                        .rposition(|row| row.path == path)
+                        .unwrap_or(0);
+                }
+                self.rebuild();
+            }
+            KeyCode::Char('/') => self.prompt = Some(String::new()),
+            KeyCode::Char('n') => self.search(true),
+            KeyCode::Char('N') => self.search(false),
+            KeyCode::Char('y') => {
+                let path = jq_path(&self.current().path);
+                write!(out, "\x1b]52;c;{}\x07", base64(path.as_bytes()))?;
+                self.message = Some(format!("copied {}", path));
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Moves to the next value, or with `forward` false the previous one,
+    /// whose key or text contains the query, ignoring case, opening what
+    /// it is inside of. Searches the whole document, wrapping around.
+    fn search(&mut self, forward: bool) {
+        if self.query.is_empty() {
+            return;
+        }
+        let query = self.query.to_lowercase();
+        let mut found = vec![];
+        find(self.root, &mut Path::default(), &query, &mut found);
+
+        let current = &self.current().path.0;
+        let next = if forward {
+            found
+                .iter()
+                .find(|path| &path.0 > current)
+                .or(found.first())
+        } else {
+            found
+                .iter()
+                .rev()
+                .find(|path| &path.0 < current)
+                .or(found.last())
+        };
+        let Some(next) = next.cloned() else {
+            self.message = Some(format!("not found: {}", self.query));
+            return;
+        };
+
+        for len in 0..next.0.len() {
+            self.expanded.insert(next.0[..len].to_vec());
+        }
+        self.rebuild();
+        if let Ok(idx) = self.rows.binary_search_by(|row| row.path.0.cmp(&next.0)) {
+            self.cursor = idx;
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, body) = (width as usize, height.saturating_sub(1).max(1) as usize);
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + body {
+            self.scroll = self.cursor + 1 - body;
+        }
+
+        queue!(out, terminal::Clear(ClearType::All))?;
+        for (line, row) in self.rows.iter().enumerate().skip(self.scroll).take(body) {
+            queue!(out, cursor::MoveTo(0, (line - self.scroll) as u16))?;
+            if line == self.cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            let mut room = width;
+            for (color, text) in describe(row, self.expanded.contains(&row.path.0)) {
+                let text: String = text.chars().take(room).collect();
+                room -= text.chars().count();
+                match color {
+                    Some(color) => queue!(out, SetForegroundColor(color), Print(text), ResetColor)?,
+                    None => queue!(out, Print(text))?,
+                }
+            }
+            queue!(out, Print(" ".repeat(room)), SetAttribute(Attribute::Reset))?;
+        }
+
+        let status = match (&self.prompt, &self.message) {
+            (Some(prompt), _) => format!("/{}", prompt),
+            (None, Some(message)) => message.clone(),
+            (None, None) => format!(
+                "{} {}  {}/{}  {}",
+                self.name,
+                jq_path(&self.current().path),
+                self.cursor + 1,
+                self.rows.len(),
+                HINT
+            ),
+        };
+        let status: String = status.chars().take(width).collect();
+        let room = width - status.chars().count();
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            SetAttribute(Attribute::Reverse),
+            Print(status),
+            Print(" ".repeat(room)),
+            SetAttribute(Attribute::Reset)
+        )?;
+        out.flush()
+    }
+}
+
+/// The members of an object, ordered by key, or the elements of an array.
+fn children(value: &JSONValue) -> Vec<(Segment, &JSONValue)> {
+    match value {
+        JSONValue::Object(json) => {
+            let mut members: Vec<_> = json
+                .iter()
+                .map(|(key, value)| (Segment::Key(key.to_string()), value))
+                .collect();
+            members.sort_by(|(a, _), (b, _)| a.cmp(b));
+            members
+        }
+        JSONValue::Array(values) => values
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (Segment::Index(idx), value))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_container(value: &JSONValue) -> bool {
+    matches!(value, JSONValue::Object(_) | JSONValue::Array(_))
+}
+
+/// Collects, in the order they are listed, the paths of the values whose
+/// key or, for scalars, text contains `query`, which is in lowercase.
+fn find(value: &JSONValue, path: &mut Path, query: &str, found: &mut Vec<Path>) {
+    let key_matches = matches!(
+        path.segments().last(),
+        Some(Segment::Key(key)) if key.to_lowercase().contains(query)
+    );
+    let text_matches = match value {
+        JSONValue::String(text) => text.to_lowercase().contains(query),
+        JSONValue::Object(_) | JSONValue::Array(_) => false,
+        scalar => scalar.to_string().to_lowercase().contains(query),
+    };
+    if key_matches || text_matches {
+        found.push(path.clone());
+    }
+    for (segment, child) in children(value) {
+        path.push(segment);
+        find(child, path, query, found);
+        path.pop();
+    }
+}
+
+/// A row as colored pieces of text: indentation, a marker for containers,
+/// the key or index and the value, which for containers is a summary.
+fn describe(row: &Row, expanded: bool) -> Vec<(Option<Color>, String)> {
+    let depth = row.path.segments().len();
+    let mut pieces = vec![(None, "  ".repeat(depth))];
+    pieces.push(match row.value {
+        JSONValue::Object(_) | JSONValue::Array(_) if expanded => (None, String::from("▾ ")),
+        JSONValue::Object(_) | JSONValue::Array(_) => (None, String::from("▸ ")),
+        _ => (None, String::from("  ")),
+    });
+    match row.path.segments().last() {
+        Some(Segment::Key(key)) => {
+            pieces.push((Some(Color::Blue), JSONValue::from(key.as_str()).to_string()));
+            pieces.push((None, String::from(": ")));
+        }
+        Some(Segment::Index(idx)) => pieces.push((Some(Color::DarkGrey), format!("{}: ", idx))),
+        None => {}
+    }
+    pieces.push(match row.value {
+        JSONValue::Object(json) => {
+            let count = json.iter().count();
+            let open = if expanded { "{" } else { "{…}" };
+            (None, format!("{} {} {}", open, count, plural(count, "key")))
+        }
+        JSONValue::Array(values) => {
+            let open = if expanded { "[" } else { "[…]" };
+            (
+                None,
+                format!("{} {} {}", open, values.len(), plural(values.len(), "item")),
+            )
+        }
+        JSONValue::String(_) => (Some(Color::Green), row.value.to_string()),
+        JSONValue::Bool(_) | JSONValue::Null => (Some(Color::Magenta), row.value.to_string()),
+        _ => (Some(Color::Yellow), row.value.to_string()),
+    });
+    pieces
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        String::from(noun)
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+/// The path as jq writes it, which `--get` also reads: `.users[3].name`.
+fn jq_path(path: &Path) -> String {
+    format!(".{}", path)
+}
+
+/// Standard base64 with padding, as the OSC 52 clipboard sequence wants.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, &byte)| {
+            bits | (byte as u32) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
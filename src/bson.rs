@@ -0,0 +1,212 @@
+use crate::parser::{JSONValue, JSON};
+
+/// What to do with a root array, which BSON cannot represent as a document.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RootArrayPolicy {
+    /// Encode each element as its own document, one after another, which is
+    /// the layout `mongorestore` and similar tools read. Every element must
+    /// be an object.
+    #[default]
+    Split,
+    /// Encode the array as a single document under the key `items`.
+    Wrap,
+    Reject,
+}
+
+impl RootArrayPolicy {
+    pub fn from_name(name: &str) -> Option<RootArrayPolicy> {
+        match name {
+            "split" => Some(RootArrayPolicy::Split),
+            "wrap" => Some(RootArrayPolicy::Wrap),
+            "reject" => Some(RootArrayPolicy::Reject),
+            _ => None,
+        }
+    }
+}
+
+impl JSON {
+    /// Encodes the object as a BSON document. Members are written in key
+    /// order, so equal objects always encode to the same bytes.
+    pub fn to_bson(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = vec![];
+        write_document(&mut bytes, sorted_members(self))?;
+        Ok(bytes)
+    }
+}
+
+impl JSONValue {
+    /// Encodes a root object or array as BSON, handling arrays as `policy`
+    /// says.
+    pub fn to_bson(&self, policy: RootArrayPolicy) -> Result<Vec<u8>, String> {
+        match (self, policy) {
+            (JSONValue::Object(json), _) => json.to_bson(),
+            (JSONValue::Array(values), RootArrayPolicy::Split) => {
+                let mut bytes = vec![];
+                for (idx, value) in values.iter().enumerate() {
+                    match value {
+                        JSONValue::Object(json) => bytes.extend(json.to_bson()?),
+                        _ => {
+                            return Err(format!(
+                                "element {} of the root array is not an object",
+                                idx
+                            ))
+                        }
+                    }
+                }
+                Ok(bytes)
+            }
+            (JSONValue::Array(_), RootArrayPolicy::Wrap) => {
+                let mut bytes = vec![];
+                write_document(&mut bytes, [("items", self)].into_iter())?;
+                Ok(bytes)
+            }
+            _ => Err(String::from(
+                "BSON documents must have an object at the root",
+            )),
+        }
+    }
+}
+
+fn write_document<'a>(
+    bytes: &mut Vec<u8>,
+    members: impl Iterator<Item = (&'a str, &'a JSONValue)>,
+) -> Result<(), String> {
+    let start = bytes.len();
+    bytes.extend([0; 4]);
+    for (key, value) in members {
+        write_element(bytes, key, value)?;
+    }
+    bytes.push(0);
+
+    let len = (bytes.len() - start) as i32;
+    bytes[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn write_element(bytes: &mut Vec<u8>, key: &str, value: &JSONValue) -> Result<(), String> {
+    let kind = match value {
+        JSONValue::String(_) => 0x02,
+        JSONValue::Object(_) => 0x03,
+        JSONValue::Array(_) => 0x04,
+        JSONValue::Bool(_) => 0x08,
+        JSONValue::Null => 0x0a,
+        JSONValue::Number(_) => 0x10,
+        #[cfg(feature = "i128")]
+        JSONValue::Int128(val) if i64::try_from(*val).is_ok() => 0x12,
+        #[cfg(feature = "i128")]
+        JSONValue::Int128(_) | JSONValue::UInt128(_) => 0x13,
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::Decimal(_) => 0x13,
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::NonFinite(_) => 0x01,
+    };
+    bytes.push(kind);
+    write_cstring(bytes, key)?;
+
+    match value {
+        JSONValue::String(val) => {
+            bytes.extend((val.len() as i32 + 1).to_le_bytes());
+            bytes.extend(val.as_bytes());
+            bytes.push(0);
+        }
+        JSONValue::Object(json) => write_document(bytes, sorted_members(json))?,
+        JSONValue::Array(values) => {
+            let keys: Vec<String> = (0..values.len()).map(|idx| idx.to_string()).collect();
+            write_document(bytes, keys.iter().map(String::as_str).zip(values))?
+        }
+        JSONValue::Bool(val) => bytes.push(*val as u8),
+        JSONValue::Null => {}
+        JSONValue::Number(val) => bytes.extend(val.to_le_bytes()),
+        #[cfg(feature = "i128")]
+        JSONValue::Int128(val) => match i64::try_from(*val) {
+            Ok(val) => bytes.extend(val.to_le_bytes()),
+            Err(_) => bytes.extend(wide_integer(*val < 0, val.unsigned_abs())?),
+        },
+        #[cfg(feature = "i128")]
+        JSONValue::UInt128(val) => bytes.extend(wide_integer(false, *val)?),
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::Decimal(val) => bytes.extend(decimal128(val)?),
+        // A BSON double holds NaN and the infinities as they are.
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::NonFinite(val) => bytes.extend(val.to_le_bytes()),
+    }
+    Ok(())
+}
+
+/// Encodes a decimal as an IEEE 754 decimal128 in the binary integer
+/// decimal encoding BSON uses, which holds up to 34 significant digits.
+#[cfg(feature = "bigdecimal")]
+fn decimal128(val: &bigdecimal::BigDecimal) -> Result<[u8; 16], String> {
+    use bigdecimal::{num_bigint::Sign, ToPrimitive};
+
+    let (mut digits, mut scale) = val.as_bigint_and_exponent();
+    if digits
+        .magnitude()
+        .to_u128()
+        .is_none_or(|coefficient| coefficient > MAX_COEFFICIENT)
+    {
+        (digits, scale) = val.normalized().into_bigint_and_exponent();
+    }
+    digits
+        .magnitude()
+        .to_u128()
+        .and_then(|coefficient| {
+            decimal128_parts(
+                digits.sign() == Sign::Minus,
+                coefficient,
+                scale.checked_neg()?,
+            )
+        })
+        .ok_or_else(|| format!("{} does not fit in a BSON decimal128", val))
+}
+
+/// The largest coefficient a decimal128 holds, 34 nines.
+#[cfg(any(feature = "bigdecimal", feature = "i128"))]
+const MAX_COEFFICIENT: u128 = 10u128.pow(34) - 1;
+
+/// Encodes `coefficient * 10^exponent`, negated if `negative`, as a
+/// decimal128, if it fits.
+#[cfg(any(feature = "bigdecimal", feature = "i128"))]
+fn decimal128_parts(negative: bool, coefficient: u128, exponent: i64) -> Option<[u8; 16]> {
+    const EXPONENT_BIAS: i64 = 6176;
+
+    if coefficient > MAX_COEFFICIENT {
+        return None;
+    }
+    let exponent = exponent
+        .checked_add(EXPONENT_BIAS)
+        .filter(|exponent| (0..=6111 + EXPONENT_BIAS).contains(exponent))?;
+    let sign = u128::from(negative);
+    Some((sign << 127 | (exponent as u128) << 113 | coefficient).to_le_bytes())
+}
+
+/// Encodes an integer too wide for `int64` as a decimal128, if it fits.
+#[cfg(feature = "i128")]
+fn wide_integer(negative: bool, magnitude: u128) -> Result<[u8; 16], String> {
+    decimal128_parts(negative, magnitude, 0).ok_or_else(|| {
+        let sign = if negative { "-" } else { "" };
+        format!("{}{} does not fit in a BSON decimal128", sign, magnitude)
+    })
+}
+
+fn sorted_members(json: &JSON) -> impl Iterator<Item = (&str, &JSONValue)> {
+    let mut members: Vec<_> = json
+        .object
+        .iter()
+        .map(|(key, value)| (&**key, value))
+        .collect();
+    members.sort_by_key(|&(key, _)| key);
+    members.into_iter()
+}
+
+fn write_cstring(bytes: &mut Vec<u8>, key: &str) -> Result<(), String> {
+    if key.contains('\0') {
+        return Err(format!(
+            "key {:?} contains a NUL byte, which BSON cannot encode",
+            key
+        ));
+    }
+    bytes.extend(key.as_bytes());
+    bytes.push(0);
+    Ok(())
+}
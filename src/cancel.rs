@@ -0,0 +1,94 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// Values parsed between two looks at the token and the clock.
+const POLL_INTERVAL: u32 = 1024;
+
+/// Lets another thread abort a parse in progress. Clones share one flag, so
+/// a handler can keep a clone and cancel the parse it handed the original to.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a parse stopped before reaching the end of the document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interrupted {
+    Cancelled,
+    TimedOut,
+}
+
+impl Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interrupted::Cancelled => write!(f, "parse cancelled"),
+            Interrupted::TimedOut => write!(f, "parse timed out"),
+        }
+    }
+}
+
+/// Polls a parse's token and deadline. The parser calls `poll` once per
+/// value, but only every `POLL_INTERVAL`th call reads the clock, so the cost
+/// is negligible when nothing is being watched and small when something is.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Watch {
+    token: Option<CancelToken>,
+    deadline: Option<Instant>,
+    countdown: u32,
+    interrupted: Option<Interrupted>,
+}
+
+impl Watch {
+    pub fn new(token: Option<CancelToken>, deadline: Option<Instant>) -> Watch {
+        Watch {
+            token,
+            deadline,
+            countdown: 0,
+            interrupted: None,
+        }
+    }
+
+    /// Returns false once the parse should stop.
+    pub fn poll(&mut self) -> bool {
+        if self.token.is_none() && self.deadline.is_none() {
+            return true;
+        }
+        if self.countdown > 0 {
+            self.countdown -= 1;
+            return self.interrupted.is_none();
+        }
+
+        self.countdown = POLL_INTERVAL;
+        if self.token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            self.interrupted = Some(Interrupted::Cancelled);
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.interrupted = Some(Interrupted::TimedOut);
+        }
+        self.interrupted.is_none()
+    }
+
+    pub fn interrupted(&self) -> Option<Interrupted> {
+        self.interrupted
+    }
+}
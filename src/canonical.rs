@@ -0,0 +1,61 @@
+use crate::{
+    parser::{JSONValue, JSON},
+    printer::Style,
+};
+
+const NO_CANONICAL_FORM: &str =
+    "NaN, infinite numbers and numbers too large for a double have no canonical form";
+
+impl Style {
+    /// The JSON Canonicalization Scheme (RFC 8785): no whitespace, keys in
+    /// UTF-16 order and numbers written as ECMAScript writes doubles, so
+    /// `1.0`, `1` and `1e0` are all `1`. Strings are already escaped the way
+    /// it asks.
+    pub fn canonical() -> Style {
+        Style {
+            sort_keys: true,
+            ecmascript_numbers: true,
+            ..Style::compact()
+        }
+    }
+}
+
+impl JSONValue {
+    /// The same text for every document with the same content, however it was
+    /// formatted or ordered. Fails on NaN, infinities and numbers beyond the
+    /// range of a double, which the scheme has no form for.
+    pub fn to_canonical_string(&self) -> Result<String, String> {
+        self.try_to_styled_string(&Style::canonical())
+            .map_err(|_| String::from(NO_CANONICAL_FORM))
+    }
+
+    /// The SHA-256 of `to_canonical_string`, in lowercase hex.
+    #[cfg(feature = "hash")]
+    pub fn canonical_hash(&self) -> Result<String, String> {
+        Ok(hex_sha256(&self.to_canonical_string()?))
+    }
+}
+
+impl JSON {
+    pub fn to_canonical_string(&self) -> Result<String, String> {
+        self.try_to_styled_string(&Style::canonical())
+            .map_err(|_| String::from(NO_CANONICAL_FORM))
+    }
+
+    #[cfg(feature = "hash")]
+    pub fn canonical_hash(&self) -> Result<String, String> {
+        Ok(hex_sha256(&self.to_canonical_string()?))
+    }
+}
+
+#[cfg(feature = "hash")]
+fn hex_sha256(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    let mut hex = String::with_capacity(64);
+    for byte in Sha256::digest(text.as_bytes()) {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
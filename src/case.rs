@@ -0,0 +1,116 @@
+use crate::parser::{JSONValue, JSON};
+
+/// A naming convention for object keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCase {
+    /// `userId`
+    Camel,
+    /// `user_id`
+    Snake,
+    /// `user-id`
+    Kebab,
+}
+
+impl KeyCase {
+    pub fn from_name(name: &str) -> Option<KeyCase> {
+        match name {
+            "camel" => Some(KeyCase::Camel),
+            "snake" => Some(KeyCase::Snake),
+            "kebab" => Some(KeyCase::Kebab),
+            _ => None,
+        }
+    }
+
+    /// Rewrites `key` in this convention. Words are split at `_`, `-`,
+    /// spaces and changes of case, keeping acronyms together, so
+    /// `HTTPServer_port` becomes `http_server_port` in snake case. Leading
+    /// underscores, as in `_id`, are kept.
+    pub fn convert(self, key: &str) -> String {
+        let body = key.trim_start_matches('_');
+        let mut converted = key[..key.len() - body.len()].to_string();
+        for (idx, word) in words(body).iter().enumerate() {
+            let word = word.to_lowercase();
+            match self {
+                KeyCase::Camel if idx > 0 => {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        converted.extend(first.to_uppercase());
+                        converted.push_str(chars.as_str());
+                    }
+                }
+                KeyCase::Snake if idx > 0 => {
+                    converted.push('_');
+                    converted.push_str(&word);
+                }
+                KeyCase::Kebab if idx > 0 => {
+                    converted.push('-');
+                    converted.push_str(&word);
+                }
+                _ => converted.push_str(&word),
+            }
+        }
+        converted
+    }
+}
+
+fn words(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut words = vec![];
+    let mut start = None;
+    for (pos, &(idx, ch)) in chars.iter().enumerate() {
+        if matches!(ch, '_' | '-' | ' ') {
+            if let Some(start) = start.take() {
+                words.push(&text[start..idx]);
+            }
+            continue;
+        }
+
+        let prev = pos.checked_sub(1).map(|pos| chars[pos].1);
+        let next = chars.get(pos + 1).map(|&(_, ch)| ch);
+        let boundary = ch.is_uppercase()
+            && prev.is_some_and(|prev| {
+                prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+        match start {
+            Some(word_start) if boundary => {
+                words.push(&text[word_start..idx]);
+                start = Some(idx);
+            }
+            Some(_) => {}
+            None => start = Some(idx),
+        }
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+impl JSON {
+    /// Renames every key, at any depth, to the `case` convention. When two
+    /// keys of one object convert to the same name, only one is kept.
+    pub fn rename_keys(&mut self, case: KeyCase) {
+        let members = std::mem::take(&mut self.object);
+        for (key, mut value) in members {
+            value.rename_keys(case);
+            self.insert(&case.convert(&key), value);
+        }
+    }
+}
+
+impl JSONValue {
+    /// Like `JSON::rename_keys`, for objects anywhere below this value.
+    pub fn rename_keys(&mut self, case: KeyCase) {
+        match self {
+            JSONValue::Object(json) => json.rename_keys(case),
+            JSONValue::Array(values) => {
+                for value in values {
+                    value.rename_keys(case);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{
+    lexer::is_whitespace,
+    parser::{JSONValue, ParseOptions, Parser},
+    stream::{Ignore, StreamError, Validator},
+};
+
+/// What one call to `ChunkedParser::feed` got through.
+pub struct Fed {
+    /// Bytes of the chunk read: up to the end of the document or error, if
+    /// there is one, and otherwise all of them. Bytes past it must be fed
+    /// again.
+    pub consumed: usize,
+    /// The document the chunk completed, or why the one being read failed.
+    pub document: Option<Result<JSONValue, StreamError>>,
+}
+
+/// Parses a stream of concatenated documents that arrives in arbitrary
+/// pieces, as from a socket. Bytes of a document not yet complete are kept
+/// between calls, so the caller never has to find where one message ends or
+/// buffer whole messages itself:
+///
+/// ```no_run
+/// # use json_parser::{chunked::ChunkedParser, parser::ParseOptions};
+/// # use std::io::Read;
+/// # fn serve(mut socket: std::net::TcpStream) -> std::io::Result<()> {
+/// let mut parser = ChunkedParser::new(ParseOptions::default());
+/// let mut buffer = [0; 4096];
+/// loop {
+///     let len = socket.read(&mut buffer)?;
+///     if len == 0 {
+///         break;
+///     }
+///     for document in parser.feed_all(&buffer[..len]) {
+///         match document {
+///             Ok(document) => println!("{:?}", document),
+///             Err(err) => eprintln!("{}", err),
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Each document must be an object or an array; they may be separated by
+/// whitespace or by nothing at all. `ParseOptions::limits` apply to each
+/// document and are enforced while it is still arriving, so a peer cannot
+/// make the parser buffer without bound.
+pub struct ChunkedParser {
+    parser: Parser,
+    options: ParseOptions,
+    validator: Validator,
+    /// The document read so far, from its first byte.
+    pending: Vec<u8>,
+    /// Where `pending` starts in the stream.
+    start: (usize, usize),
+}
+
+impl ChunkedParser {
+    pub fn new(options: ParseOptions) -> ChunkedParser {
+        ChunkedParser {
+            parser: Parser::default(),
+            validator: Validator::with_options(false, &options),
+            options,
+            pending: vec![],
+            start: (1, 1),
+        }
+    }
+
+    /// Reads `chunk` until it completes or breaks a document, or runs out.
+    /// After an error the broken document is dropped and the parser expects
+    /// a new one, though a stream that broke partway rarely recovers.
+    pub fn feed(&mut self, chunk: &[u8]) -> Fed {
+        for (idx, &byte) in chunk.iter().enumerate() {
+            let document = match self.feed_byte(byte) {
+                Ok(None) => continue,
+                Ok(Some(document)) => Ok(document),
+                Err(err) => {
+                    self.reset();
+                    Err(err)
+                }
+            };
+            return Fed {
+                consumed: idx + 1,
+                document: Some(document),
+            };
+        }
+
+        Fed {
+            consumed: chunk.len(),
+            document: None,
+        }
+    }
+
+    /// Reads all of `chunk`, returning every document it completes and every
+    /// error, in stream order.
+    pub fn feed_all(&mut self, mut chunk: &[u8]) -> Vec<Result<JSONValue, StreamError>> {
+        let mut documents = vec![];
+        while !chunk.is_empty() {
+            let fed = self.feed(chunk);
+            documents.extend(fed.document);
+            chunk = &chunk[fed.consumed..];
+        }
+        documents
+    }
+
+    /// Whether a document has been started but not completed, which at the
+    /// end of the stream means it was cut short.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Checks that the stream did not end partway through a document.
+    pub fn finish(&self) -> Result<(), StreamError> {
+        if self.is_pending() {
+            Err(self.error_at(self.start, "unexpected end of input"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Drops the document being read, if any.
+    pub fn reset(&mut self) {
+        self.validator.expect_value();
+        self.pending.clear();
+    }
+
+    fn feed_byte(&mut self, byte: u8) -> Result<Option<JSONValue>, StreamError> {
+        let idle = self.validator.is_idle();
+        self.validator.feed(byte, &mut Ignore)?;
+        if idle && is_whitespace(byte) {
+            return Ok(None);
+        }
+        if self.pending.is_empty() {
+            self.start = self.validator.position();
+        }
+        self.pending.push(byte);
+
+        if let Err(err) = self.options.limits.check_bytes(self.pending.len()) {
+            return Err(self.validator.error(&err.to_string()));
+        }
+
+        if !self.validator.is_complete() {
+            return Ok(None);
+        }
+
+        self.validator.expect_value();
+        let pending = std::mem::take(&mut self.pending);
+        // The validator has already checked that the bytes are UTF-8.
+        let content = String::from_utf8(pending).unwrap_or_default();
+        match self.parser.parse_from_string(&content, &self.options) {
+            Ok(document) => Ok(Some(document)),
+            Err(err) => Err(self.parse_error(&err.0)),
+        }
+    }
+
+    /// Moves a `line:col: message` error from the parser, which counts from
+    /// the start of the document, to where it is in the stream.
+    fn parse_error(&self, err: &str) -> StreamError {
+        let (start_line, start_column) = self.start;
+        let mut parts = err.splitn(3, ':');
+        let line = parts.next().and_then(|line| line.parse::<usize>().ok());
+        let column = parts.next().and_then(|column| column.parse::<usize>().ok());
+        match (line, column, parts.next()) {
+            (Some(1), Some(column), Some(message)) => self.error_at(
+                (start_line, start_column + column - 1),
+                message.trim_start(),
+            ),
+            (Some(line), Some(column), Some(message)) => {
+                self.error_at((start_line + line - 1, column), message.trim_start())
+            }
+            _ => self.error_at(self.start, err),
+        }
+    }
+
+    fn error_at(&self, (line, column): (usize, usize), message: &str) -> StreamError {
+        StreamError {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+}
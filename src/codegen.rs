@@ -0,0 +1,331 @@
+//! Infers TypeScript interfaces from sample documents, and the one-line
+//! signatures printed by `--describe`.
+
+use crate::parser::JSONValue;
+use std::fmt::Write;
+
+/// The inferred type of every value seen at one place in the samples.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// Nothing seen yet, such as the elements of an empty array.
+    Unknown,
+    Null,
+    Bool,
+    Integer,
+    /// Numbers with a fraction, or NaN and infinities; merged with
+    /// `Integer`, it stays `Float`.
+    Float,
+    String,
+    Array(Box<Shape>),
+    Object(Vec<Field>),
+    /// Several of the above, with `Null` kept separately as a flag.
+    Union(Vec<Shape>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    shape: Shape,
+    /// Missing from some of the objects seen.
+    optional: bool,
+}
+
+/// Accumulates samples and renders them as TypeScript interfaces.
+#[derive(Default)]
+pub struct TypeScript {
+    shape: Option<Shape>,
+}
+
+impl TypeScript {
+    pub fn new() -> TypeScript {
+        TypeScript::default()
+    }
+
+    /// Adds a sample; fields missing from some samples become optional.
+    pub fn add_sample(&mut self, value: &JSONValue) {
+        let shape = infer(value);
+        self.shape = Some(match self.shape.take() {
+            Some(existing) => merge(existing, shape),
+            None => shape,
+        });
+    }
+
+    /// Renders the interfaces, the root one named `root_name`.
+    pub fn render(&self, root_name: &str) -> String {
+        let mut output = Output {
+            interfaces: vec![],
+            names: vec![],
+        };
+        let root = self.shape.clone().unwrap_or(Shape::Unknown);
+        let root_type = output.type_of(&root, root_name);
+        if !matches!(root, Shape::Object(_)) {
+            output
+                .interfaces
+                .push(format!("export type {} = {};\n", root_name, root_type));
+        }
+        output.interfaces.join("\n")
+    }
+}
+
+impl JSONValue {
+    /// A compact type signature for the document, such as
+    /// `{ id: int, meta?: { tags: [string] }, name: string | null }`. The
+    /// elements of each array are merged into one type, and keys missing from
+    /// some of the objects merged are marked `?`.
+    pub fn describe(&self) -> String {
+        signature(&infer(self))
+    }
+}
+
+fn signature(shape: &Shape) -> String {
+    match shape {
+        Shape::Unknown => String::from("unknown"),
+        Shape::Null => String::from("null"),
+        Shape::Bool => String::from("bool"),
+        Shape::Integer => String::from("int"),
+        Shape::Float => String::from("float"),
+        Shape::String => String::from("string"),
+        Shape::Array(element) => format!("[{}]", signature(element)),
+        Shape::Union(shapes) => {
+            let mut types: Vec<String> = shapes
+                .iter()
+                .filter(|shape| **shape != Shape::Null)
+                .map(signature)
+                .collect();
+            if shapes.contains(&Shape::Null) {
+                types.push(String::from("null"));
+            }
+            types.join(" | ")
+        }
+        Shape::Object(fields) if fields.is_empty() => String::from("{}"),
+        Shape::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{}{}: {}",
+                        property_name(&field.name),
+                        if field.optional { "?" } else { "" },
+                        signature(&field.shape)
+                    )
+                })
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+fn infer(value: &JSONValue) -> Shape {
+    match value {
+        JSONValue::Null => Shape::Null,
+        JSONValue::Bool(_) => Shape::Bool,
+        JSONValue::Number(_) => Shape::Integer,
+        #[cfg(feature = "i128")]
+        JSONValue::Int128(_) | JSONValue::UInt128(_) => Shape::Integer,
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::Decimal(decimal) if decimal.is_integer() => Shape::Integer,
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::Decimal(_) | JSONValue::NonFinite(_) => Shape::Float,
+        JSONValue::String(_) => Shape::String,
+        JSONValue::Array(values) => Shape::Array(Box::new(
+            values.iter().map(infer).fold(Shape::Unknown, merge),
+        )),
+        JSONValue::Object(json) => {
+            let mut fields: Vec<Field> = json
+                .object
+                .iter()
+                .map(|(key, value)| Field {
+                    name: key.to_string(),
+                    shape: infer(value),
+                    optional: false,
+                })
+                .collect();
+            fields.sort_by(|a, b| a.name.cmp(&b.name));
+            Shape::Object(fields)
+        }
+    }
+}
+
+fn merge(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (Shape::Unknown, shape) | (shape, Shape::Unknown) => shape,
+        (a, b) if a == b => a,
+        (Shape::Integer, Shape::Float) | (Shape::Float, Shape::Integer) => Shape::Float,
+        (Shape::Array(a), Shape::Array(b)) => Shape::Array(Box::new(merge(*a, *b))),
+        (Shape::Object(a), Shape::Object(b)) => Shape::Object(merge_fields(a, b)),
+        (Shape::Union(shapes), shape) | (shape, Shape::Union(shapes)) => {
+            let mut shapes = shapes;
+            add_to_union(&mut shapes, shape);
+            Shape::Union(shapes)
+        }
+        (a, b) => {
+            let mut shapes = vec![a];
+            add_to_union(&mut shapes, b);
+            Shape::Union(shapes)
+        }
+    }
+}
+
+/// Merges a shape into a union, combining it with a member of the same kind
+/// so that a union holds at most one array, one object and one number.
+fn add_to_union(shapes: &mut Vec<Shape>, shape: Shape) {
+    let kind = |shape: &Shape| match shape {
+        Shape::Float => std::mem::discriminant(&Shape::Integer),
+        shape => std::mem::discriminant(shape),
+    };
+    let same_kind = shapes
+        .iter()
+        .position(|existing| kind(existing) == kind(&shape));
+    match (same_kind, shape) {
+        (_, Shape::Union(others)) => {
+            for other in others {
+                add_to_union(shapes, other);
+            }
+        }
+        (Some(idx), shape) => {
+            let existing = std::mem::replace(&mut shapes[idx], Shape::Unknown);
+            shapes[idx] = merge(existing, shape);
+        }
+        (None, shape) => shapes.push(shape),
+    }
+}
+
+fn merge_fields(a: Vec<Field>, b: Vec<Field>) -> Vec<Field> {
+    let mut merged = vec![];
+    let mut b = b;
+    for field in a {
+        match b.iter().position(|other| other.name == field.name) {
+            Some(idx) => {
+                let other = b.remove(idx);
+                merged.push(Field {
+                    name: field.name,
+                    shape: merge(field.shape, other.shape),
+                    optional: field.optional || other.optional,
+                });
+            }
+            None => merged.push(Field {
+                optional: true,
+                ..field
+            }),
+        }
+    }
+    merged.extend(b.into_iter().map(|field| Field {
+        optional: true,
+        ..field
+    }));
+    merged.sort_by(|a, b| a.name.cmp(&b.name));
+    merged
+}
+
+struct Output {
+    interfaces: Vec<String>,
+    names: Vec<String>,
+}
+
+impl Output {
+    /// Returns the TypeScript type for `shape`, emitting an interface named
+    /// after `name` for each object on the way.
+    fn type_of(&mut self, shape: &Shape, name: &str) -> String {
+        match shape {
+            Shape::Unknown => String::from("unknown"),
+            Shape::Null => String::from("null"),
+            Shape::Bool => String::from("boolean"),
+            Shape::Integer | Shape::Float => String::from("number"),
+            Shape::String => String::from("string"),
+            Shape::Array(element) => {
+                let element = self.type_of(element, &singular(name));
+                if element.contains(' ') {
+                    format!("({})[]", element)
+                } else {
+                    format!("{}[]", element)
+                }
+            }
+            Shape::Union(shapes) => {
+                let mut types: Vec<String> = shapes
+                    .iter()
+                    .filter(|shape| **shape != Shape::Null)
+                    .map(|shape| self.type_of(shape, name))
+                    .collect();
+                if shapes.contains(&Shape::Null) {
+                    types.push(String::from("null"));
+                }
+                types.join(" | ")
+            }
+            Shape::Object(fields) => {
+                let name = self.unique_name(name);
+                let mut interface = format!("export interface {} {{\n", name);
+                for field in fields {
+                    let field_type = self.type_of(&field.shape, &pascal_case(&field.name));
+                    let _ = writeln!(
+                        interface,
+                        "  {}{}: {};",
+                        property_name(&field.name),
+                        if field.optional { "?" } else { "" },
+                        field_type
+                    );
+                }
+                interface.push_str("}\n");
+                self.interfaces.push(interface);
+                name
+            }
+        }
+    }
+
+    fn unique_name(&mut self, name: &str) -> String {
+        let mut unique = name.to_string();
+        let mut suffix = 2;
+        while self.names.contains(&unique) {
+            unique = format!("{}{}", name, suffix);
+            suffix += 1;
+        }
+        self.names.push(unique.clone());
+        unique
+    }
+}
+
+fn pascal_case(key: &str) -> String {
+    let mut name = String::new();
+    let mut upper = true;
+    for ch in key.chars() {
+        if ch.is_alphanumeric() {
+            if upper {
+                name.extend(ch.to_uppercase());
+            } else {
+                name.push(ch);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if name.is_empty() || name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+/// Names an array's element type after the array: `users` holds `User`s.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if name.ends_with('s') && !name.ends_with("ss") && name.len() > 1 {
+        name[..name.len() - 1].to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+fn property_name(key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_' || ch == '$')
+        && key
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        JSONValue::String(key.to_string()).to_string()
+    }
+}
@@ -0,0 +1,404 @@
+use crate::{
+    lexer::{is_whitespace, Cursor},
+    parser::{get_line_column, ArgsParseError, JSONValue, JSON},
+};
+use std::fmt::{self, Display, Write};
+
+/// A JSONC document that keeps its comments, for tools that edit config
+/// files written by people. Comments are kept as written, `//` or `/* */`
+/// included, and objects keep their members in source order.
+///
+/// Printing it gives the document back with its comments, indented by two
+/// spaces with one member or element per line. Trailing commas are accepted
+/// but not kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentedDocument {
+    pub root: Commented,
+    /// Comments on the lines after the root.
+    pub end: Vec<String>,
+}
+
+/// A value with the comments around it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Commented {
+    /// Comments on the lines before the value or, in an object, its key.
+    pub before: Vec<String>,
+    pub value: CommentedValue,
+    /// Comments after the value, or the comma after it, on the same line.
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommentedValue {
+    /// A string, number, boolean or null.
+    Scalar(JSONValue),
+    Array {
+        elements: Vec<Commented>,
+        /// Comments after the last element, before the `]`.
+        end: Vec<String>,
+    },
+    Object {
+        members: Vec<(String, Commented)>,
+        /// Comments after the last member, before the `}`.
+        end: Vec<String>,
+    },
+}
+
+impl Default for CommentedValue {
+    fn default() -> Self {
+        CommentedValue::Scalar(JSONValue::Null)
+    }
+}
+
+impl CommentedDocument {
+    pub fn parse(content: &str) -> Result<CommentedDocument, ArgsParseError> {
+        let mut reader = Reader {
+            content,
+            cursor: Cursor::new(content),
+        };
+        reader.document().map_err(|offset| {
+            let (line, column) = get_line_column(content, offset);
+            ArgsParseError(format!("{}:{}: Invalid JSON file!", line, column))
+        })
+    }
+}
+
+impl Commented {
+    /// The value with its comments left out.
+    pub fn to_value(&self) -> JSONValue {
+        match &self.value {
+            CommentedValue::Scalar(value) => value.clone(),
+            CommentedValue::Array { elements, .. } => {
+                JSONValue::Array(elements.iter().map(Commented::to_value).collect())
+            }
+            CommentedValue::Object { members, .. } => {
+                let mut json = JSON::new();
+                for (key, member) in members {
+                    json.insert(key, member.to_value());
+                }
+                JSONValue::Object(json)
+            }
+        }
+    }
+
+    /// The member at `key`, if this is an object that has one.
+    pub fn get(&self, key: &str) -> Option<&Commented> {
+        match &self.value {
+            CommentedValue::Object { members, .. } => members
+                .iter()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Commented> {
+        match &mut self.value {
+            CommentedValue::Object { members, .. } => members
+                .iter_mut()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Sets the member at `key` of an object, keeping the comments of the
+    /// one it replaces or, for a new key, adding it last without any.
+    /// Returns the value replaced. Does nothing unless this is an object.
+    pub fn insert(&mut self, key: &str, value: impl Into<JSONValue>) -> Option<JSONValue> {
+        let value = Commented::from(value.into()).value;
+        if let Some(member) = self.get_mut(key) {
+            let old = member.to_value();
+            member.value = value;
+            return Some(old);
+        }
+        if let CommentedValue::Object { members, .. } = &mut self.value {
+            members.push((
+                key.to_string(),
+                Commented {
+                    value,
+                    ..Commented::default()
+                },
+            ));
+        }
+        None
+    }
+
+    /// Removes the member at `key` of an object, comments and all.
+    pub fn remove(&mut self, key: &str) -> Option<Commented> {
+        match &mut self.value {
+            CommentedValue::Object { members, .. } => {
+                let idx = members.iter().position(|(member, _)| member == key)?;
+                Some(members.remove(idx).1)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A value without comments. Object members come in key order.
+impl From<JSONValue> for Commented {
+    fn from(value: JSONValue) -> Self {
+        let value = match value {
+            JSONValue::Array(values) => CommentedValue::Array {
+                elements: values.into_iter().map(Commented::from).collect(),
+                end: vec![],
+            },
+            JSONValue::Object(json) => {
+                let mut members: Vec<(String, Commented)> = json
+                    .object
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), Commented::from(value)))
+                    .collect();
+                members.sort_by(|(a, _), (b, _)| a.cmp(b));
+                CommentedValue::Object {
+                    members,
+                    end: vec![],
+                }
+            }
+            value => CommentedValue::Scalar(value),
+        };
+        Commented {
+            value,
+            ..Commented::default()
+        }
+    }
+}
+
+const INDENT: usize = 2;
+
+impl Display for CommentedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_comments(f, &self.root.before, 0)?;
+        write_value(f, &self.root.value, 0)?;
+        write_after(f, &self.root.after)?;
+        f.write_char('\n')?;
+        write_comments(f, &self.end, 0)
+    }
+}
+
+/// Writes each comment on a line of its own.
+fn write_comments(f: &mut impl Write, comments: &[String], depth: usize) -> fmt::Result {
+    for comment in comments {
+        writeln!(f, "{:indent$}{}", "", comment, indent = depth * INDENT)?;
+    }
+    Ok(())
+}
+
+fn write_after(f: &mut impl Write, comments: &[String]) -> fmt::Result {
+    for comment in comments {
+        write!(f, " {}", comment)?;
+    }
+    Ok(())
+}
+
+fn write_value(f: &mut impl Write, value: &CommentedValue, depth: usize) -> fmt::Result {
+    let (open, close, end, len) = match value {
+        CommentedValue::Scalar(value) => return write!(f, "{}", value),
+        CommentedValue::Array { elements, end } => ('[', ']', end, elements.len()),
+        CommentedValue::Object { members, end } => ('{', '}', end, members.len()),
+    };
+    if len == 0 && end.is_empty() {
+        return write!(f, "{}{}", open, close);
+    }
+
+    writeln!(f, "{}", open)?;
+    let inner = (depth + 1) * INDENT;
+    for idx in 0..len {
+        let (key, element) = match value {
+            CommentedValue::Array { elements, .. } => (None, &elements[idx]),
+            CommentedValue::Object { members, .. } => (Some(&members[idx].0), &members[idx].1),
+            CommentedValue::Scalar(_) => unreachable!("scalars return above"),
+        };
+        write_comments(f, &element.before, depth + 1)?;
+        write!(f, "{:inner$}", "")?;
+        if let Some(key) = key {
+            write!(f, "{}: ", JSONValue::String(key.clone()))?;
+        }
+        write_value(f, &element.value, depth + 1)?;
+        if idx + 1 < len {
+            f.write_char(',')?;
+        }
+        write_after(f, &element.after)?;
+        f.write_char('\n')?;
+    }
+    write_comments(f, end, depth + 1)?;
+    write!(f, "{:indent$}{}", "", close, indent = depth * INDENT)
+}
+
+struct Reader<'a> {
+    content: &'a str,
+    cursor: Cursor<'a>,
+}
+
+/// A comment and whether a line break came between it and what preceded it.
+type Gap = Vec<(String, bool)>;
+
+impl Reader<'_> {
+    fn document(&mut self) -> Result<CommentedDocument, usize> {
+        let before = self
+            .comments()?
+            .into_iter()
+            .map(|(comment, _)| comment)
+            .collect();
+        if !matches!(self.cursor.peek(), Some(b'{' | b'[')) {
+            return Err(self.cursor.pos);
+        }
+        let value = self.value()?;
+        let (after, end) = split_gap(self.comments()?);
+        if self.cursor.remaining() > 0 {
+            return Err(self.cursor.pos);
+        }
+        Ok(CommentedDocument {
+            root: Commented {
+                before,
+                value,
+                after,
+            },
+            end,
+        })
+    }
+
+    /// Skips whitespace and reads the comments in it.
+    fn comments(&mut self) -> Result<Gap, usize> {
+        let mut gap = vec![];
+        let mut line_break = false;
+        loop {
+            match self.cursor.peek() {
+                Some(b'\n') => {
+                    line_break = true;
+                    self.cursor.next();
+                }
+                Some(byte) if is_whitespace(byte) => {
+                    self.cursor.next();
+                }
+                Some(b'/') => {
+                    let start = self.cursor.pos;
+                    let rest = &self.content[start..];
+                    let len = if rest.starts_with("//") {
+                        rest.find('\n').unwrap_or(rest.len())
+                    } else if let Some(body) = rest.strip_prefix("/*") {
+                        match body.find("*/") {
+                            Some(len) => len + 4,
+                            None => return Err(start),
+                        }
+                    } else {
+                        return Err(start);
+                    };
+                    let comment = rest[..len].trim_end_matches('\r').to_string();
+                    self.cursor.pos += len;
+                    gap.push((comment, line_break));
+                    line_break = false;
+                }
+                _ => return Ok(gap),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<CommentedValue, usize> {
+        let token = self.cursor.peek();
+        if !matches!(token, Some(b'{' | b'[')) {
+            return match JSON::parse_value(&mut self.cursor) {
+                Ok(value) => Ok(CommentedValue::Scalar(value)),
+                Err(_) => Err(self.cursor.pos.saturating_sub(1)),
+            };
+        }
+
+        self.cursor.next();
+        if !self.cursor.enter() {
+            return Err(self.cursor.pos - 1);
+        }
+        let value = if token == Some(b'{') {
+            self.object()
+        } else {
+            self.array()
+        };
+        self.cursor.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<CommentedValue, usize> {
+        let mut elements: Vec<Commented> = vec![];
+        let mut before = comments_only(self.comments()?);
+        loop {
+            if self.cursor.peek() == Some(b']') {
+                self.cursor.next();
+                return Ok(CommentedValue::Array {
+                    elements,
+                    end: before,
+                });
+            }
+            let value = self.value()?;
+            let (after, next) = self.separator(b']')?;
+            elements.push(Commented {
+                before,
+                value,
+                after,
+            });
+            before = next;
+        }
+    }
+
+    fn object(&mut self) -> Result<CommentedValue, usize> {
+        let mut members: Vec<(String, Commented)> = vec![];
+        let mut before = comments_only(self.comments()?);
+        loop {
+            if self.cursor.peek() == Some(b'}') {
+                self.cursor.next();
+                return Ok(CommentedValue::Object {
+                    members,
+                    end: before,
+                });
+            }
+            let key = match JSON::parse_key(&mut self.cursor) {
+                Ok(key) => key.to_string(),
+                Err(_) => return Err(self.cursor.pos.saturating_sub(1)),
+            };
+            // Comments around the colon have nowhere better to go.
+            before.extend(comments_only(self.comments()?));
+            if self.cursor.next() != Some(b':') {
+                return Err(self.cursor.pos.saturating_sub(1));
+            }
+            before.extend(comments_only(self.comments()?));
+            let value = self.value()?;
+            let (after, next) = self.separator(b'}')?;
+            members.push((
+                key,
+                Commented {
+                    before,
+                    value,
+                    after,
+                },
+            ));
+            before = next;
+        }
+    }
+
+    /// Reads what follows an element or member: comments on its line, which
+    /// are its own, then a comma unless `close` comes next, then the
+    /// comments before the next one.
+    fn separator(&mut self, close: u8) -> Result<(Vec<String>, Vec<String>), usize> {
+        let mut gap = self.comments()?;
+        match self.cursor.peek() {
+            Some(b',') => {
+                self.cursor.next();
+                gap.extend(self.comments()?);
+            }
+            Some(byte) if byte == close => {}
+            _ => return Err(self.cursor.pos),
+        }
+        Ok(split_gap(gap))
+    }
+}
+
+/// Splits a gap into the comments on the line it starts on and the rest.
+fn split_gap(gap: Gap) -> (Vec<String>, Vec<String>) {
+    let same_line = gap.iter().take_while(|(_, line_break)| !line_break).count();
+    let mut gap = comments_only(gap);
+    let rest = gap.split_off(same_line);
+    (gap, rest)
+}
+
+fn comments_only(gap: Gap) -> Vec<String> {
+    gap.into_iter().map(|(comment, _)| comment).collect()
+}
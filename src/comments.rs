@@ -0,0 +1,68 @@
+use crate::parser::{get_line_column, strip_trailing_commas, ArgsParseError};
+
+/// Turns JSONC into JSON: removes `//` and `/* */` comments outside strings,
+/// then trailing commas, leaving every other byte as it was. A line holding
+/// nothing but a comment is removed whole, as is the whitespace before a
+/// comment that ends its line.
+pub fn strip_comments(content: &str) -> Result<String, ArgsParseError> {
+    let bytes = content.as_bytes();
+    let mut output = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let byte = bytes[idx];
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            idx += 1;
+            continue;
+        }
+
+        let end = match (byte, bytes.get(idx + 1)) {
+            (b'"', _) => {
+                in_string = true;
+                idx += 1;
+                continue;
+            }
+            (b'/', Some(b'/')) => content[idx..]
+                .find('\n')
+                .map_or(bytes.len(), |len| idx + len),
+            (b'/', Some(b'*')) => match content[idx + 2..].find("*/") {
+                Some(len) => idx + 2 + len + 2,
+                None => {
+                    let (line, column) = get_line_column(content, idx);
+                    return Err(ArgsParseError(format!(
+                        "{}:{}: unterminated comment",
+                        line, column
+                    )));
+                }
+            },
+            _ => {
+                idx += 1;
+                continue;
+            }
+        };
+
+        output.push_str(&content[start..idx]);
+        let rest = &content[end..];
+        let line_ends = rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n");
+        if line_ends {
+            let trimmed = output.trim_end_matches([' ', '\t']).len();
+            output.truncate(trimmed);
+        }
+        start = end;
+        if line_ends && (output.is_empty() || output.ends_with('\n')) {
+            start += rest.find('\n').map_or(0, |len| len + 1);
+        }
+        idx = start;
+    }
+    output.push_str(&content[start..]);
+
+    Ok(strip_trailing_commas(&output))
+}
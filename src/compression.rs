@@ -0,0 +1,101 @@
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_file_name(file_name: &str) -> Compression {
+        if file_name.ends_with(".gz") {
+            Compression::Gzip
+        } else if file_name.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Sniffs the compression format from the leading magic bytes, used for
+    /// stdin where there is no file name to go by.
+    pub fn from_magic(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Wraps `reader` in a streaming decoder, for inputs too large to hold in
+/// memory.
+pub fn reader<'a>(
+    reader: impl Read + 'a,
+    compression: Compression,
+) -> io::Result<Box<dyn Read + 'a>> {
+    match compression {
+        Compression::None => Ok(Box::new(reader)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+        #[allow(unreachable_patterns)]
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "decompression support is not enabled",
+        )),
+    }
+}
+
+/// Decompresses `bytes`, reading at most one byte past `limit` so that
+/// oversized output can be detected without inflating all of it.
+pub fn decompress(
+    bytes: &[u8],
+    compression: Compression,
+    limit: Option<usize>,
+) -> io::Result<Vec<u8>> {
+    let limit = limit.map_or(u64::MAX, |limit| limit as u64 + 1);
+    match compression {
+        Compression::None => Ok(bytes.to_vec()),
+        Compression::Gzip => decompress_gzip(bytes, limit),
+        Compression::Zstd => decompress_zstd(bytes, limit),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn decompress_gzip(bytes: &[u8], limit: u64) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    flate2::read::MultiGzDecoder::new(bytes)
+        .take(limit)
+        .read_to_end(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress_gzip(_: &[u8], _: u64) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "gzip support is not enabled",
+    ))
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(bytes: &[u8], limit: u64) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    zstd::stream::read::Decoder::new(bytes)?
+        .take(limit)
+        .read_to_end(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_: &[u8], _: u64) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "zstd support is not enabled",
+    ))
+}
@@ -0,0 +1,130 @@
+use crate::utils::Args;
+use json_parser::printer::{NonFinitePolicy, Style};
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
+
+const CONFIG_FILE: &str = ".json-parser.toml";
+
+/// Builds the default arguments from `~/.json-parser.toml` and then the
+/// nearest `.json-parser.toml` found walking up from the current directory,
+/// so project settings override personal ones. Command-line flags are applied
+/// on top of the result.
+pub fn load() -> Result<Args, String> {
+    let mut args = Args {
+        style: Style {
+            final_newline: true,
+            ..terminal_style()
+        },
+        ..Args::default()
+    };
+
+    let home = env::var_os("HOME").map(|home| PathBuf::from(home).join(CONFIG_FILE));
+    if let Some(home) = &home {
+        if home.is_file() {
+            apply(&mut args, home)?;
+        }
+    }
+
+    if let Some(project) = find_project_config() {
+        if Some(&project) != home.as_ref() {
+            apply(&mut args, &project)?;
+        }
+    }
+
+    Ok(args)
+}
+
+/// Pretty and colored output for people, as on a terminal; compact output
+/// for programs, as when piped. `NO_COLOR` turns color off either way.
+fn terminal_style() -> Style {
+    if !io::stdout().is_terminal() {
+        return Style {
+            compact: true,
+            ..Style::default()
+        };
+    }
+
+    Style {
+        color: env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        ..Style::default()
+    }
+}
+
+fn find_project_config() -> Option<PathBuf> {
+    let mut dir = env::current_dir().ok()?;
+    loop {
+        let candidate = dir.join(CONFIG_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+fn apply(args: &mut Args, path: &PathBuf) -> Result<(), String> {
+    let name = path.display();
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("{}: {}", name, err)),
+    };
+    let table = match content.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(err) => return Err(format!("{}: {}", name, err.message())),
+    };
+
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("indent", toml::Value::Integer(indent)) if indent >= 0 => {
+                args.style.indent = indent as usize;
+                args.style.compact = false;
+            }
+            ("color", toml::Value::Boolean(color)) => args.style.color = color,
+            ("line_ending", toml::Value::String(ending)) => match ending.as_str() {
+                "lf" => args.style.crlf = false,
+                "crlf" => args.style.crlf = true,
+                _ => return Err(format!("{}: line_ending must be \"lf\" or \"crlf\"", name)),
+            },
+            ("final_newline", toml::Value::Boolean(final_newline)) => {
+                args.style.final_newline = final_newline
+            }
+            ("non_finite", toml::Value::String(policy)) => {
+                match NonFinitePolicy::from_name(&policy) {
+                    Some(policy) => args.style.non_finite = policy,
+                    None => {
+                        return Err(format!(
+                            "{}: non_finite must be \"error\", \"null\", \"string\" or \"literal\"",
+                            name
+                        ))
+                    }
+                }
+            }
+            ("deterministic", toml::Value::Boolean(deterministic)) => {
+                args.deterministic = deterministic
+            }
+            ("require_extension", toml::Value::Boolean(require)) => {
+                args.require_extension = require
+            }
+            ("mode", toml::Value::String(mode)) => match mode.as_str() {
+                "strict" => args.options.lenient = false,
+                "lenient" => args.options.lenient = true,
+                _ => return Err(format!("{}: mode must be \"strict\" or \"lenient\"", name)),
+            },
+            ("ignore", toml::Value::Array(globs)) => {
+                for glob in globs {
+                    match glob {
+                        toml::Value::String(glob) => args.ignore.push(glob),
+                        _ => return Err(format!("{}: ignore must be a list of strings", name)),
+                    }
+                }
+            }
+            (key, _) => return Err(format!("{}: invalid value for `{}`", name, key)),
+        }
+    }
+
+    Ok(())
+}
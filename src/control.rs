@@ -0,0 +1,132 @@
+use crate::{
+    parser::{JSONValue, JSON},
+    path::{Path, Segment},
+};
+use std::fmt::Display;
+
+/// What to do with control characters found in strings and keys, which JSON
+/// allows through escapes such as `\u0000` but many consumers choke on. See
+/// `is_flagged` for which characters count.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ControlPolicy {
+    /// Keeps them.
+    #[default]
+    Preserve,
+    /// Fails the parse at the first one.
+    Reject,
+    /// Drops them from the decoded text.
+    Strip,
+}
+
+/// Whether a policy applies to `ch`: any control character (Unicode category
+/// Cc, U+0000 to U+001F and U+007F to U+009F) other than tab, line feed and
+/// carriage return, which are everyday text.
+pub fn is_flagged(ch: char) -> bool {
+    ch.is_control() && !matches!(ch, '\t' | '\n' | '\r')
+}
+
+impl ControlPolicy {
+    pub fn from_name(name: &str) -> Option<ControlPolicy> {
+        match name {
+            "preserve" => Some(ControlPolicy::Preserve),
+            "reject" => Some(ControlPolicy::Reject),
+            "strip" => Some(ControlPolicy::Strip),
+            _ => None,
+        }
+    }
+}
+
+/// A control character found in a document, located by the path of the
+/// string holding it, or of the member whose key holds it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlChar {
+    pub path: Path,
+    pub ch: char,
+    pub in_key: bool,
+}
+
+impl Display for ControlChar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "<root>" } else { &path };
+        write!(
+            f,
+            "{}: control character U+{:04X} in {}",
+            path,
+            self.ch as u32,
+            if self.in_key { "key" } else { "string" }
+        )
+    }
+}
+
+impl JSONValue {
+    /// Every flagged control character in the document's strings and keys, one entry
+    /// per occurrence, ordered by path.
+    pub fn control_chars(&self) -> Vec<ControlChar> {
+        let mut found = vec![];
+        find_in_value(self, &mut Path::default(), &mut found);
+        found.sort_by_cached_key(|control| control.path.to_string());
+        found
+    }
+
+    /// Removes every flagged control character from the document's strings and keys.
+    pub fn strip_control_chars(&mut self) {
+        match self {
+            JSONValue::String(text) => text.retain(|ch| !is_flagged(ch)),
+            JSONValue::Array(values) => values.iter_mut().for_each(JSONValue::strip_control_chars),
+            JSONValue::Object(json) => json.strip_control_chars(),
+            _ => {}
+        }
+    }
+}
+
+impl JSON {
+    pub fn strip_control_chars(&mut self) {
+        let members = std::mem::take(&mut self.object);
+        for (key, mut value) in members {
+            value.strip_control_chars();
+            if key.chars().any(is_flagged) {
+                let key: String = key.chars().filter(|&ch| !is_flagged(ch)).collect();
+                self.insert(&key, value);
+            } else {
+                self.object.insert(key, value);
+            }
+        }
+    }
+}
+
+fn find_in_value(value: &JSONValue, path: &mut Path, found: &mut Vec<ControlChar>) {
+    match value {
+        JSONValue::String(text) => found.extend(text.chars().filter(|&ch| is_flagged(ch)).map(
+            |ch| ControlChar {
+                path: path.clone(),
+                ch,
+                in_key: false,
+            },
+        )),
+        JSONValue::Array(values) => {
+            for (idx, value) in values.iter().enumerate() {
+                path.push(Segment::Index(idx));
+                find_in_value(value, path, found);
+                path.pop();
+            }
+        }
+        JSONValue::Object(json) => {
+            for (key, value) in &json.object {
+                path.push(Segment::Key(key.to_string()));
+                found.extend(
+                    key.chars()
+                        .filter(|&ch| is_flagged(ch))
+                        .map(|ch| ControlChar {
+                            path: path.clone(),
+                            ch,
+                            in_key: true,
+                        }),
+                );
+                find_in_value(value, path, found);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
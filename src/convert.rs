@@ -0,0 +1,568 @@
+use crate::{
+    parser::{JSONValue, JSON},
+    path::{Path, Segment},
+};
+use std::{collections::HashMap, fmt::Display};
+
+/// Numbers are stored as `i32`, so wider or fractional values cannot be
+/// converted without loss.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberError {
+    OutOfRange { value: String, target: &'static str },
+    NotAnInteger(f64),
+    NotANumber,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberError::OutOfRange { value, target } => {
+                write!(f, "{} does not fit in {}", value, target)
+            }
+            NumberError::NotAnInteger(value) => write!(f, "{} is not an integer", value),
+            NumberError::NotANumber => write!(f, "value is not a number"),
+        }
+    }
+}
+
+impl JSONValue {
+    /// Whether this is a number, in whichever variant holds it.
+    pub fn is_number(&self) -> bool {
+        match self {
+            JSONValue::Number(_) => true,
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(_) | JSONValue::UInt128(_) => true,
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(_) | JSONValue::NonFinite(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Reads a number as `T`, failing with a descriptive error if this is not
+    /// a number or the number does not fit.
+    pub fn as_int_checked<T: TryFrom<i128> + TryFrom<u128>>(&self) -> Result<T, NumberError> {
+        let out_of_range = |value: &dyn Display| NumberError::OutOfRange {
+            value: value.to_string(),
+            target: std::any::type_name::<T>(),
+        };
+        let signed = |value: i128| <T as TryFrom<i128>>::try_from(value).ok();
+        match self {
+            JSONValue::Number(value) => {
+                signed(i128::from(*value)).ok_or_else(|| out_of_range(value))
+            }
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(value) => signed(*value).ok_or_else(|| out_of_range(value)),
+            #[cfg(feature = "i128")]
+            JSONValue::UInt128(value) => {
+                <T as TryFrom<u128>>::try_from(*value).map_err(|_| out_of_range(value))
+            }
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(value) => {
+                use bigdecimal::ToPrimitive;
+                if !value.is_integer() {
+                    return Err(NumberError::NotAnInteger(
+                        value.to_f64().unwrap_or(f64::NAN),
+                    ));
+                }
+                match value.to_i128() {
+                    Some(int) => signed(int),
+                    None => value
+                        .to_u128()
+                        .and_then(|int| <T as TryFrom<u128>>::try_from(int).ok()),
+                }
+                .ok_or_else(|| out_of_range(value))
+            }
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::NonFinite(value) => Err(NumberError::NotAnInteger(*value)),
+            _ => Err(NumberError::NotANumber),
+        }
+    }
+
+    pub fn as_i32_checked(&self) -> Result<i32, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_i64_checked(&self) -> Result<i64, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_u8_checked(&self) -> Result<u8, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_u16_checked(&self) -> Result<u16, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_u32_checked(&self) -> Result<u32, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_u64_checked(&self) -> Result<u64, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_usize_checked(&self) -> Result<usize, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_i128_checked(&self) -> Result<i128, NumberError> {
+        self.as_int_checked()
+    }
+
+    pub fn as_u128_checked(&self) -> Result<u128, NumberError> {
+        self.as_int_checked()
+    }
+}
+
+impl From<&str> for JSONValue {
+    fn from(value: &str) -> Self {
+        JSONValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JSONValue {
+    fn from(value: String) -> Self {
+        JSONValue::String(value)
+    }
+}
+
+impl From<i32> for JSONValue {
+    fn from(value: i32) -> Self {
+        JSONValue::Number(value)
+    }
+}
+
+impl TryFrom<i64> for JSONValue {
+    type Error = NumberError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match i32::try_from(value) {
+            Ok(value) => Ok(JSONValue::Number(value)),
+            #[cfg(feature = "i128")]
+            Err(_) => Ok(JSONValue::Int128(i128::from(value))),
+            #[cfg(not(feature = "i128"))]
+            Err(_) => Err(NumberError::OutOfRange {
+                value: value.to_string(),
+                target: "i32",
+            }),
+        }
+    }
+}
+
+/// Integers that fit an `i32` become a `Number`, as with every other
+/// conversion.
+#[cfg(feature = "i128")]
+impl From<i128> for JSONValue {
+    fn from(value: i128) -> Self {
+        match i32::try_from(value) {
+            Ok(value) => JSONValue::Number(value),
+            Err(_) => JSONValue::Int128(value),
+        }
+    }
+}
+
+#[cfg(feature = "i128")]
+impl From<u128> for JSONValue {
+    fn from(value: u128) -> Self {
+        match i128::try_from(value) {
+            Ok(value) => JSONValue::from(value),
+            Err(_) => JSONValue::UInt128(value),
+        }
+    }
+}
+
+/// With the `bigdecimal` feature, fractions and integers beyond `i32` become
+/// a `Decimal` holding the shortest digits that read back as `value`, so
+/// `0.1` is `0.1` rather than its exact binary expansion, and NaN and the
+/// infinities become a `NonFinite`. Without it they are refused with
+/// `NotAnInteger`, like any other float without an integer form.
+impl TryFrom<f64> for JSONValue {
+    type Error = NumberError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        #[cfg(feature = "bigdecimal")]
+        if !value.is_finite() {
+            return Ok(JSONValue::NonFinite(value));
+        }
+        #[cfg(feature = "bigdecimal")]
+        if value.fract() != 0.0 || value < i32::MIN as f64 || value > i32::MAX as f64 {
+            return match format!("{:e}", value).parse() {
+                Ok(decimal) => Ok(JSONValue::Decimal(decimal)),
+                Err(_) => Err(NumberError::NotAnInteger(value)),
+            };
+        }
+        if !value.is_finite() || value.fract() != 0.0 {
+            return Err(NumberError::NotAnInteger(value));
+        }
+        if value < i32::MIN as f64 || value > i32::MAX as f64 {
+            return Err(NumberError::OutOfRange {
+                value: value.to_string(),
+                target: "i32",
+            });
+        }
+        Ok(JSONValue::Number(value as i32))
+    }
+}
+
+impl From<bool> for JSONValue {
+    fn from(value: bool) -> Self {
+        JSONValue::Bool(value)
+    }
+}
+
+impl<T: Into<JSONValue>> From<Vec<T>> for JSONValue {
+    fn from(values: Vec<T>) -> Self {
+        JSONValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<JSONValue>> From<HashMap<String, T>> for JSONValue {
+    fn from(members: HashMap<String, T>) -> Self {
+        JSONValue::Object(JSON::from(members))
+    }
+}
+
+impl<T: Into<JSONValue>> From<HashMap<String, T>> for JSON {
+    fn from(members: HashMap<String, T>) -> Self {
+        let object = members
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        JSON { object }
+    }
+}
+
+impl From<JSON> for JSONValue {
+    fn from(json: JSON) -> Self {
+        JSONValue::Object(json)
+    }
+}
+
+/// `None` becomes `null`.
+impl<T: Into<JSONValue>> From<Option<T>> for JSONValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => JSONValue::Null,
+        }
+    }
+}
+
+/// Why a value could not be converted by `FromJson` or `ToJson`, and where
+/// in the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertError {
+    pub path: Path,
+    pub message: String,
+}
+
+impl ConvertError {
+    pub fn new(message: impl Into<String>) -> ConvertError {
+        ConvertError {
+            path: Path::default(),
+            message: message.into(),
+        }
+    }
+
+    /// Places the error under `key`, as the error unwinds out of a member.
+    pub fn at_key(mut self, key: &str) -> ConvertError {
+        self.path.0.insert(0, Segment::Key(key.to_string()));
+        self
+    }
+
+    /// Places the error under `idx`, as the error unwinds out of an element.
+    pub fn at_index(mut self, idx: usize) -> ConvertError {
+        self.path.0.insert(0, Segment::Index(idx));
+        self
+    }
+}
+
+impl Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "<root>" } else { &path };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+impl From<NumberError> for ConvertError {
+    fn from(err: NumberError) -> Self {
+        ConvertError::new(err.to_string())
+    }
+}
+
+/// Types that can be read out of a document. With the `derive` feature,
+/// `#[derive(FromJson)]` implements it for structs with named fields.
+pub trait FromJson: Sized {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError>;
+
+    /// The value of a member that is not there at all, if that is allowed.
+    /// Only `Option`s, which become `None`, allow it.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Types that can be written as a document. With the `derive` feature,
+/// `#[derive(ToJson)]` implements it for structs with named fields. It can
+/// fail since numbers must fit in an `i32`.
+pub trait ToJson {
+    fn to_json(&self) -> Result<JSONValue, ConvertError>;
+}
+
+/// Reads member `key` of `json`, for `#[derive(FromJson)]`.
+#[doc(hidden)]
+pub fn read_field<T: FromJson>(json: &JSON, key: &str) -> Result<T, ConvertError> {
+    match json.object.get(key) {
+        Some(value) => T::from_json(value).map_err(|err| err.at_key(key)),
+        None => {
+            T::from_missing().ok_or_else(|| ConvertError::new(format!("missing field `{}`", key)))
+        }
+    }
+}
+
+/// Reads member `key` of `json`, or calls `default` if it is missing or
+/// null, for `#[json(default)]` fields.
+#[doc(hidden)]
+pub fn read_field_or<T: FromJson>(
+    json: &JSON,
+    key: &str,
+    default: impl FnOnce() -> T,
+) -> Result<T, ConvertError> {
+    match json.object.get(key) {
+        None | Some(JSONValue::Null) => Ok(default()),
+        Some(value) => T::from_json(value).map_err(|err| err.at_key(key)),
+    }
+}
+
+/// The object a derived `FromJson` reads its fields from.
+#[doc(hidden)]
+pub fn expect_object<'a>(value: &'a JSONValue, name: &str) -> Result<&'a JSON, ConvertError> {
+    match value {
+        JSONValue::Object(json) => Ok(json),
+        _ => Err(ConvertError::new(format!(
+            "expected an object for {}",
+            name
+        ))),
+    }
+}
+
+fn kind_error(expected: &str) -> ConvertError {
+    ConvertError::new(format!("expected {}", expected))
+}
+
+impl FromJson for JSONValue {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for JSON {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        value
+            .as_object()
+            .cloned()
+            .ok_or_else(|| kind_error("an object"))
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        match value {
+            JSONValue::Bool(value) => Ok(*value),
+            _ => Err(kind_error("a boolean")),
+        }
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        match value {
+            JSONValue::String(value) => Ok(value.clone()),
+            _ => Err(kind_error("a string")),
+        }
+    }
+}
+
+macro_rules! int_conversions {
+    ($($int:ty),*) => {
+        $(
+            impl FromJson for $int {
+                fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+                    Ok(value.as_int_checked()?)
+                }
+            }
+
+            impl ToJson for $int {
+                fn to_json(&self) -> Result<JSONValue, ConvertError> {
+                    match i32::try_from(*self) {
+                        Ok(value) => Ok(JSONValue::Number(value)),
+                        #[cfg(feature = "i128")]
+                        Err(_) => Ok(match i128::try_from(*self) {
+                            Ok(value) => JSONValue::Int128(value),
+                            Err(_) => JSONValue::UInt128(*self as u128),
+                        }),
+                        #[cfg(not(feature = "i128"))]
+                        Err(_) => Err(NumberError::OutOfRange {
+                            value: self.to_string(),
+                            target: "i32",
+                        }
+                        .into()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+int_conversions!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl FromJson for f64 {
+    #[cfg(not(feature = "bigdecimal"))]
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        Ok(f64::from(value.as_i32_checked()?))
+    }
+
+    /// Any number, rounded to the nearest `f64` as Rust reads its digits.
+    #[cfg(feature = "bigdecimal")]
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        if let JSONValue::NonFinite(float) = value {
+            return Ok(*float);
+        }
+        let float = value
+            .as_decimal()
+            .and_then(|decimal| decimal.to_string().parse().ok());
+        match float {
+            Some(float) => Ok(float),
+            None => Err(NumberError::NotANumber.into()),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        match value {
+            JSONValue::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        let values = value.as_array().ok_or_else(|| kind_error("an array"))?;
+        values
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| T::from_json(value).map_err(|err| err.at_index(idx)))
+            .collect()
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(value: &JSONValue) -> Result<Self, ConvertError> {
+        let json = value.as_object().ok_or_else(|| kind_error("an object"))?;
+        json.object
+            .iter()
+            .map(|(key, value)| match T::from_json(value) {
+                Ok(value) => Ok((key.to_string(), value)),
+                Err(err) => Err(err.at_key(key)),
+            })
+            .collect()
+    }
+}
+
+impl ToJson for JSONValue {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        Ok(self.clone())
+    }
+}
+
+impl ToJson for JSON {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        Ok(JSONValue::Object(self.clone()))
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        Ok(JSONValue::Bool(*self))
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        Ok(JSONValue::String(self.to_string()))
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        self.as_str().to_json()
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        Ok(JSONValue::try_from(*self)?)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        (**self).to_json()
+    }
+}
+
+/// `None` becomes `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        match self {
+            Some(value) => value.to_json(),
+            None => Ok(JSONValue::Null),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        self.iter()
+            .enumerate()
+            .map(|(idx, value)| value.to_json().map_err(|err| err.at_index(idx)))
+            .collect::<Result<_, _>>()
+            .map(JSONValue::Array)
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for HashMap<String, T> {
+    fn to_json(&self) -> Result<JSONValue, ConvertError> {
+        let mut json = JSON::new();
+        for (key, value) in self {
+            json.insert(key, value.to_json().map_err(|err| err.at_key(key))?);
+        }
+        Ok(JSONValue::Object(json))
+    }
+}
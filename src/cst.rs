@@ -0,0 +1,199 @@
+use crate::{
+    lazy::Lazy,
+    parser::{get_line_column, ArgsParseError, JSONValue, JSON},
+    path::{Path, Segment},
+    printer::Style,
+    stream::validate_syntax,
+};
+use std::fmt::Display;
+
+/// A document kept exactly as written, for programs that edit files under
+/// version control. Whitespace, member order, repeated keys and the way each
+/// number is spelled all survive, since edits splice new text over the span
+/// of the value they change and leave every other byte alone: setting one
+/// value makes a one-line diff.
+///
+/// Values are found with `Lazy`, so each edit rescans only the containers on
+/// the way to it. New values are written compactly in a document that fits
+/// on one line, and otherwise as `{:#}` writes them, indented to match the
+/// document and the line they go on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    text: String,
+}
+
+impl Cst {
+    /// Checks that `text` is well-formed. Numbers of any size or precision
+    /// are accepted, as they are never converted.
+    pub fn parse(text: impl Into<String>) -> Result<Cst, ArgsParseError> {
+        let text = text.into();
+        match validate_syntax(text.as_bytes()) {
+            Ok(_) => Ok(Cst { text }),
+            Err(err) => Err(ArgsParseError(err.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// The source text of the value at `path`.
+    pub fn raw(&self, path: &Path) -> Option<&str> {
+        let value = Lazy::new(&self.text).at(path).ok()??;
+        Some(value.raw())
+    }
+
+    /// The value at `path`, if there is one and it can be parsed.
+    pub fn get(&self, path: &Path) -> Option<JSONValue> {
+        let value = Lazy::new(&self.text).at(path).ok()??;
+        value.value().ok().cloned()
+    }
+
+    /// Replaces the value at `path`, or adds it as the last member of its
+    /// object or, when `path` ends one past the last index, the last element
+    /// of its array.
+    pub fn set(&mut self, path: &Path, value: impl Into<JSONValue>) -> Result<(), ArgsParseError> {
+        let value = value.into();
+        let root = Lazy::new(&self.text);
+        if let Some(target) = root.at(path).map_err(|offset| self.error(offset))? {
+            let (start, end) = (target.offset(), target.end());
+            let rendered = self.render(&value, start)?;
+            self.text.replace_range(start..end, &rendered);
+            return Ok(());
+        }
+
+        let mut parent_path = path.clone();
+        let last = parent_path.pop();
+        let parent = match root.at(&parent_path).map_err(|offset| self.error(offset))? {
+            Some(parent) => parent,
+            None => return Err(ArgsParseError(format!("no value at `{}`", parent_path))),
+        };
+        let children = parent.children().map_err(|offset| self.error(offset))?;
+        let key = match (last, parent.raw().as_bytes().first()) {
+            (Some(Segment::Key(key)), Some(b'{')) => Some(key),
+            (Some(Segment::Index(idx)), Some(b'[')) if idx == children.len() => None,
+            _ => return Err(ArgsParseError(format!("no value at `{}`", path))),
+        };
+
+        let (start, end) = (parent.offset(), parent.end());
+        let (at, text) = match children.last() {
+            None => {
+                let container = match key {
+                    Some(key) => {
+                        let mut json = JSON::new();
+                        json.insert(&key, value);
+                        JSONValue::Object(json)
+                    }
+                    None => JSONValue::Array(vec![value]),
+                };
+                let rendered = self.render(&container, start)?;
+                self.text.replace_range(start..end, &rendered);
+                return Ok(());
+            }
+            Some(last) => {
+                // What separates the last two members, or the first member
+                // from the bracket, is reused before the new one.
+                let gap = match children.len() {
+                    1 => format!(",{}", &self.text[start + 1..last.entry]),
+                    len => self.text[children[len - 2].end..last.entry].to_string(),
+                };
+                let rendered = self.render(&value, last.start)?;
+                let text = match key {
+                    Some(key) => {
+                        let head = &self.text[last.entry..last.start];
+                        let colon = head.rfind('"').map_or(": ", |idx| &head[idx + 1..]);
+                        format!("{}{}{}{}", gap, JSONValue::String(key), colon, rendered)
+                    }
+                    None => format!("{}{}", gap, rendered),
+                };
+                (last.end, text)
+            }
+        };
+        self.text.insert_str(at, &text);
+        Ok(())
+    }
+
+    /// Removes the value at `path` with its key and one of the commas beside
+    /// it, returning whether there was one.
+    pub fn remove(&mut self, path: &Path) -> Result<bool, ArgsParseError> {
+        let mut parent_path = path.clone();
+        let last = match parent_path.pop() {
+            Some(last) => last,
+            None => return Err(ArgsParseError(String::from("cannot remove the root"))),
+        };
+        let root = Lazy::new(&self.text);
+        let parent = match root.at(&parent_path).map_err(|offset| self.error(offset))? {
+            Some(parent) => parent,
+            None => return Ok(false),
+        };
+        let children = parent.children().map_err(|offset| self.error(offset))?;
+        let idx = match (&last, parent.raw().as_bytes().first()) {
+            (Segment::Key(key), Some(b'{')) => children
+                .iter()
+                .rposition(|member| member.key.as_deref() == Some(key.as_str())),
+            (Segment::Index(idx), Some(b'[')) => Some(*idx).filter(|&idx| idx < children.len()),
+            _ => None,
+        };
+        let idx = match idx {
+            Some(idx) => idx,
+            None => return Ok(false),
+        };
+
+        let range = if children.len() == 1 {
+            parent.offset() + 1..parent.end() - 1
+        } else if idx + 1 < children.len() {
+            children[idx].entry..children[idx + 1].entry
+        } else {
+            children[idx - 1].end..children[idx].end
+        };
+        self.text.replace_range(range, "");
+        Ok(true)
+    }
+
+    /// Writes `value` to go at byte `at`. NaN and infinities are refused,
+    /// since no document can hold them.
+    fn render(&self, value: &JSONValue, at: usize) -> Result<String, ArgsParseError> {
+        if !matches!(value, JSONValue::Array(_) | JSONValue::Object(_)) || !self.text.contains('\n')
+        {
+            return value
+                .try_to_styled_string(&Style::compact())
+                .map_err(ArgsParseError);
+        }
+
+        let line = self.text[..at].rfind('\n').map_or(0, |idx| idx + 1);
+        let indent: String = self.text[line..]
+            .chars()
+            .take_while(|&ch| ch == ' ' || ch == '\t')
+            .collect();
+        let style = Style {
+            indent: self.indent_width(),
+            ..Style::default()
+        };
+        let text = value.try_to_styled_string(&style).map_err(ArgsParseError)?;
+        Ok(text.replace('\n', &format!("\n{}", indent)))
+    }
+
+    /// The indentation of the first indented line, taken to be one level.
+    fn indent_width(&self) -> usize {
+        self.text
+            .lines()
+            .map(|line| line.len() - line.trim_start_matches(' ').len())
+            .find(|&width| width > 0)
+            .unwrap_or(Style::default().indent)
+    }
+
+    fn error(&self, offset: usize) -> ArgsParseError {
+        let (line, column) = get_line_column(&self.text, offset);
+        ArgsParseError(format!("{}:{}: Invalid JSON file!", line, column))
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
@@ -0,0 +1,10 @@
+use crate::{formats::StringFormat, parser::JSONValue, path::Path, redact::KeyPattern};
+
+impl JSONValue {
+    /// The strings under a key matching `keys`, at any depth, that are not
+    /// RFC 3339 timestamps as `jtd::is_rfc3339` checks them, with their
+    /// paths, as `invalid_formats` finds them.
+    pub fn invalid_dates(&self, keys: &KeyPattern) -> Vec<(Path, &str)> {
+        self.invalid_formats(keys, StringFormat::DateTime)
+    }
+}
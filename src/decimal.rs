@@ -0,0 +1,23 @@
+use crate::parser::JSONValue;
+pub use bigdecimal::BigDecimal;
+
+impl JSONValue {
+    /// Any number as an exact decimal.
+    pub fn as_decimal(&self) -> Option<BigDecimal> {
+        match self {
+            JSONValue::Number(val) => Some(BigDecimal::from(*val)),
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(val) => Some(BigDecimal::from(*val)),
+            #[cfg(feature = "i128")]
+            JSONValue::UInt128(val) => Some(BigDecimal::from(*val)),
+            JSONValue::Decimal(val) => Some(val.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl From<BigDecimal> for JSONValue {
+    fn from(value: BigDecimal) -> Self {
+        JSONValue::Decimal(value)
+    }
+}
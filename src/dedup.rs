@@ -0,0 +1,305 @@
+use crate::parser::{ArgsParseError, JSONValue, Key, ParseOptions, Parser, JSON};
+use std::{collections::HashMap, mem::size_of, sync::Arc};
+
+/// Repeated subtrees found in a document, and how much memory sharing them
+/// saves.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DedupStats {
+    /// Objects and arrays in the document.
+    pub subtrees: usize,
+    /// Objects and arrays identical to one seen earlier, not counting those
+    /// nested inside another repeat.
+    pub duplicates: usize,
+    /// Estimated bytes held by those repeats.
+    pub duplicate_bytes: usize,
+}
+
+/// A document in which identical objects and arrays, wherever they appear,
+/// are one allocation, as made by `dedup`. A payload repeating the same
+/// nested object thousands of times holds it once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    /// Any value but an object or array.
+    Scalar(JSONValue),
+    Array(Arc<[SharedValue]>),
+    /// Members in key order.
+    Object(Arc<[(Key, SharedValue)]>),
+}
+
+impl SharedValue {
+    pub fn get(&self, key: &str) -> Option<&SharedValue> {
+        match self {
+            SharedValue::Object(members) => members
+                .binary_search_by(|(member, _)| (**member).cmp(key))
+                .ok()
+                .map(|idx| &members[idx].1),
+            _ => None,
+        }
+    }
+
+    pub fn index(&self, idx: usize) -> Option<&SharedValue> {
+        match self {
+            SharedValue::Array(values) => values.get(idx),
+            _ => None,
+        }
+    }
+
+    /// Whether both are the same allocation rather than merely equal.
+    pub fn ptr_eq(&self, other: &SharedValue) -> bool {
+        match (self, other) {
+            (SharedValue::Array(a), SharedValue::Array(b)) => Arc::ptr_eq(a, b),
+            (SharedValue::Object(a), SharedValue::Object(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Copies the document out into values of its own, unsharing it.
+    pub fn to_value(&self) -> JSONValue {
+        match self {
+            SharedValue::Scalar(value) => value.clone(),
+            SharedValue::Array(values) => {
+                JSONValue::Array(values.iter().map(SharedValue::to_value).collect())
+            }
+            SharedValue::Object(members) => {
+                let mut json = JSON::new();
+                for (key, value) in members.iter() {
+                    json.object.insert(key.clone(), value.to_value());
+                }
+                JSONValue::Object(json)
+            }
+        }
+    }
+}
+
+impl Parser {
+    /// Parses a document as `parse_from_string` does and shares its repeated
+    /// subtrees. The document is parsed whole before it is shared, so only
+    /// the memory it is kept in afterwards shrinks.
+    pub fn parse_shared(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<(SharedValue, DedupStats), ArgsParseError> {
+        self.parse_from_string(content, options)
+            .map(JSONValue::dedup)
+    }
+}
+
+/// A subtree with its children replaced by the ids of their own shapes, so
+/// identical subtrees get the same id without comparing them in full.
+#[derive(PartialEq, Eq, Hash)]
+enum Shape {
+    Scalar(String),
+    Array(Vec<usize>),
+    Object(Vec<(String, usize)>),
+}
+
+struct Node {
+    id: usize,
+    /// Values in this subtree, itself included.
+    len: usize,
+    bytes: usize,
+    container: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Value(&'a JSONValue),
+    Root(&'a JSON),
+}
+
+impl JSONValue {
+    pub fn dedup_stats(&self) -> DedupStats {
+        dedup_stats(Value::Value(self))
+    }
+
+    /// Shares every object and array identical to one before it, with the
+    /// same stats `dedup_stats` gives.
+    pub fn dedup(self) -> (SharedValue, DedupStats) {
+        let mut sharer = Sharer::default();
+        let value = sharer.share(self).value;
+        (value, sharer.stats)
+    }
+}
+
+impl JSON {
+    pub fn dedup_stats(&self) -> DedupStats {
+        dedup_stats(Value::Root(self))
+    }
+
+    pub fn dedup(self) -> (SharedValue, DedupStats) {
+        JSONValue::Object(self).dedup()
+    }
+
+    /// Parses a single document with a fresh `Parser`, sharing its repeated
+    /// subtrees.
+    pub fn parse_shared(
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<(SharedValue, DedupStats), ArgsParseError> {
+        Parser::default().parse_shared(content, options)
+    }
+}
+
+/// Shares subtrees bottom-up: children are shared before their parent, so
+/// identical subtrees have identical children and are found by the ids of
+/// those alone.
+#[derive(Default)]
+struct Sharer {
+    ids: HashMap<Shape, usize>,
+    /// The shared value of each id, for objects and arrays.
+    shared: Vec<Option<SharedValue>>,
+    stats: DedupStats,
+}
+
+/// A shared value with its shape's id and estimated bytes.
+struct Shared {
+    value: SharedValue,
+    id: usize,
+    bytes: usize,
+}
+
+impl Sharer {
+    fn share(&mut self, value: JSONValue) -> Shared {
+        // Repeats nested inside a repeat are not counted on their own.
+        let before = self.stats;
+        let mut bytes = size_of::<JSONValue>();
+        let (shape, value) = match value {
+            JSONValue::Array(values) => {
+                self.stats.subtrees += 1;
+                let mut ids = Vec::with_capacity(values.len());
+                let mut shared = Vec::with_capacity(values.len());
+                for value in values {
+                    let child = self.share(value);
+                    bytes += child.bytes;
+                    ids.push(child.id);
+                    shared.push(child.value);
+                }
+                (Shape::Array(ids), SharedValue::Array(shared.into()))
+            }
+            JSONValue::Object(json) => {
+                self.stats.subtrees += 1;
+                let mut members: Vec<(Key, JSONValue)> = json.object.into_iter().collect();
+                members.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let mut ids = Vec::with_capacity(members.len());
+                let mut shared = Vec::with_capacity(members.len());
+                for (key, value) in members {
+                    let child = self.share(value);
+                    bytes += child.bytes + key.len();
+                    ids.push((key.to_string(), child.id));
+                    shared.push((key, child.value));
+                }
+                (Shape::Object(ids), SharedValue::Object(shared.into()))
+            }
+            JSONValue::String(val) => {
+                bytes += val.len();
+                let shape = Shape::Scalar(format!("\"{}", val));
+                (shape, SharedValue::Scalar(JSONValue::String(val)))
+            }
+            value => (Shape::Scalar(value.to_string()), SharedValue::Scalar(value)),
+        };
+
+        let container = !matches!(value, SharedValue::Scalar(_));
+        let next_id = self.ids.len();
+        let id = *self.ids.entry(shape).or_insert(next_id);
+        let value = match &self.shared.get(id) {
+            Some(Some(shared)) => {
+                self.stats.duplicates = before.duplicates + 1;
+                self.stats.duplicate_bytes = before.duplicate_bytes + bytes;
+                shared.clone()
+            }
+            Some(None) => value,
+            None => {
+                self.shared.push(container.then(|| value.clone()));
+                value
+            }
+        };
+        Shared { value, id, bytes }
+    }
+}
+
+fn dedup_stats(root: Value) -> DedupStats {
+    let mut shapes = HashMap::new();
+    let mut nodes = vec![];
+    number(root, &mut shapes, &mut nodes);
+
+    let mut stats = DedupStats::default();
+    let mut seen = vec![false; shapes.len()];
+    let mut idx = 0;
+    while idx < nodes.len() {
+        let node = &nodes[idx];
+        if !node.container {
+            idx += 1;
+            continue;
+        }
+
+        stats.subtrees += 1;
+        if seen[node.id] {
+            stats.duplicates += 1;
+            stats.duplicate_bytes += node.bytes;
+            stats.subtrees += nodes[idx + 1..idx + node.len]
+                .iter()
+                .filter(|node| node.container)
+                .count();
+            idx += node.len;
+        } else {
+            seen[node.id] = true;
+            idx += 1;
+        }
+    }
+    stats
+}
+
+/// Records every value in pre-order, returning the index of `value`'s node.
+fn number(value: Value, shapes: &mut HashMap<Shape, usize>, nodes: &mut Vec<Node>) -> usize {
+    let idx = nodes.len();
+    nodes.push(Node {
+        id: 0,
+        len: 1,
+        bytes: size_of::<JSONValue>(),
+        container: false,
+    });
+
+    let mut children_bytes = 0;
+    let shape = match value {
+        Value::Value(JSONValue::Array(values)) => {
+            let ids = values
+                .iter()
+                .map(|value| {
+                    let child = number(Value::Value(value), shapes, nodes);
+                    children_bytes += nodes[child].bytes;
+                    nodes[child].id
+                })
+                .collect();
+            Shape::Array(ids)
+        }
+        Value::Root(json) | Value::Value(JSONValue::Object(json)) => {
+            let mut members: Vec<(String, usize)> = json
+                .object
+                .iter()
+                .map(|(key, value)| {
+                    let child = number(Value::Value(value), shapes, nodes);
+                    children_bytes += nodes[child].bytes + key.len();
+                    (key.to_string(), nodes[child].id)
+                })
+                .collect();
+            members.sort();
+            Shape::Object(members)
+        }
+        Value::Value(JSONValue::String(val)) => {
+            children_bytes += val.len();
+            Shape::Scalar(format!("\"{}", val))
+        }
+        Value::Value(value) => Shape::Scalar(value.to_string()),
+    };
+
+    let next_id = shapes.len();
+    let container = matches!(shape, Shape::Array(_) | Shape::Object(_));
+    nodes[idx] = Node {
+        id: *shapes.entry(shape).or_insert(next_id),
+        len: nodes.len() - idx,
+        bytes: size_of::<JSONValue>() + children_bytes,
+        container,
+    };
+    idx
+}
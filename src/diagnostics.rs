@@ -0,0 +1,309 @@
+use crate::parser::{JSONValue, JSON};
+
+/// One problem found in an input, read back from the error text the parser
+/// and checks report, `name:line:col: message` or `name: message`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 1-based line and column, in characters, when the error has them.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+/// LSP's `DiagnosticSeverity.Error`.
+const SEVERITY_ERROR: i32 = 1;
+
+impl Diagnostic {
+    /// Splits the errors reported for the input `name`, one per line.
+    pub fn from_errors(name: &str, errors: &str) -> Vec<Diagnostic> {
+        errors
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Diagnostic::from_error(name, line))
+            .collect()
+    }
+
+    fn from_error(name: &str, error: &str) -> Diagnostic {
+        let rest = error.strip_prefix(name).unwrap_or(error);
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        let mut parts = rest.splitn(3, ':');
+        let line = parts.next().and_then(|line| line.parse().ok());
+        let column = parts.next().and_then(|column| column.parse().ok());
+        match (line, column, parts.next()) {
+            (Some(line), Some(column), Some(message)) => Diagnostic {
+                position: Some((line, column)),
+                message: message.trim_start().to_string(),
+            },
+            _ => Diagnostic {
+                position: None,
+                message: rest.trim_start().to_string(),
+            },
+        }
+    }
+
+    /// A stable name for the kind of problem, for editors to filter on.
+    pub fn code(&self) -> &'static str {
+        let message = self.message.as_str();
+        if message.starts_with("limit exceeded") {
+            "limit"
+        } else if message.starts_with("nesting is deeper") {
+            "depth"
+        } else if message.contains("control character") {
+            "control-character"
+        } else if message.contains("UTF-8") {
+            "encoding"
+        } else if message.contains("does not satisfy") {
+            "schema"
+        } else if message.contains("not an RFC 3339 timestamp") {
+            "timestamp"
+        } else if message.contains("does not match format") {
+            "format"
+        } else if message.contains("repeated on lines") {
+            "duplicate-key"
+        } else if message.contains("${") || message.contains("is not set") {
+            "substitution"
+        } else if message.contains("Invalid JSON")
+            || message.contains("unexpected")
+            || message.contains("unterminated")
+        {
+            "syntax"
+        } else {
+            "error"
+        }
+    }
+
+    /// The diagnostic as an LSP `Diagnostic`. Positions are 0-based and,
+    /// when the input's `text` is given, counted in UTF-16 code units as
+    /// LSP expects; otherwise in characters. Errors without a position are
+    /// put at the start of the input.
+    pub fn to_lsp(&self, text: Option<&str>) -> JSONValue {
+        let (line, start, end) = match self.position {
+            Some((line, column)) => {
+                let line_text = text.and_then(|text| text.lines().nth(line.saturating_sub(1)));
+                let start = utf16_column(line_text, column.saturating_sub(1));
+                (
+                    line.saturating_sub(1),
+                    start,
+                    utf16_column(line_text, column).max(start + 1),
+                )
+            }
+            None => (0, 0, 0),
+        };
+
+        let mut diagnostic = JSON::new();
+        diagnostic.insert("range", range(line, start, end));
+        diagnostic.insert("severity", SEVERITY_ERROR);
+        diagnostic.insert("code", self.code());
+        diagnostic.insert("source", "json-parser");
+        diagnostic.insert("message", self.message.as_str());
+        JSONValue::Object(diagnostic)
+    }
+}
+
+impl Diagnostic {
+    /// The diagnostic as a GitHub Actions `::error` workflow command, which
+    /// shows it inline on the lines of a pull request that `file` names.
+    pub fn to_github(&self, file: &str) -> String {
+        let mut properties = format!("file={}", escape_property(file));
+        if let Some((line, column)) = self.position {
+            properties.push_str(&format!(",line={},col={}", line, column));
+        }
+        properties.push_str(&format!(",title={}", escape_property(self.code())));
+        format!("::error {}::{}", properties, escape_data(&self.message))
+    }
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command, where `:` and `,` delimit.
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// The params of a `textDocument/publishDiagnostics` notification.
+pub fn publish_diagnostics(uri: &str, diagnostics: &[Diagnostic], text: Option<&str>) -> JSONValue {
+    let mut params = JSON::new();
+    params.insert("uri", uri);
+    params.insert(
+        "diagnostics",
+        JSONValue::Array(
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_lsp(text))
+                .collect(),
+        ),
+    );
+    JSONValue::Object(params)
+}
+
+/// A SARIF 2.1.0 log of one run over `inputs`, each named as it was given
+/// and listed with the problems found in it, for code-scanning dashboards.
+/// Every input is listed as an artifact, valid or not. Relative names stay
+/// relative, so results resolve against wherever the run was made.
+pub fn sarif(inputs: &[(String, Vec<Diagnostic>)]) -> JSONValue {
+    let mut codes: Vec<&str> = inputs
+        .iter()
+        .flat_map(|(_, diagnostics)| diagnostics.iter().map(Diagnostic::code))
+        .collect();
+    codes.sort_unstable();
+    codes.dedup();
+
+    let rules = codes.iter().map(|&code| {
+        let mut rule = JSON::new();
+        rule.insert("id", code);
+        rule.insert("shortDescription", text(describe(code)));
+        JSONValue::Object(rule)
+    });
+    let mut driver = JSON::new();
+    driver.insert("name", "json-parser");
+    driver.insert("version", env!("CARGO_PKG_VERSION"));
+    driver.insert("rules", JSONValue::Array(rules.collect()));
+    let mut tool = JSON::new();
+    tool.insert("driver", driver);
+
+    let mut artifacts = vec![];
+    let mut results = vec![];
+    for (idx, (name, diagnostics)) in inputs.iter().enumerate() {
+        let uri = relative_uri(name);
+        let mut location = JSON::new();
+        location.insert("uri", uri.as_str());
+        let mut artifact = JSON::new();
+        artifact.insert("location", location);
+        artifacts.push(JSONValue::Object(artifact));
+
+        for diagnostic in diagnostics {
+            let mut artifact_location = JSON::new();
+            artifact_location.insert("uri", uri.as_str());
+            artifact_location.insert("index", idx as i32);
+            let mut physical = JSON::new();
+            physical.insert("artifactLocation", artifact_location);
+            if let Some((line, column)) = diagnostic.position {
+                let mut region = JSON::new();
+                region.insert("startLine", line as i32);
+                region.insert("startColumn", column as i32);
+                physical.insert("region", region);
+            }
+            let mut location = JSON::new();
+            location.insert("physicalLocation", physical);
+
+            let code = diagnostic.code();
+            let mut result = JSON::new();
+            result.insert("ruleId", code);
+            result.insert(
+                "ruleIndex",
+                codes.binary_search(&code).unwrap_or_default() as i32,
+            );
+            result.insert("level", "error");
+            result.insert("message", text(&diagnostic.message));
+            result.insert(
+                "locations",
+                JSONValue::Array(vec![JSONValue::Object(location)]),
+            );
+            results.push(JSONValue::Object(result));
+        }
+    }
+
+    let mut run = JSON::new();
+    run.insert("tool", tool);
+    // Columns are counted in characters, as the errors count them.
+    run.insert("columnKind", "unicodeCodePoints");
+    run.insert("artifacts", JSONValue::Array(artifacts));
+    run.insert("results", JSONValue::Array(results));
+
+    let mut log = JSON::new();
+    log.insert("$schema", "https://json.schemastore.org/sarif-2.1.0.json");
+    log.insert("version", "2.1.0");
+    log.insert("runs", JSONValue::Array(vec![JSONValue::Object(run)]));
+    JSONValue::Object(log)
+}
+
+/// What each `code` means, as SARIF rules describe them.
+fn describe(code: &str) -> &'static str {
+    match code {
+        "limit" => "Input exceeds a configured size limit",
+        "depth" => "Values are nested too deeply",
+        "control-character" => "String contains a control character",
+        "encoding" => "Input is not valid UTF-8",
+        "schema" => "Value does not satisfy the schema",
+        "timestamp" => "Date field is not an RFC 3339 timestamp",
+        "format" => "String is not in the format its field requires",
+        "duplicate-key" => "Object repeats a key",
+        "substitution" => "Environment placeholder cannot be filled in",
+        "syntax" => "Input is not well-formed JSON",
+        _ => "Input could not be read or checked",
+    }
+}
+
+fn text(message: &str) -> JSON {
+    let mut text = JSON::new();
+    text.insert("text", message);
+    text
+}
+
+/// A `file://` URI for a path, or the name as it is if it is already a URI.
+pub fn file_uri(name: &str) -> String {
+    if name.contains("://") {
+        return name.to_string();
+    }
+    let path = std::path::Path::new(name);
+    let path = path
+        .canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", encode_path(&path))
+    } else {
+        format!("file:///{}", encode_path(&path))
+    }
+}
+
+/// A relative URI reference for a relative path, or else as `file_uri`.
+fn relative_uri(name: &str) -> String {
+    let path = std::path::Path::new(name);
+    if name.contains("://") || path.is_absolute() {
+        return file_uri(name);
+    }
+    encode_path(&name.replace('\\', "/"))
+}
+
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The UTF-16 offset of the character at `column` (0-based) in `line`, or
+/// `column` itself without the line's text.
+fn utf16_column(line: Option<&str>, column: usize) -> usize {
+    match line {
+        Some(line) => line.chars().take(column).map(char::len_utf16).sum(),
+        None => column,
+    }
+}
+
+fn range(line: usize, start: usize, end: usize) -> JSONValue {
+    let position = |character: usize| {
+        let mut position = JSON::new();
+        position.insert("line", line as i32);
+        position.insert("character", character as i32);
+        JSONValue::Object(position)
+    };
+    let mut range = JSON::new();
+    range.insert("start", position(start));
+    range.insert("end", position(end));
+    JSONValue::Object(range)
+}
@@ -0,0 +1,259 @@
+use crate::{
+    parser::{JSONValue, JSON},
+    path::{Path, Segment},
+    printer::Style,
+};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Added(JSONValue),
+    Removed(JSONValue),
+    Modified { old: JSONValue, new: JSONValue },
+}
+
+/// One difference between two documents, located by its path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: Path,
+    pub kind: ChangeKind,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ChangeKind::Added(value) => write!(f, "+ {}: {}", self.path, value),
+            ChangeKind::Removed(value) => write!(f, "- {}: {}", self.path, value),
+            ChangeKind::Modified { old, new } => write!(f, "~ {}: {} -> {}", self.path, old, new),
+        }
+    }
+}
+
+const ADDED_COLOR: &str = "\x1b[32m";
+const REMOVED_COLOR: &str = "\x1b[31m";
+const MODIFIED_COLOR: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+impl Change {
+    /// The change as `{"op": "add", "path": "a.b", "value": 1}`, with `op`
+    /// one of `add`, `remove` and `replace`, and `old` and `new` in place of
+    /// `value` for a replacement.
+    pub fn to_json(&self) -> JSON {
+        let mut json = JSON::new();
+        json.insert("path", self.path.to_string());
+        match &self.kind {
+            ChangeKind::Added(value) => {
+                json.insert("op", "add");
+                json.insert("value", value.clone());
+            }
+            ChangeKind::Removed(value) => {
+                json.insert("op", "remove");
+                json.insert("value", value.clone());
+            }
+            ChangeKind::Modified { old, new } => {
+                json.insert("op", "replace");
+                json.insert("old", old.clone());
+                json.insert("new", new.clone());
+            }
+        }
+        json
+    }
+}
+
+/// Lays changes out for reading, one per line: a `+`, `-` or `~` marker,
+/// the path padded so the values line up, then the value, or the old and new
+/// values, written compactly in `style`. With `style.color`, additions are
+/// green, removals red and the markers of modifications yellow, with their
+/// old value red and new value green. Fails as `try_to_styled_string` does
+/// when a value holds a NaN or infinity `style` cannot write.
+pub fn render_changes(changes: &[Change], style: &Style) -> Result<String, String> {
+    let paths: Vec<String> = changes
+        .iter()
+        .map(|change| match change.path.to_string() {
+            path if path.is_empty() => String::from("<root>"),
+            path => path,
+        })
+        .collect();
+    let width = paths
+        .iter()
+        .map(|path| path.chars().count())
+        .max()
+        .unwrap_or(0);
+    let paint = |color_code: &str, text: &str| {
+        if style.color {
+            format!("{}{}{}", color_code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+    let value_style = Style {
+        color: false,
+        compact: true,
+        final_newline: false,
+        ..style.clone()
+    };
+    let show = |value: &JSONValue| value.try_to_styled_string(&value_style);
+
+    let mut output = String::new();
+    for (change, path) in changes.iter().zip(&paths) {
+        let padded = format!("{:width$}", path, width = width);
+        let line = match &change.kind {
+            ChangeKind::Added(value) => {
+                paint(ADDED_COLOR, &format!("+ {}  {}", padded, show(value)?))
+            }
+            ChangeKind::Removed(value) => {
+                paint(REMOVED_COLOR, &format!("- {}  {}", padded, show(value)?))
+            }
+            ChangeKind::Modified { old, new } => format!(
+                "{} {}  {} -> {}",
+                paint(MODIFIED_COLOR, "~"),
+                padded,
+                paint(REMOVED_COLOR, &show(old)?),
+                paint(ADDED_COLOR, &show(new)?)
+            ),
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+impl JSON {
+    /// Lists what changed going from `self` to `other`. Objects are compared
+    /// member by member in key order and arrays element by element, so an
+    /// element inserted into an array shows up as changes to every element
+    /// after it.
+    pub fn diff(&self, other: &JSON) -> Vec<Change> {
+        self.diff_with_tolerance(other, 0.0)
+    }
+
+    /// Like `diff`, but numbers no more than `tolerance` apart are unchanged.
+    pub fn diff_with_tolerance(&self, other: &JSON, tolerance: f64) -> Vec<Change> {
+        let mut changes = vec![];
+        diff_objects(&mut Path::default(), self, other, tolerance, &mut changes);
+        changes
+    }
+}
+
+impl JSONValue {
+    /// Like `JSON::diff_with_tolerance`, for documents of any kind.
+    pub fn diff_with_tolerance(&self, other: &JSONValue, tolerance: f64) -> Vec<Change> {
+        let mut changes = vec![];
+        diff_values(&mut Path::default(), self, other, tolerance, &mut changes);
+        changes
+    }
+
+    /// Equality that lets numbers differ by up to `epsilon`, so values that
+    /// went through a lossy numeric pipeline still compare equal. Without
+    /// the `bigdecimal` feature numbers are integers, so only an `epsilon`
+    /// of 1 or more loosens anything.
+    pub fn approx_eq(&self, other: &JSONValue, epsilon: f64) -> bool {
+        match (self, other) {
+            (JSONValue::Number(a), JSONValue::Number(b)) => {
+                (f64::from(*a) - f64::from(*b)).abs() <= epsilon
+            }
+            // The difference is taken exactly and only then rounded, so an
+            // `epsilon` of 0 asks for exact equality.
+            #[cfg(feature = "bigdecimal")]
+            (JSONValue::Decimal(_), _) | (_, JSONValue::Decimal(_)) => {
+                match (self.as_decimal(), other.as_decimal()) {
+                    (Some(a), Some(b)) => bigdecimal::ToPrimitive::to_f64(&(a - b).abs())
+                        .is_some_and(|diff| diff <= epsilon),
+                    _ => false,
+                }
+            }
+            #[cfg(feature = "i128")]
+            (JSONValue::Int128(_) | JSONValue::UInt128(_), _)
+            | (_, JSONValue::Int128(_) | JSONValue::UInt128(_)) => {
+                let diff = match (self.as_i128_checked(), other.as_i128_checked()) {
+                    (Ok(a), Ok(b)) => Some(a.abs_diff(b)),
+                    _ => self
+                        .as_u128_checked()
+                        .ok()
+                        .zip(other.as_u128_checked().ok())
+                        .map(|(a, b)| a.abs_diff(b)),
+                };
+                diff.is_some_and(|diff| diff as f64 <= epsilon)
+            }
+            (JSONValue::Array(a), JSONValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (JSONValue::Object(a), JSONValue::Object(b)) => {
+                a.object.len() == b.object.len()
+                    && a.object
+                        .iter()
+                        .all(|(key, a)| b.object.get(key).is_some_and(|b| a.approx_eq(b, epsilon)))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+fn diff_objects(
+    path: &mut Path,
+    old: &JSON,
+    new: &JSON,
+    tolerance: f64,
+    changes: &mut Vec<Change>,
+) {
+    let mut keys: Vec<_> = old.object.keys().chain(new.object.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        path.push(Segment::Key(key.to_string()));
+        match (old.object.get(key), new.object.get(key)) {
+            (Some(old), Some(new)) => diff_values(path, old, new, tolerance, changes),
+            (Some(old), None) => changes.push(Change {
+                path: path.clone(),
+                kind: ChangeKind::Removed(old.clone()),
+            }),
+            (None, Some(new)) => changes.push(Change {
+                path: path.clone(),
+                kind: ChangeKind::Added(new.clone()),
+            }),
+            (None, None) => {}
+        }
+        path.pop();
+    }
+}
+
+fn diff_values(
+    path: &mut Path,
+    old: &JSONValue,
+    new: &JSONValue,
+    tolerance: f64,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (JSONValue::Object(old), JSONValue::Object(new)) => {
+            diff_objects(path, old, new, tolerance, changes)
+        }
+        (JSONValue::Array(old), JSONValue::Array(new)) => {
+            for idx in 0..old.len().max(new.len()) {
+                path.push(Segment::Index(idx));
+                match (old.get(idx), new.get(idx)) {
+                    (Some(old), Some(new)) => diff_values(path, old, new, tolerance, changes),
+                    (Some(old), None) => changes.push(Change {
+                        path: path.clone(),
+                        kind: ChangeKind::Removed(old.clone()),
+                    }),
+                    (None, Some(new)) => changes.push(Change {
+                        path: path.clone(),
+                        kind: ChangeKind::Added(new.clone()),
+                    }),
+                    (None, None) => {}
+                }
+                path.pop();
+            }
+        }
+        (old, new) if !old.approx_eq(new, tolerance) => changes.push(Change {
+            path: path.clone(),
+            kind: ChangeKind::Modified {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        }),
+        _ => {}
+    }
+}
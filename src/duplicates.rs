@@ -0,0 +1,91 @@
+use crate::{
+    lazy::Lazy,
+    parser::{get_line_column, MAX_DEPTH},
+    path::{Path, Segment},
+};
+use std::{collections::HashMap, fmt::Display};
+
+/// A key given more than once in the object at `path`. Parsing keeps only
+/// the last value, so the others are silently lost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateKey {
+    pub path: Path,
+    pub key: String,
+    /// The line each occurrence's value starts on, in document order.
+    pub lines: Vec<usize>,
+}
+
+impl Display for DuplicateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "<root>" } else { &path };
+        let lines: Vec<String> = self.lines.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "{}: key {:?} repeated on lines {}",
+            path,
+            self.key,
+            lines.join(", ")
+        )
+    }
+}
+
+/// Every repeated key in the document, ordered by where each is first
+/// given. Errors are byte offsets, as with `Lazy`, for structure too broken
+/// or too deeply nested to walk.
+pub fn find_duplicate_keys(content: &str) -> Result<Vec<DuplicateKey>, usize> {
+    let mut found = vec![];
+    find_in(
+        content,
+        &Lazy::new(content),
+        &mut Path::default(),
+        &mut found,
+    )?;
+    found.sort_by_key(|duplicate| duplicate.lines[0]);
+    Ok(found)
+}
+
+fn find_in(
+    content: &str,
+    value: &Lazy,
+    path: &mut Path,
+    found: &mut Vec<DuplicateKey>,
+) -> Result<(), usize> {
+    if !value.raw().starts_with(['{', '[']) {
+        return Ok(());
+    }
+
+    let members = value.members()?;
+    if path.segments().len() >= MAX_DEPTH {
+        return Err(value.offset());
+    }
+    let mut seen: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut order = vec![];
+    for (idx, (key, member)) in members.iter().enumerate() {
+        match key {
+            Some(key) => {
+                let starts = seen.entry(key).or_default();
+                if starts.len() == 1 {
+                    order.push(*key);
+                }
+                starts.push(member.offset());
+                path.push(Segment::Key(key.to_string()));
+            }
+            None => path.push(Segment::Index(idx)),
+        }
+        find_in(content, member, path, found)?;
+        path.pop();
+    }
+
+    for key in order {
+        found.push(DuplicateKey {
+            path: path.clone(),
+            key: key.to_string(),
+            lines: seen[key]
+                .iter()
+                .map(|&start| get_line_column(content, start).0)
+                .collect(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use crate::{
+    parser::{JSONValue, Key, JSON},
+    path::{Path, Segment},
+};
+
+impl JSON {
+    /// An empty object.
+    pub fn new() -> JSON {
+        JSON::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: impl Into<JSONValue>) -> Option<JSONValue> {
+        self.object.insert(Key::from(key), value.into())
+    }
+
+    /// Returns the member at `key`, inserting the result of `default` first
+    /// if there is none, so nested structure can be built on demand, as in
+    /// `json.get_or_insert_with("cache", || JSON::new().into())`.
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &str,
+        default: impl FnOnce() -> JSONValue,
+    ) -> &mut JSONValue {
+        self.object.entry(Key::from(key)).or_insert_with(default)
+    }
+
+    /// The members, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &JSONValue)> {
+        self.object.iter().map(|(key, value)| (&**key, value))
+    }
+
+    /// Keeps only the members for which `keep` returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &mut JSONValue) -> bool) {
+        self.object.retain(|key, value| keep(key, value));
+    }
+
+    /// Removes every subtree, at any depth, for which `drop` returns true.
+    /// Paths use the indexes of the original document, before any elements
+    /// were removed.
+    pub fn filter_paths(&mut self, mut drop: impl FnMut(&Path, &JSONValue) -> bool) {
+        filter_object(self, &mut Path::default(), &mut drop);
+    }
+
+    /// Removes and returns the value at `path` (as in `users[3].password`),
+    /// shifting later array elements down. Returns `Ok(None)` when nothing
+    /// is there.
+    pub fn remove_path(&mut self, path: &str) -> Result<Option<JSONValue>, String> {
+        let path = Path::parse(path)?;
+        let (last, parents) = match path.segments().split_last() {
+            Some(split) => split,
+            None => return Err(String::from("cannot remove the root")),
+        };
+
+        let parent = match parents.split_first() {
+            None => return Ok(remove_child_of_object(self, last)),
+            Some((Segment::Key(key), rest)) => self
+                .object
+                .get_mut(key.as_str())
+                .and_then(|value| value_at_mut(value, rest)),
+            Some((Segment::Index(_), _)) => None,
+        };
+
+        Ok(match parent {
+            Some(JSONValue::Object(json)) => remove_child_of_object(json, last),
+            Some(JSONValue::Array(values)) => match last {
+                Segment::Index(idx) if *idx < values.len() => Some(values.remove(*idx)),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// Calls `f` on every value in the document, the root included, each time
+    /// with its path. A value is visited before its children, so keys renamed
+    /// or members replaced by `f` are what gets visited next. Since `self`
+    /// must stay an object, a root replaced by anything else leaves `self`
+    /// empty.
+    pub fn transform(&mut self, f: impl FnMut(&Path, &mut JSONValue)) {
+        let mut root = JSONValue::Object(std::mem::take(self));
+        root.transform(f);
+        if let JSONValue::Object(json) = root {
+            *self = json;
+        }
+    }
+}
+
+fn filter_object(
+    json: &mut JSON,
+    path: &mut Path,
+    drop: &mut impl FnMut(&Path, &JSONValue) -> bool,
+) {
+    json.object.retain(|key, value| {
+        path.push(Segment::Key(key.to_string()));
+        let keep = !drop(path, value);
+        if keep {
+            filter_value(value, path, drop);
+        }
+        path.pop();
+        keep
+    });
+}
+
+fn filter_value(
+    value: &mut JSONValue,
+    path: &mut Path,
+    drop: &mut impl FnMut(&Path, &JSONValue) -> bool,
+) {
+    match value {
+        JSONValue::Object(json) => filter_object(json, path, drop),
+        JSONValue::Array(values) => {
+            let mut idx = 0;
+            values.retain_mut(|value| {
+                path.push(Segment::Index(idx));
+                idx += 1;
+                let keep = !drop(path, value);
+                if keep {
+                    filter_value(value, path, drop);
+                }
+                path.pop();
+                keep
+            });
+        }
+        _ => {}
+    }
+}
+
+impl JSONValue {
+    /// Like `JSON::transform`, starting from this value.
+    pub fn transform(&mut self, mut f: impl FnMut(&Path, &mut JSONValue)) {
+        transform_value(self, &mut Path::default(), &mut f);
+    }
+
+    /// Keeps only the array elements for which `keep` returns true. Does
+    /// nothing to other values.
+    pub fn retain(&mut self, keep: impl FnMut(&mut JSONValue) -> bool) {
+        if let JSONValue::Array(values) = self {
+            values.retain_mut(keep);
+        }
+    }
+
+    /// The value at `path` below this one, if there is one.
+    pub fn at(&self, path: &Path) -> Option<&JSONValue> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (JSONValue::Object(json), Segment::Key(key)) => json.object.get(key.as_str())?,
+                (JSONValue::Array(values), Segment::Index(idx)) => values.get(*idx)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    pub fn as_object(&self) -> Option<&JSON> {
+        match self {
+            JSONValue::Object(json) => Some(json),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JSONValue>> {
+        match self {
+            JSONValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Gives arrays inside a document the full `Vec` API (`push`, `insert`,
+    /// `remove`, `truncate`, `sort_by`, ...) without taking the value apart.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JSONValue>> {
+        match self {
+            JSONValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut JSON> {
+        match self {
+            JSONValue::Object(json) => Some(json),
+            _ => None,
+        }
+    }
+}
+
+fn remove_child_of_object(json: &mut JSON, segment: &Segment) -> Option<JSONValue> {
+    match segment {
+        Segment::Key(key) => json.object.remove(key.as_str()),
+        Segment::Index(_) => None,
+    }
+}
+
+fn value_at_mut<'a>(value: &'a mut JSONValue, segments: &[Segment]) -> Option<&'a mut JSONValue> {
+    let mut current = value;
+    for segment in segments {
+        current = match (current, segment) {
+            (JSONValue::Object(json), Segment::Key(key)) => json.object.get_mut(key.as_str())?,
+            (JSONValue::Array(values), Segment::Index(idx)) => values.get_mut(*idx)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn transform_value(
+    value: &mut JSONValue,
+    path: &mut Path,
+    f: &mut impl FnMut(&Path, &mut JSONValue),
+) {
+    f(path, value);
+    match value {
+        JSONValue::Object(json) => {
+            for (key, value) in json.object.iter_mut() {
+                path.push(Segment::Key(key.to_string()));
+                transform_value(value, path, f);
+                path.pop();
+            }
+        }
+        JSONValue::Array(values) => {
+            for (idx, value) in values.iter_mut().enumerate() {
+                path.push(Segment::Index(idx));
+                transform_value(value, path, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,215 @@
+//! Checkers for the string formats JSON Schema's `format` keyword names.
+//! JTD schemas can ask for one with `"metadata": {"format": "email"}` on a
+//! `string` type; `metadata` is otherwise free-form, so unknown names are
+//! ignored there.
+
+use crate::{
+    jtd::is_rfc3339,
+    parser::JSONValue,
+    path::{Path, Segment},
+    redact::KeyPattern,
+};
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringFormat {
+    Uuid,
+    Email,
+    Uri,
+    Ipv4,
+    Ipv6,
+    Base64,
+    DateTime,
+}
+
+impl StringFormat {
+    /// Parses a format by the name JSON Schema gives it, or `base64`.
+    pub fn from_name(name: &str) -> Option<StringFormat> {
+        match name {
+            "uuid" => Some(StringFormat::Uuid),
+            "email" => Some(StringFormat::Email),
+            "uri" => Some(StringFormat::Uri),
+            "ipv4" => Some(StringFormat::Ipv4),
+            "ipv6" => Some(StringFormat::Ipv6),
+            "base64" => Some(StringFormat::Base64),
+            "date-time" => Some(StringFormat::DateTime),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StringFormat::Uuid => "uuid",
+            StringFormat::Email => "email",
+            StringFormat::Uri => "uri",
+            StringFormat::Ipv4 => "ipv4",
+            StringFormat::Ipv6 => "ipv6",
+            StringFormat::Base64 => "base64",
+            StringFormat::DateTime => "date-time",
+        }
+    }
+
+    pub fn is_valid(&self, text: &str) -> bool {
+        match self {
+            StringFormat::Uuid => is_uuid(text),
+            StringFormat::Email => is_email(text),
+            StringFormat::Uri => is_uri(text),
+            // Both reject leading zeros, which some parsers read as octal.
+            StringFormat::Ipv4 => text.parse::<Ipv4Addr>().is_ok(),
+            StringFormat::Ipv6 => text.parse::<Ipv6Addr>().is_ok(),
+            StringFormat::Base64 => is_base64(text),
+            StringFormat::DateTime => is_rfc3339(text),
+        }
+    }
+}
+
+impl Display for StringFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl JSONValue {
+    /// The strings under a key matching `keys`, at any depth, that are not
+    /// in `format`, with their paths in path order. A string in an array
+    /// counts as under the array's key, so `"ids": [...]` is checked element
+    /// by element. Values other than strings are left alone.
+    pub fn invalid_formats(&self, keys: &KeyPattern, format: StringFormat) -> Vec<(Path, &str)> {
+        let mut invalid: Vec<(Path, &str)> = self
+            .walk()
+            .filter_map(|(path, value)| match value {
+                JSONValue::String(text) if !format.is_valid(text) => Some((path, text.as_str())),
+                _ => None,
+            })
+            .filter(|(path, _)| {
+                let key = path
+                    .segments()
+                    .iter()
+                    .rev()
+                    .find_map(|segment| match segment {
+                        Segment::Key(key) => Some(key),
+                        Segment::Index(_) => None,
+                    });
+                key.is_some_and(|key| keys.is_match(key))
+            })
+            .collect();
+        invalid.sort_by(|(a, _), (b, _)| a.segments().cmp(b.segments()));
+        invalid
+    }
+}
+
+/// `8-4-4-4-12` hex digits, in either case, as RFC 9562 writes UUIDs.
+fn is_uuid(text: &str) -> bool {
+    let groups: Vec<&str> = text.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// An RFC 5321 mailbox: a dot-atom or quoted local part, and a host name or
+/// bracketed address literal.
+fn is_email(text: &str) -> bool {
+    let Some((local, domain)) = text.rsplit_once('@') else {
+        return false;
+    };
+    let local_ok = if let Some(quoted) = local
+        .strip_prefix('"')
+        .and_then(|local| local.strip_suffix('"'))
+    {
+        let mut escaped = false;
+        quoted.chars().all(|ch| {
+            let ok = escaped || (ch != '"' && (ch == ' ' || ch.is_ascii_graphic()));
+            escaped = !escaped && ch == '\\';
+            ok
+        }) && !escaped
+    } else {
+        !local.is_empty()
+            && local.split('.').all(|atom| {
+                !atom.is_empty()
+                    && atom
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(ch))
+            })
+    };
+    if !local_ok || local.len() > 64 {
+        return false;
+    }
+
+    if let Some(literal) = domain
+        .strip_prefix('[')
+        .and_then(|domain| domain.strip_suffix(']'))
+    {
+        return match literal.strip_prefix("IPv6:") {
+            Some(address) => address.parse::<Ipv6Addr>().is_ok(),
+            None => literal.parse::<Ipv4Addr>().is_ok(),
+        };
+    }
+    is_hostname(domain)
+}
+
+/// Dot-separated labels of letters, digits and inner hyphens, each at most
+/// 63 bytes, at most 253 in all.
+fn is_hostname(text: &str) -> bool {
+    text.len() <= 253
+        && text.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// An absolute RFC 3986 URI: a scheme, then only characters a URI may hold,
+/// with every `%` starting a two-digit hex escape.
+fn is_uri(text: &str) -> bool {
+    let Some((scheme, rest)) = text.split_once(':') else {
+        return false;
+    };
+    let scheme_ok = scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && scheme
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'));
+    if !scheme_ok {
+        return false;
+    }
+
+    let bytes = rest.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'%' => {
+                if !bytes
+                    .get(idx + 1..idx + 3)
+                    .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                {
+                    return false;
+                }
+                idx += 3;
+                continue;
+            }
+            b if b.is_ascii_alphanumeric() => {}
+            b'-' | b'.' | b'_' | b'~' | b':' | b'/' | b'?' | b'#' | b'[' | b']' | b'@' => {}
+            b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' => {}
+            _ => return false,
+        }
+        idx += 1;
+    }
+    true
+}
+
+/// The RFC 4648 base64 alphabet, padded with `=` to a multiple of four.
+fn is_base64(text: &str) -> bool {
+    let data = text.trim_end_matches('=');
+    text.len().is_multiple_of(4)
+        && text.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+}
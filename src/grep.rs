@@ -0,0 +1,26 @@
+use crate::{
+    parser::JSONValue,
+    path::{Path, Segment},
+    redact::KeyPattern,
+};
+
+impl JSONValue {
+    /// Every string value matching `pattern`, with its path, and with
+    /// `keys`, every member whose key matches, whatever its value. Matches
+    /// are ordered by path.
+    pub fn grep(&self, pattern: &KeyPattern, keys: bool) -> Vec<(Path, &JSONValue)> {
+        let mut found: Vec<_> = self
+            .walk()
+            .filter(|(path, value)| {
+                let key_matches = keys
+                    && path.segments().last().is_some_and(|segment| match segment {
+                        Segment::Key(key) => pattern.is_match(key),
+                        Segment::Index(_) => false,
+                    });
+                key_matches || matches!(value, JSONValue::String(text) if pattern.is_match(text))
+            })
+            .collect();
+        found.sort_by_cached_key(|(path, _)| path.to_string());
+        found
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    parser::JSONValue,
+    path::{Path, Segment},
+};
+use std::{collections::HashMap, fmt::Display};
+
+const TYPES: [&str; 6] = ["object", "array", "string", "number", "boolean", "null"];
+
+/// How many values of each type were found at each field of a document,
+/// fields being paths with array indices left out, as in `users[].age`.
+/// Meant for spotting fields whose type is not what it should be.
+#[derive(Debug, Default, Clone)]
+pub struct TypeHistogram {
+    fields: HashMap<String, [usize; TYPES.len()]>,
+}
+
+/// The counts for one field of a `TypeHistogram`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTypes<'a> {
+    pub field: &'a str,
+    pub total: usize,
+    /// `(type, count)` pairs, most common first.
+    pub types: Vec<(&'static str, usize)>,
+}
+
+impl TypeHistogram {
+    pub fn new() -> TypeHistogram {
+        TypeHistogram::default()
+    }
+
+    /// Counts every value in `value`, so several documents can be added up.
+    pub fn add(&mut self, value: &JSONValue) {
+        for (path, value) in value.walk() {
+            let kind = match value {
+                JSONValue::Object(_) => 0,
+                JSONValue::Array(_) => 1,
+                JSONValue::String(_) => 2,
+                JSONValue::Number(_) => 3,
+                #[cfg(feature = "i128")]
+                JSONValue::Int128(_) | JSONValue::UInt128(_) => 3,
+                #[cfg(feature = "bigdecimal")]
+                JSONValue::Decimal(_) | JSONValue::NonFinite(_) => 3,
+                JSONValue::Bool(_) => 4,
+                JSONValue::Null => 5,
+            };
+            self.fields.entry(field(&path)).or_default()[kind] += 1;
+        }
+    }
+
+    /// The fields in order.
+    pub fn fields(&self) -> Vec<FieldTypes<'_>> {
+        let mut fields: Vec<_> = self
+            .fields
+            .iter()
+            .map(|(field, counts)| {
+                let mut types: Vec<_> = TYPES
+                    .iter()
+                    .zip(counts)
+                    .filter(|(_, &count)| count > 0)
+                    .map(|(&kind, &count)| (kind, count))
+                    .collect();
+                types.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+                FieldTypes {
+                    field,
+                    total: counts.iter().sum(),
+                    types,
+                }
+            })
+            .collect();
+        fields.sort_by(|a, b| a.field.cmp(b.field));
+        fields
+    }
+}
+
+impl JSONValue {
+    pub fn type_histogram(&self) -> TypeHistogram {
+        let mut histogram = TypeHistogram::new();
+        histogram.add(self);
+        histogram
+    }
+}
+
+/// One line per field: `users[].age: 98% number, 2% string (1532 values)`.
+impl Display for TypeHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for FieldTypes {
+            field,
+            total,
+            types,
+        } in self.fields()
+        {
+            let field = if field.is_empty() { "<root>" } else { field };
+            let shares: Vec<String> = types
+                .iter()
+                .map(|(kind, count)| format!("{} {}", percent(*count, total), kind))
+                .collect();
+            writeln!(
+                f,
+                "{}: {} ({} value{})",
+                field,
+                shares.join(", "),
+                total,
+                if total == 1 { "" } else { "s" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `count` as a whole percentage of `total`, never rounding a share that is
+/// there to 0% or one that is not everything to 100%.
+fn percent(count: usize, total: usize) -> String {
+    let share = count as f64 * 100.0 / total as f64;
+    match share {
+        _ if count == total => String::from("100%"),
+        share if share < 1.0 => String::from("<1%"),
+        share if share > 99.0 => String::from(">99%"),
+        share => format!("{:.0}%", share),
+    }
+}
+
+/// `path` with its indices written `[]`, so all elements share a field.
+fn field(path: &Path) -> String {
+    let mut field = String::new();
+    for segment in path.segments() {
+        match segment {
+            Segment::Index(_) => field.push_str("[]"),
+            Segment::Key(key) => {
+                // Quoted, where it needs to be, the way `Path` writes it.
+                let key = Path(vec![Segment::Key(key.clone())]).to_string();
+                if !field.is_empty() && !key.starts_with('[') {
+                    field.push('.');
+                }
+                field.push_str(&key);
+            }
+        }
+    }
+    field
+}
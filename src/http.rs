@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+pub fn is_url(arg: &str) -> bool {
+    arg.starts_with("http://") || arg.starts_with("https://")
+}
+
+/// Downloads `url`, reading no more than one byte past `limit` of the body,
+/// so a response over `--max-bytes` is caught without holding all of it.
+#[cfg(feature = "http")]
+pub fn fetch(
+    url: &str,
+    timeout: Option<Duration>,
+    limit: Option<usize>,
+) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let mut agent = ureq::AgentBuilder::new();
+    if let Some(timeout) = timeout {
+        agent = agent.timeout(timeout);
+    }
+
+    let response = match agent.build().get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, _)) => return Err(format!("server responded with {}", code)),
+        Err(ureq::Error::Transport(transport)) => {
+            return Err(match transport.message() {
+                Some(message) => format!("{}: {}", transport.kind(), message),
+                None => transport.kind().to_string(),
+            })
+        }
+    };
+
+    let mut bytes = vec![];
+    let limit = limit.map_or(u64::MAX, |limit| limit as u64 + 1);
+    match response.into_reader().take(limit).read_to_end(&mut bytes) {
+        Ok(_) => Ok(bytes),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(not(feature = "http"))]
+pub fn fetch(_: &str, _: Option<Duration>, _: Option<usize>) -> Result<Vec<u8>, String> {
+    Err(String::from(
+        "URL support is not enabled, rebuild with `--features http`",
+    ))
+}
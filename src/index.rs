@@ -0,0 +1,300 @@
+use crate::{
+    lazy::Lazy,
+    lexer::{is_whitespace, Cursor},
+    parser::{ArgsParseError, JSONValue, JSON},
+    path::{Path, Segment},
+    stream::{unescape, Event, Sink, StreamError, Validator},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+/// Where each top-level member or element of a document lies, so one can be
+/// read by seeking to it rather than parsing everything before it. Built by
+/// streaming through the document once, in memory bounded by the number of
+/// entries rather than by the document.
+///
+/// Written to a sidecar file as text: a header line, then one line per
+/// entry with its byte range and, in an object, its key as a JSON string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    /// The size of the document indexed, to tell when it has changed since.
+    pub source_len: u64,
+    pub object: bool,
+    entries: Vec<Entry>,
+    /// Each key's entry, the last one if it is repeated, as parsing keeps.
+    keys: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Option<String>,
+    /// Bytes `start..end` of the document hold the value.
+    pub start: u64,
+    pub end: u64,
+}
+
+const HEADER: &str = "json-parser-index 1";
+
+/// The sink `Index::build` reads keys with, keeping only those it is asked
+/// to.
+#[derive(Default)]
+struct Keys {
+    wanted: bool,
+    key: Option<String>,
+}
+
+impl Sink for Keys {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        if let (true, Event::Key(key)) = (self.wanted, event) {
+            self.key = Some(if key.contains('\\') {
+                unescape(key)
+            } else {
+                key.to_string()
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Index {
+    /// Checks the grammar of the document in `reader`, with numbers of any
+    /// size or precision, while noting where each of its top-level values
+    /// starts and ends. The root must be an object or array.
+    pub fn build(mut reader: impl Read) -> Result<Index, StreamError> {
+        let mut validator = Validator::new(true);
+        let mut keys = Keys::default();
+        let mut entries: Vec<Entry> = vec![];
+        let mut key = None;
+        let mut start = None;
+        let mut object = false;
+
+        let mut buffer = [0; 64 * 1024];
+        let mut offset = 0;
+        loop {
+            let len = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(validator.error(&err.to_string())),
+            };
+            for &byte in &buffer[..len] {
+                let depth = validator.depth();
+                let awaits_value = validator.awaits_value();
+                let in_number = validator.in_number();
+                keys.wanted = depth == 1 && validator.in_key();
+                if validator.is_idle() && !is_whitespace(byte) {
+                    if byte != b'{' && byte != b'[' {
+                        validator.feed(byte, &mut keys)?;
+                        return Err(validator.error("only an object or an array can be indexed"));
+                    }
+                    object = byte == b'{';
+                }
+                validator.feed(byte, &mut keys)?;
+
+                if keys.wanted {
+                    key = keys.key.take().or(key);
+                }
+                if depth == 1 && awaits_value && !is_whitespace(byte) && validator.depth() >= 1 {
+                    start = Some(offset);
+                }
+                // A number ends at the byte after it, any other value with
+                // the byte that closes it.
+                let end = if depth == 1 && in_number && !validator.in_number() {
+                    Some(offset)
+                } else if validator.after_value() && validator.depth() == 1 {
+                    Some(offset + 1)
+                } else {
+                    None
+                };
+                if let (Some(end), Some(value_start)) = (end, start) {
+                    entries.push(Entry {
+                        key: key.take(),
+                        start: value_start,
+                        end,
+                    });
+                    start = None;
+                }
+                offset += 1;
+            }
+        }
+        if !validator.is_complete() {
+            return Err(validator.error("unexpected end of input"));
+        }
+
+        Ok(Index::new(offset, object, entries))
+    }
+
+    fn new(source_len: u64, object: bool, entries: Vec<Entry>) -> Index {
+        let keys = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| entry.key.clone().map(|key| (key, idx)))
+            .collect();
+        Index {
+            source_len,
+            object,
+            entries,
+            keys,
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry for a member of the root object or element of the root
+    /// array.
+    pub fn entry(&self, segment: &Segment) -> Option<&Entry> {
+        match segment {
+            Segment::Key(key) if self.object => self.keys.get(key).map(|&idx| &self.entries[idx]),
+            Segment::Index(idx) if !self.object => self.entries.get(*idx),
+            _ => None,
+        }
+    }
+
+    pub fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        let kind = if self.object { "object" } else { "array" };
+        writeln!(out, "{} {} {}", HEADER, self.source_len, kind)?;
+        for entry in &self.entries {
+            write!(out, "{} {}", entry.start, entry.end)?;
+            if let Some(key) = &entry.key {
+                write!(out, " {}", JSONValue::String(key.clone()))?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+
+    pub fn read_from(reader: impl BufRead) -> Result<Index, ArgsParseError> {
+        let invalid = |line: usize| ArgsParseError(format!("{}: not a valid index", line));
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(Ok(header)) => header,
+            _ => return Err(invalid(1)),
+        };
+        let (source_len, object) = match header
+            .strip_prefix(HEADER)
+            .map(|rest| rest.split_whitespace().collect::<Vec<_>>())
+            .as_deref()
+        {
+            Some([len, "object"]) => (len.parse().map_err(|_| invalid(1))?, true),
+            Some([len, "array"]) => (len.parse().map_err(|_| invalid(1))?, false),
+            _ => return Err(invalid(1)),
+        };
+
+        let mut entries = vec![];
+        for (idx, line) in lines.enumerate() {
+            let line = line.map_err(|err| ArgsParseError(err.to_string()))?;
+            let mut parts = line.splitn(3, ' ');
+            let start = parts.next().and_then(|start| start.parse().ok());
+            let end = parts.next().and_then(|end| end.parse().ok());
+            let key = match (object, parts.next()) {
+                (true, Some(key)) => match JSON::parse_key(&mut Cursor::new(key)) {
+                    Ok(key) => Some(key.to_string()),
+                    Err(_) => return Err(invalid(idx + 2)),
+                },
+                (false, None) => None,
+                _ => return Err(invalid(idx + 2)),
+            };
+            match (start, end) {
+                (Some(start), Some(end)) if start <= end && end <= source_len => {
+                    entries.push(Entry { key, start, end })
+                }
+                _ => return Err(invalid(idx + 2)),
+            }
+        }
+        Ok(Index::new(source_len, object, entries))
+    }
+}
+
+/// The file an index of `path` is kept in: `path` with `.idx` added.
+pub fn sidecar(path: impl AsRef<std::path::Path>) -> PathBuf {
+    let mut sidecar = path.as_ref().as_os_str().to_owned();
+    sidecar.push(".idx");
+    PathBuf::from(sidecar)
+}
+
+/// A document opened with its index, to read single top-level members or
+/// elements of a file too large to parse.
+pub struct IndexedFile {
+    file: File,
+    index: Index,
+}
+
+impl IndexedFile {
+    /// Opens `path` and the index beside it, failing if the file is not the
+    /// size it was when indexed.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<IndexedFile, ArgsParseError> {
+        let path = path.as_ref();
+        let sidecar = sidecar(path);
+        let opened = |path: &std::path::Path| {
+            File::open(path).map_err(|err| ArgsParseError(format!("{}: {}", path.display(), err)))
+        };
+        let index = Index::read_from(BufReader::new(opened(&sidecar)?))
+            .map_err(|err| ArgsParseError(format!("{}:{}", sidecar.display(), err)))?;
+        let file = opened(path)?;
+        let len = file
+            .metadata()
+            .map_err(|err| ArgsParseError(format!("{}: {}", path.display(), err)))?
+            .len();
+        if len != index.source_len {
+            return Err(ArgsParseError(format!(
+                "{}: the file has changed since it was indexed",
+                path.display()
+            )));
+        }
+        Ok(IndexedFile { file, index })
+    }
+
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// The source text of the top-level member or element at `segment`.
+    pub fn raw(&mut self, segment: &Segment) -> Result<Option<String>, ArgsParseError> {
+        let entry = match self.index.entry(segment) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let mut raw = vec![0; (entry.end - entry.start) as usize];
+        self.file
+            .seek(SeekFrom::Start(entry.start))
+            .and_then(|_| self.file.read_exact(&mut raw))
+            .map_err(|err| ArgsParseError(err.to_string()))?;
+        String::from_utf8(raw)
+            .map(Some)
+            .map_err(|_| ArgsParseError(String::from("the index does not match the file")))
+    }
+
+    /// The value at `path`, parsing only the top-level member or element its
+    /// first segment names, and of that only what leads to the value.
+    pub fn get(&mut self, path: &Path) -> Result<Option<JSONValue>, ArgsParseError> {
+        let (first, rest) = match path.segments().split_first() {
+            Some(split) => split,
+            None => return Err(ArgsParseError(String::from("the root is not indexed"))),
+        };
+        let raw = match self.raw(first)? {
+            Some(raw) => raw,
+            None => return Ok(None),
+        };
+        let invalid = |_| ArgsParseError(String::from("the index does not match the file"));
+        let value = match Lazy::new(&raw).at(&Path(rest.to_vec())).map_err(invalid)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let value = value.value().map_err(invalid)?;
+        Ok(Some(value.clone()))
+    }
+}
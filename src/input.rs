@@ -0,0 +1,254 @@
+use crate::{
+    archive,
+    compression::{self, Compression},
+    http,
+    parser::ArgsParseError,
+    trace,
+};
+use std::{
+    fs,
+    io::{self, BufRead, Read},
+    ops::Deref,
+    path::Path,
+    time::Duration,
+};
+
+/// The raw bytes of an input, either read into memory or mapped from disk.
+pub enum Bytes {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Bytes::Owned(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(map) => map,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::Owned(bytes)
+    }
+}
+
+/// A raw document read from a file, stdin, a URL or an archive entry, not yet
+/// decompressed or parsed.
+pub struct Input {
+    pub name: String,
+    pub bytes: Bytes,
+    pub compression: Compression,
+}
+
+impl Input {
+    /// Whether the input is named as newline-delimited JSON, `*.jsonl` or
+    /// `*.ndjson`, optionally compressed.
+    pub fn is_ndjson(&self) -> bool {
+        let stem = self.name.trim_end_matches(".gz").trim_end_matches(".zst");
+        stem.ends_with(".jsonl") || stem.ends_with(".ndjson")
+    }
+
+    /// With `mmap`, the file is mapped instead of read, so huge documents are
+    /// paged in by the OS as the parser walks them rather than copied up front.
+    /// Builds without the `mmap` feature fall back to reading the file.
+    ///
+    /// Any file name is accepted unless `extension` is given. Compression is
+    /// then told by a `.gz` or `.zst` suffix, or failing that by the content.
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        extension: Option<&str>,
+        mmap: bool,
+    ) -> Result<Input, ArgsParseError> {
+        let path = path.as_ref();
+        let span = trace::read(&path.display().to_string());
+        let result = Input::read_file(path, extension, mmap);
+        span.record_result(&result, |input| input.bytes.len());
+        result
+    }
+
+    fn read_file(
+        path: &Path,
+        extension: Option<&str>,
+        mmap: bool,
+    ) -> Result<Input, ArgsParseError> {
+        let name = path.display().to_string();
+        let compression = match extension {
+            Some(extension) => Some(check_extension(&name, extension)?),
+            None => None,
+        };
+
+        let bytes = if mmap { map_file(path) } else { None };
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => match fs::read(path) {
+                Ok(bytes) => bytes.into(),
+                Err(err) => return Err(io_error(&name, err)),
+            },
+        };
+
+        let compression = compression.unwrap_or_else(|| sniff(&name, &bytes));
+        Ok(Input {
+            name,
+            bytes,
+            compression,
+        })
+    }
+
+    pub fn from_stdin(decompress: bool) -> Result<Input, ArgsParseError> {
+        let span = trace::read("<stdin>");
+        let mut bytes = vec![];
+        if io::stdin().read_to_end(&mut bytes).is_err() {
+            let err = ArgsParseError(String::from("could not read from stdin"));
+            span.error(|| err.to_string());
+            return Err(err);
+        }
+        span.record_bytes(bytes.len());
+
+        let compression = if decompress {
+            Compression::from_magic(&bytes)
+        } else {
+            Compression::None
+        };
+        Ok(Input {
+            name: String::from("<stdin>"),
+            bytes: bytes.into(),
+            compression,
+        })
+    }
+
+    /// Downloads `url`; see `http::fetch` for `limit`.
+    pub fn from_url(
+        url: &str,
+        timeout: Option<Duration>,
+        limit: Option<usize>,
+    ) -> Result<Input, ArgsParseError> {
+        let span = trace::read(url);
+        let result = http::fetch(url, timeout, limit);
+        span.record_result(&result, Vec::len);
+        match result {
+            Ok(bytes) => Ok(Input {
+                name: url.to_string(),
+                bytes: bytes.into(),
+                compression: Compression::from_file_name(url),
+            }),
+            Err(err) => Err(ArgsParseError(format!("{}: {}", url, err))),
+        }
+    }
+
+    /// Reads every `*.json` entry of an archive, naming each one
+    /// `archive.zip!path/in/archive.json`. See `archive::read_json_entries`
+    /// for `limit`.
+    pub fn from_archive(
+        file_name: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<Input>, ArgsParseError> {
+        match archive::read_json_entries(file_name, limit) {
+            Ok(entries) => Ok(entries
+                .into_iter()
+                .map(|entry| Input {
+                    name: format!("{}!{}", file_name, entry.path),
+                    bytes: entry.bytes.into(),
+                    compression: Compression::None,
+                })
+                .collect()),
+            Err(err) => Err(ArgsParseError(err)),
+        }
+    }
+}
+
+/// Rejects files not named `*.json` (or whatever `extension` is, optionally
+/// compressed) and returns the compression implied by the name. Only used
+/// with `--require-extension`.
+pub fn check_extension(file_name: &str, extension: &str) -> Result<Compression, ArgsParseError> {
+    let stem = file_name.trim_end_matches(".gz").trim_end_matches(".zst");
+    if stem.ends_with(extension) {
+        Ok(Compression::from_file_name(file_name))
+    } else {
+        let kind = extension.trim_start_matches('.').to_uppercase();
+        Err(ArgsParseError(format!(
+            "{} is not a {} file",
+            file_name, kind
+        )))
+    }
+}
+
+/// Opens a file or stdin as a decompressing reader without loading it, for
+/// the streaming validator. Files must be named `*.json` only when
+/// `require_extension` is set.
+pub fn open_stream(
+    file_name: Option<&str>,
+    decompress: bool,
+    require_extension: bool,
+) -> Result<Box<dyn Read>, ArgsParseError> {
+    let (reader, compression): (Box<dyn Read>, Compression) = match file_name {
+        Some(file_name) => {
+            let required = if require_extension {
+                Some(check_extension(file_name, ".json")?)
+            } else {
+                None
+            };
+            let mut file = match fs::File::open(file_name) {
+                Ok(file) => io::BufReader::new(file),
+                Err(err) => return Err(io_error(file_name, err)),
+            };
+            let compression = match (required, file.fill_buf()) {
+                (Some(compression), _) => compression,
+                (None, Ok(bytes)) => sniff(file_name, bytes),
+                (None, Err(err)) => return Err(io_error(file_name, err)),
+            };
+            (Box::new(file), compression)
+        }
+        None => {
+            let mut stdin = io::BufReader::new(io::stdin());
+            let compression = match stdin.fill_buf() {
+                Ok(bytes) if decompress => Compression::from_magic(bytes),
+                _ => Compression::None,
+            };
+            (Box::new(stdin), compression)
+        }
+    };
+
+    match compression::reader(reader, compression) {
+        Ok(reader) => Ok(reader),
+        Err(err) => Err(ArgsParseError(format!(
+            "{}: failed to decompress: {}",
+            file_name.unwrap_or("<stdin>"),
+            err
+        ))),
+    }
+}
+
+/// Tells a file's compression by its name, or failing that by its first
+/// bytes, which can never start a JSON document.
+fn sniff(file_name: &str, bytes: &[u8]) -> Compression {
+    match Compression::from_file_name(file_name) {
+        Compression::None => Compression::from_magic(bytes),
+        compression => compression,
+    }
+}
+
+/// Reports why a file could not be read: missing, not permitted, a
+/// directory and so on.
+fn io_error(name: &str, err: io::Error) -> ArgsParseError {
+    ArgsParseError(format!("{}: {}", name, err))
+}
+
+#[cfg(feature = "mmap")]
+fn map_file(path: &Path) -> Option<Bytes> {
+    let file = fs::File::open(path).ok()?;
+    // Safety: the map is read-only and only lives for one parse; a file
+    // truncated underneath us by another process is outside what we guard.
+    let map = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+    Some(Bytes::Mapped(map))
+}
+
+#[cfg(not(feature = "mmap"))]
+fn map_file(_: &Path) -> Option<Bytes> {
+    None
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+/// Deduplicates object keys within a parse, so a key repeated across many
+/// objects (`"timestamp"`, `"level"`, ...) is allocated once and shared.
+#[derive(Default)]
+pub struct Interner {
+    keys: HashSet<Arc<str>>,
+    lookups: usize,
+    saved_bytes: usize,
+}
+
+/// What an interner holds and how much allocation it has avoided.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InternerStats {
+    /// Distinct keys held.
+    pub keys: usize,
+    /// Bytes of key text held.
+    pub key_bytes: usize,
+    /// Keys looked up, one per object member parsed.
+    pub lookups: usize,
+    /// Bytes of key text that were shared instead of allocated again.
+    pub saved_bytes: usize,
+}
+
+impl Interner {
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        self.lookups += 1;
+        if let Some(interned) = self.keys.get(key) {
+            self.saved_bytes += key.len();
+            return Arc::clone(interned);
+        }
+
+        let interned: Arc<str> = Arc::from(key);
+        self.keys.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Forgets every key but keeps the table's capacity for the next parse.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        InternerStats {
+            keys: self.keys.len(),
+            key_bytes: self.keys.iter().map(|key| key.len()).sum(),
+            lookups: self.lookups,
+            saved_bytes: self.saved_bytes,
+        }
+    }
+}
+
+/// An interner kept across documents and shareable between parsers, so
+/// identical keys in thousands of similarly shaped documents all point at
+/// one allocation. It only grows, so it suits documents drawn from a fixed
+/// set of keys; a parser holds its lock for the duration of each parse.
+#[derive(Default, Clone)]
+pub struct SharedInterner(Arc<Mutex<Interner>>);
+
+impl SharedInterner {
+    pub fn new() -> SharedInterner {
+        SharedInterner::default()
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        match self.0.lock() {
+            Ok(interner) => interner.stats(),
+            Err(poisoned) => poisoned.into_inner().stats(),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, Interner> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
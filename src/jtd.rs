@@ -0,0 +1,531 @@
+//! Validation against JSON Type Definition schemas (RFC 8927).
+
+use crate::{
+    formats::StringFormat,
+    parser::{JSONValue, JSON},
+    patch::pointer,
+    path::{Path, Segment},
+};
+use std::fmt::Display;
+
+/// A place where an instance breaks its schema, as a pair of JSON Pointers
+/// in the form RFC 8927 specifies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JtdError {
+    pub instance_path: String,
+    pub schema_path: String,
+}
+
+impl Display for JtdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let root = |path: &str| if path.is_empty() { "/" } else { path }.to_string();
+        write!(
+            f,
+            "{} does not satisfy {}",
+            root(&self.instance_path),
+            root(&self.schema_path)
+        )
+    }
+}
+
+const KEYWORDS: [&str; 13] = [
+    "definitions",
+    "metadata",
+    "nullable",
+    "ref",
+    "type",
+    "enum",
+    "elements",
+    "properties",
+    "optionalProperties",
+    "additionalProperties",
+    "values",
+    "discriminator",
+    "mapping",
+];
+
+const TYPES: [&str; 11] = [
+    "boolean",
+    "string",
+    "timestamp",
+    "float32",
+    "float64",
+    "int8",
+    "uint8",
+    "int16",
+    "uint16",
+    "int32",
+    "uint32",
+];
+
+/// A checked root schema.
+pub struct Schema {
+    root: JSON,
+}
+
+impl Schema {
+    /// Checks that `root` is a well-formed JTD root schema.
+    pub fn new(root: JSON) -> Result<Schema, String> {
+        let definitions = match root.object.get("definitions") {
+            Some(JSONValue::Object(definitions)) => Some(definitions),
+            Some(_) => return Err(String::from("`definitions` must be an object")),
+            None => None,
+        };
+        if let Some(definitions) = definitions {
+            for (name, schema) in &definitions.object {
+                check_schema(schema, definitions, false)
+                    .map_err(|err| format!("definitions/{}: {}", name, err))?;
+            }
+            check_ref_cycles(definitions)?;
+        }
+        check_object(&root, definitions.unwrap_or(&JSON::new()), true)?;
+        Ok(Schema { root })
+    }
+
+    /// Returns every error in `instance`, or none if it is valid.
+    pub fn validate(&self, instance: &JSONValue) -> Vec<JtdError> {
+        let mut validator = Validator {
+            definitions: match self.root.object.get("definitions") {
+                Some(JSONValue::Object(definitions)) => Some(definitions),
+                _ => None,
+            },
+            instance_path: Path::default(),
+            schema_path: Path::default(),
+            errors: vec![],
+        };
+        validator.validate(&self.root, instance, None);
+        validator.errors
+    }
+}
+
+/// Rejects definitions that only refer to one another, such as `{"ref": "a"}`
+/// as definition `a`, which validation would otherwise follow forever.
+fn check_ref_cycles(definitions: &JSON) -> Result<(), String> {
+    for name in definitions.object.keys() {
+        let mut seen = vec![&**name];
+        let mut current = &**name;
+        while let Some(JSONValue::Object(schema)) = definitions.object.get(current) {
+            match schema.object.get("ref") {
+                Some(JSONValue::String(next)) if seen.contains(&next.as_str()) => {
+                    return Err(format!("definitions/{}: `ref` cycle", name));
+                }
+                Some(JSONValue::String(next)) => {
+                    seen.push(next);
+                    current = next;
+                }
+                _ => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_schema(schema: &JSONValue, definitions: &JSON, root: bool) -> Result<(), String> {
+    match schema {
+        JSONValue::Object(schema) => check_object(schema, definitions, root),
+        _ => Err(String::from("schemas must be objects")),
+    }
+}
+
+fn check_object(schema: &JSON, definitions: &JSON, root: bool) -> Result<(), String> {
+    if let Some(key) = schema
+        .object
+        .keys()
+        .find(|key| !KEYWORDS.contains(&&***key))
+    {
+        return Err(format!("unknown keyword `{}`", key));
+    }
+    if !root && schema.object.contains_key("definitions") {
+        return Err(String::from("`definitions` is only allowed at the root"));
+    }
+    if let Some(nullable) = schema.object.get("nullable") {
+        if !matches!(nullable, JSONValue::Bool(_)) {
+            return Err(String::from("`nullable` must be a boolean"));
+        }
+    }
+
+    let has = |key: &str| schema.object.contains_key(key);
+    let forms = [
+        has("ref"),
+        has("type"),
+        has("enum"),
+        has("elements"),
+        has("properties") || has("optionalProperties"),
+        has("values"),
+        has("discriminator"),
+    ];
+    if forms.iter().filter(|&&form| form).count() > 1 {
+        return Err(String::from("a schema can only have one form"));
+    }
+    if has("additionalProperties") && !forms[4] {
+        return Err(String::from("`additionalProperties` needs `properties`"));
+    }
+    if has("mapping") != has("discriminator") {
+        return Err(String::from("`discriminator` and `mapping` go together"));
+    }
+
+    if let Some(reference) = schema.object.get("ref") {
+        match reference {
+            JSONValue::String(name) if definitions.object.contains_key(name.as_str()) => {}
+            _ => return Err(String::from("`ref` must name a definition")),
+        }
+    }
+    if let Some(kind) = schema.object.get("type") {
+        match kind {
+            JSONValue::String(kind) if TYPES.contains(&kind.as_str()) => {}
+            _ => return Err(String::from("unknown `type`")),
+        }
+    }
+    if let Some(values) = schema.object.get("enum") {
+        match values {
+            JSONValue::Array(values)
+                if !values.is_empty()
+                    && values
+                        .iter()
+                        .all(|value| matches!(value, JSONValue::String(_)))
+                    && values
+                        .iter()
+                        .enumerate()
+                        .all(|(idx, value)| !values[..idx].contains(value)) => {}
+            _ => return Err(String::from("`enum` must be distinct strings")),
+        }
+    }
+    for key in ["elements", "values"] {
+        if let Some(schema) = schema.object.get(key) {
+            check_schema(schema, definitions, false)?;
+        }
+    }
+    for key in ["properties", "optionalProperties"] {
+        match schema.object.get(key) {
+            Some(JSONValue::Object(properties)) => {
+                for schema in properties.object.values() {
+                    check_schema(schema, definitions, false)?;
+                }
+            }
+            Some(_) => return Err(format!("`{}` must be an object", key)),
+            None => {}
+        }
+    }
+    if let (Some(JSONValue::Object(required)), Some(JSONValue::Object(optional))) = (
+        schema.object.get("properties"),
+        schema.object.get("optionalProperties"),
+    ) {
+        if let Some(key) = required
+            .object
+            .keys()
+            .find(|key| optional.object.contains_key(*key))
+        {
+            return Err(format!("`{}` is both required and optional", key));
+        }
+    }
+    if let Some(additional) = schema.object.get("additionalProperties") {
+        if !matches!(additional, JSONValue::Bool(_)) {
+            return Err(String::from("`additionalProperties` must be a boolean"));
+        }
+    }
+    if has("discriminator") {
+        if !matches!(
+            schema.object.get("discriminator"),
+            Some(JSONValue::String(_))
+        ) {
+            return Err(String::from("`discriminator` must be a string"));
+        }
+        let mapping = match schema.object.get("mapping") {
+            Some(JSONValue::Object(mapping)) => mapping,
+            _ => return Err(String::from("`mapping` must be an object")),
+        };
+        for variant in mapping.object.values() {
+            check_schema(variant, definitions, false)?;
+            match variant {
+                JSONValue::Object(variant)
+                    if (variant.object.contains_key("properties")
+                        || variant.object.contains_key("optionalProperties"))
+                        && variant.object.get("nullable") != Some(&JSONValue::Bool(true)) => {}
+                _ => {
+                    return Err(String::from(
+                        "`mapping` values must be non-nullable properties schemas",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Validator<'a> {
+    definitions: Option<&'a JSON>,
+    instance_path: Path,
+    schema_path: Path,
+    errors: Vec<JtdError>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, schema_keys: &[&str]) {
+        let mut schema_path = self.schema_path.clone();
+        for key in schema_keys {
+            schema_path.push(Segment::Key(key.to_string()));
+        }
+        self.errors.push(JtdError {
+            instance_path: pointer(&self.instance_path),
+            schema_path: pointer(&schema_path),
+        });
+    }
+
+    /// Runs `f` with `segment` appended to the schema path.
+    fn in_schema(&mut self, segments: &[&str], f: impl FnOnce(&mut Self)) {
+        for segment in segments {
+            self.schema_path.push(Segment::Key(segment.to_string()));
+        }
+        f(self);
+        for _ in segments {
+            self.schema_path.pop();
+        }
+    }
+
+    fn in_instance(&mut self, segment: Segment, f: impl FnOnce(&mut Self)) {
+        self.instance_path.push(segment);
+        f(self);
+        self.instance_path.pop();
+    }
+
+    fn validate(&mut self, schema: &'a JSON, instance: &JSONValue, tag: Option<&str>) {
+        let get = |key: &str| schema.object.get(key);
+        if get("nullable") == Some(&JSONValue::Bool(true)) && *instance == JSONValue::Null {
+            return;
+        }
+
+        if let Some(JSONValue::String(name)) = get("ref") {
+            let definition = self
+                .definitions
+                .and_then(|definitions| definitions.object.get(name.as_str()));
+            if let Some(JSONValue::Object(definition)) = definition {
+                let schema_path = std::mem::take(&mut self.schema_path);
+                self.in_schema(&["definitions", name], |validator| {
+                    validator.validate(definition, instance, None)
+                });
+                self.schema_path = schema_path;
+            }
+        } else if let Some(JSONValue::String(kind)) = get("type") {
+            if !type_matches(kind, instance) {
+                self.error(&["type"]);
+            } else if let (JSONValue::String(text), Some(format)) =
+                (instance, string_format(schema))
+            {
+                if !format.is_valid(text) {
+                    self.error(&["metadata", "format"]);
+                }
+            }
+        } else if let Some(JSONValue::Array(values)) = get("enum") {
+            if !matches!(instance, JSONValue::String(_)) || !values.contains(instance) {
+                self.error(&["enum"]);
+            }
+        } else if let Some(JSONValue::Object(elements)) = get("elements") {
+            match instance {
+                JSONValue::Array(items) => {
+                    for (idx, item) in items.iter().enumerate() {
+                        self.in_instance(Segment::Index(idx), |validator| {
+                            validator.in_schema(&["elements"], |validator| {
+                                validator.validate(elements, item, None)
+                            })
+                        });
+                    }
+                }
+                _ => self.error(&["elements"]),
+            }
+        } else if get("properties").is_some() || get("optionalProperties").is_some() {
+            self.validate_properties(schema, instance, tag);
+        } else if let Some(JSONValue::Object(values)) = get("values") {
+            match instance {
+                JSONValue::Object(json) => {
+                    for (key, value) in &json.object {
+                        self.in_instance(Segment::Key(key.to_string()), |validator| {
+                            validator.in_schema(&["values"], |validator| {
+                                validator.validate(values, value, None)
+                            })
+                        });
+                    }
+                }
+                _ => self.error(&["values"]),
+            }
+        } else if let Some(JSONValue::String(discriminator)) = get("discriminator") {
+            let json = match instance {
+                JSONValue::Object(json) => json,
+                _ => return self.error(&["discriminator"]),
+            };
+            let tag = match json.object.get(discriminator.as_str()) {
+                Some(JSONValue::String(tag)) => tag,
+                Some(_) => {
+                    return self.in_instance(Segment::Key(discriminator.clone()), |validator| {
+                        validator.error(&["discriminator"])
+                    })
+                }
+                None => return self.error(&["discriminator"]),
+            };
+            let variant = match get("mapping") {
+                Some(JSONValue::Object(mapping)) => mapping.object.get(tag.as_str()),
+                _ => None,
+            };
+            match variant {
+                Some(JSONValue::Object(variant)) => {
+                    self.in_schema(&["mapping", tag], |validator| {
+                        validator.validate(variant, instance, Some(discriminator))
+                    });
+                }
+                _ => self.in_instance(Segment::Key(discriminator.clone()), |validator| {
+                    validator.error(&["mapping"])
+                }),
+            }
+        }
+    }
+
+    fn validate_properties(&mut self, schema: &'a JSON, instance: &JSONValue, tag: Option<&str>) {
+        let json = match instance {
+            JSONValue::Object(json) => json,
+            _ if schema.object.contains_key("properties") => return self.error(&["properties"]),
+            _ => return self.error(&["optionalProperties"]),
+        };
+
+        let mut known: Vec<&str> = tag.into_iter().collect();
+        for (keyword, required) in [("properties", true), ("optionalProperties", false)] {
+            let properties = match schema.object.get(keyword) {
+                Some(JSONValue::Object(properties)) => properties,
+                _ => continue,
+            };
+            for (name, property) in &properties.object {
+                known.push(name);
+                let property = match property {
+                    JSONValue::Object(property) => property,
+                    _ => continue,
+                };
+                match json.object.get(&**name) {
+                    Some(value) => self.in_instance(Segment::Key(name.to_string()), |validator| {
+                        validator.in_schema(&[keyword, name], |validator| {
+                            validator.validate(property, value, None)
+                        })
+                    }),
+                    None if required => {
+                        self.in_schema(&[keyword, name], |validator| validator.error(&[]))
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if schema.object.get("additionalProperties") == Some(&JSONValue::Bool(true)) {
+            return;
+        }
+        let mut extra: Vec<_> = json
+            .object
+            .keys()
+            .filter(|key| !known.contains(&&***key))
+            .collect();
+        extra.sort();
+        for key in extra {
+            self.in_instance(Segment::Key(key.to_string()), |validator| {
+                validator.error(&[])
+            });
+        }
+    }
+}
+
+/// The format `metadata.format` names, as `formats` describes.
+fn string_format(schema: &JSON) -> Option<StringFormat> {
+    match schema.object.get("metadata") {
+        Some(JSONValue::Object(metadata)) => match metadata.object.get("format") {
+            Some(JSONValue::String(name)) => StringFormat::from_name(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn type_matches(kind: &str, instance: &JSONValue) -> bool {
+    let range = match kind {
+        "boolean" => return matches!(instance, JSONValue::Bool(_)),
+        "string" => return matches!(instance, JSONValue::String(_)),
+        "timestamp" => {
+            return matches!(instance, JSONValue::String(val) if is_rfc3339(val));
+        }
+        "float32" | "float64" => return instance.is_number(),
+        "int8" => i8::MIN as i64..=i8::MAX as i64,
+        "uint8" => 0..=u8::MAX as i64,
+        "int16" => i16::MIN as i64..=i16::MAX as i64,
+        "uint16" => 0..=u16::MAX as i64,
+        "int32" => i32::MIN as i64..=i32::MAX as i64,
+        "uint32" => 0..=u32::MAX as i64,
+        _ => return false,
+    };
+    // A decimal with a fraction of zero, as in `1.0`, still makes an
+    // integer.
+    instance
+        .as_i64_checked()
+        .is_ok_and(|val| range.contains(&val))
+}
+
+/// Checks for an RFC 3339 `date-time`, such as `1985-04-12T23:20:50.52Z`.
+/// A leap second (`:60`) is accepted without checking it fell on a real leap
+/// second.
+pub fn is_rfc3339(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let (Some(year), Some(month), Some(day), Some(hour), Some(minute), Some(second)) = (
+        digits(text, 0..4),
+        digits(text, 5..7),
+        digits(text, 8..10),
+        digits(text, 11..13),
+        digits(text, 14..16),
+        digits(text, 17..19),
+    ) else {
+        return false;
+    };
+    if bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return false;
+    }
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    if day == 0 || day > days || hour > 23 || minute > 59 || second > 60 {
+        return false;
+    }
+
+    let mut rest = &text[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+
+    match rest {
+        "Z" | "z" => true,
+        _ => {
+            rest.len() == 6
+                && matches!(rest.as_bytes()[0], b'+' | b'-')
+                && rest.as_bytes()[3] == b':'
+                && digits(rest, 1..3).is_some_and(|hour| hour < 24)
+                && digits(rest, 4..6).is_some_and(|minute| minute < 60)
+        }
+    }
+}
+
+fn digits(text: &str, range: std::ops::Range<usize>) -> Option<u32> {
+    let part = text.get(range)?;
+    if part.bytes().all(|byte| byte.is_ascii_digit()) {
+        part.parse().ok()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    lexer::Cursor,
+    parser::{JSONValue, JSON},
+    path::{Path, Segment},
+};
+use std::cell::OnceCell;
+
+/// Where a member or element lies in the document, as byte offsets.
+pub(crate) struct Member {
+    /// `None` for array elements.
+    pub(crate) key: Option<String>,
+    /// Where the member's key starts, or for an element its value.
+    pub(crate) entry: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// A value kept as its raw source text and only parsed on first access.
+/// Navigating with `get`/`index` scans past sibling values without building
+/// them, so reading a couple of fields out of a huge document never builds
+/// the rest of it. Only the parts that are visited are validated.
+///
+/// Errors are byte offsets into the document.
+pub struct Lazy<'a> {
+    content: &'a str,
+    start: usize,
+    end: usize,
+    children: OnceCell<Result<Vec<Member>, usize>>,
+    value: OnceCell<Result<JSONValue, usize>>,
+}
+
+impl<'a> Lazy<'a> {
+    pub fn new(content: &'a str) -> Lazy<'a> {
+        let start = content.len() - content.trim_start().len();
+        let end = content.trim_end().len().max(start);
+        Lazy::span(content, start, end)
+    }
+
+    fn span(content: &'a str, start: usize, end: usize) -> Lazy<'a> {
+        Lazy {
+            content,
+            start,
+            end,
+            children: OnceCell::new(),
+            value: OnceCell::new(),
+        }
+    }
+
+    pub fn raw(&self) -> &'a str {
+        &self.content[self.start..self.end]
+    }
+
+    /// Parses this value fully, caching the result.
+    pub fn value(&self) -> Result<&JSONValue, usize> {
+        let value = self.value.get_or_init(|| {
+            let mut cursor = Cursor::new(&self.content[..self.end]);
+            cursor.pos = self.start;
+            match JSON::parse_value(&mut cursor) {
+                Ok(_) if cursor.remaining() > 0 => Err(cursor.pos),
+                Ok(value) => Ok(value),
+                Err(_) => Err(cursor.pos.saturating_sub(1)),
+            }
+        });
+
+        value.as_ref().map_err(|&offset| offset)
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<Lazy<'a>>, usize> {
+        Ok(self
+            .children()?
+            .iter()
+            .rev()
+            .find(|member| member.key.as_deref() == Some(key))
+            .map(|member| Lazy::span(self.content, member.start, member.end)))
+    }
+
+    pub fn index(&self, idx: usize) -> Result<Option<Lazy<'a>>, usize> {
+        if !self.raw().starts_with('[') {
+            return Ok(None);
+        }
+        Ok(self
+            .children()?
+            .get(idx)
+            .map(|member| Lazy::span(self.content, member.start, member.end)))
+    }
+
+    pub fn at(&self, path: &Path) -> Result<Option<Lazy<'a>>, usize> {
+        let mut current = Lazy::span(self.content, self.start, self.end);
+        for segment in path.segments() {
+            let next = match segment {
+                Segment::Key(key) => current.get(key)?,
+                Segment::Index(idx) => current.index(*idx)?,
+            };
+            current = match next {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(current))
+    }
+
+    /// The members of an object or elements of an array, in document order
+    /// and including any repeated keys.
+    pub(crate) fn members(&self) -> Result<Vec<(Option<&str>, Lazy<'a>)>, usize> {
+        Ok(self
+            .children()?
+            .iter()
+            .map(|member| {
+                let value = Lazy::span(self.content, member.start, member.end);
+                (member.key.as_deref(), value)
+            })
+            .collect())
+    }
+
+    /// Where this value starts in the document, as a byte offset.
+    pub(crate) fn offset(&self) -> usize {
+        self.start
+    }
+
+    /// Where this value ends in the document, as a byte offset.
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    pub(crate) fn children(&self) -> Result<&Vec<Member>, usize> {
+        let children = self.children.get_or_init(|| {
+            let mut cursor = Cursor::new(&self.content[..self.end]);
+            cursor.pos = self.start;
+            match cursor.next() {
+                Some(b'{') => scan_members(&mut cursor, b'}', true),
+                Some(b'[') => scan_members(&mut cursor, b']', false),
+                _ => Ok(vec![]),
+            }
+        });
+
+        children.as_ref().map_err(|&offset| offset)
+    }
+}
+
+/// Records the span of each member of a container whose opening bracket has
+/// been consumed, skipping over the values without parsing them.
+fn scan_members(cursor: &mut Cursor, close: u8, keyed: bool) -> Result<Vec<Member>, usize> {
+    let mut members = vec![];
+
+    cursor.skip_whitespace();
+    if cursor.peek() == Some(close) {
+        return Ok(members);
+    }
+
+    loop {
+        cursor.skip_whitespace();
+        let entry = cursor.pos;
+        let key = if keyed {
+            if cursor.next_non_whitespace() != Some(b'"') {
+                return Err(cursor.pos.saturating_sub(1));
+            }
+            let key = JSON::parse_string_value(cursor).map_err(|_| cursor.pos)?;
+            if cursor.next_non_whitespace() != Some(b':') {
+                return Err(cursor.pos.saturating_sub(1));
+            }
+            Some(key)
+        } else {
+            None
+        };
+
+        cursor.skip_whitespace();
+        let start = cursor.pos;
+        skip_value(cursor)?;
+        if cursor.pos == start {
+            return Err(start);
+        }
+        members.push(Member {
+            key,
+            entry,
+            start,
+            end: cursor.pos,
+        });
+
+        match cursor.next_non_whitespace() {
+            Some(b',') => {}
+            Some(byte) if byte == close => return Ok(members),
+            _ => return Err(cursor.pos.saturating_sub(1)),
+        }
+    }
+}
+
+/// Moves past one value, tracking only strings and bracket depth.
+fn skip_value(cursor: &mut Cursor) -> Result<(), usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+
+    while let Some(byte) = cursor.peek() {
+        if in_string {
+            if !cursor.skip_to_quote_or_escape() {
+                break;
+            }
+            if cursor.next() == Some(b'"') {
+                in_string = false;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else {
+                cursor.next();
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => return Ok(()),
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    cursor.next();
+                    return Ok(());
+                }
+            }
+            b',' if depth == 0 => return Ok(()),
+            _ if depth == 0 && crate::lexer::is_whitespace(byte) => return Ok(()),
+            _ => {}
+        }
+        cursor.next();
+    }
+
+    if depth == 0 && !in_string {
+        Ok(())
+    } else {
+        Err(cursor.pos)
+    }
+}
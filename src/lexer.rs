@@ -0,0 +1,215 @@
+use crate::{
+    cancel::Watch,
+    control::ControlPolicy,
+    intern::Interner,
+    limits::{allocation, container_bytes, LimitError, Limits},
+    parser::{Failure, JSONParseError, JSONValue, MAX_DEPTH},
+    simd,
+};
+use std::mem::size_of;
+
+/// A byte cursor over the document text. All structural characters in JSON
+/// are ASCII, so the parser can work on raw bytes and only ever slices the
+/// underlying `&str` at ASCII positions, which are always char boundaries.
+pub struct Cursor<'a> {
+    content: &'a str,
+    pub pos: usize,
+    pub interner: Interner,
+    /// Reused when a key has to be unescaped before it can be interned.
+    pub scratch: String,
+    pub(crate) watch: Watch,
+    /// Containers open around the current position, not counting the root.
+    pub depth: usize,
+    too_deep: bool,
+    pub control: ControlPolicy,
+    rejected_control: Option<char>,
+    /// Tolerates a trailing comma before a closing `]` or `}` and, with the
+    /// `bigdecimal` feature, reads `NaN`, `Infinity` and `-Infinity`.
+    pub lenient: bool,
+    /// Checked as values are read. Values seen and the estimated memory of
+    /// the document so far are counted against them.
+    pub limits: Limits,
+    nodes: usize,
+    memory: usize,
+    exceeded: Option<LimitError>,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Cursor::with_buffers(content, Interner::default(), String::new())
+    }
+
+    pub fn with_buffers(content: &'a str, interner: Interner, scratch: String) -> Self {
+        Cursor {
+            content,
+            pos: 0,
+            interner,
+            scratch,
+            watch: Watch::default(),
+            depth: 0,
+            too_deep: false,
+            control: ControlPolicy::default(),
+            rejected_control: None,
+            lenient: false,
+            limits: Limits::default(),
+            nodes: 0,
+            memory: size_of::<JSONValue>(),
+            exceeded: None,
+        }
+    }
+
+    pub fn into_buffers(self) -> (Interner, String) {
+        (self.interner, self.scratch)
+    }
+
+    /// Explains an error returned by the parser: an interruption, a limit or
+    /// policy the document broke, or else a syntax error at the byte just
+    /// consumed.
+    pub(crate) fn failure(&self) -> Failure {
+        let offset = self.pos.saturating_sub(1);
+        match (
+            self.watch.interrupted(),
+            self.exceeded,
+            self.rejected_control,
+        ) {
+            (Some(why), _, _) => Failure::Interrupted(why, offset),
+            _ if self.too_deep => Failure::TooDeep(offset),
+            (None, Some(err), _) => Failure::Limit(err, offset),
+            (None, None, Some(ch)) => Failure::ControlChar(ch, offset),
+            (None, None, None) => Failure::At(offset),
+        }
+    }
+
+    /// Fails the parse over a control character, under `ControlPolicy::Reject`.
+    pub fn reject_control(&mut self, ch: char) -> JSONParseError {
+        self.rejected_control = Some(ch);
+        JSONParseError
+    }
+
+    /// Opens a nested container, returning false once `MAX_DEPTH` or
+    /// `max_depth` would be exceeded.
+    pub fn enter(&mut self) -> bool {
+        if self.depth + 1 >= MAX_DEPTH {
+            self.too_deep = true;
+            return false;
+        }
+        // The root is a level too.
+        if self.limit(self.limits.check_depth(self.depth + 2)).is_err() {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    /// Fails the parse if `result` is a broken limit.
+    pub(crate) fn limit(&mut self, result: Result<(), LimitError>) -> Result<(), JSONParseError> {
+        result.map_err(|err| {
+            self.exceeded = Some(err);
+            JSONParseError
+        })
+    }
+
+    /// Counts a value against `max_nodes`.
+    pub(crate) fn count_value(&mut self) -> Result<(), JSONParseError> {
+        self.nodes += 1;
+        self.limit(self.limits.check_nodes(self.nodes))
+    }
+
+    /// Counts a string or key whose text, between its quotes, ran from
+    /// `start` to just before the cursor, against `max_string_len` and
+    /// `max_memory`. On failure the cursor is left on the opening quote.
+    pub(crate) fn count_string(&mut self, start: usize, key: bool) -> Result<(), JSONParseError> {
+        if self.limits.max_string_len.is_none() && self.limits.max_memory.is_none() {
+            return Ok(());
+        }
+        let content = self.content;
+        let text = &content[start..self.pos - 1];
+        let result = self
+            .limits
+            .check_string_len(text.chars().count())
+            .and_then(|()| {
+                // Keys are `Arc<str>`s, with their counts.
+                let extra = if key { 2 * size_of::<usize>() } else { 0 };
+                self.count_memory(allocation(text.len() + extra))
+            });
+        if result.is_err() {
+            self.pos = start;
+        }
+        self.limit(result)
+    }
+
+    /// Counts a closed array or object of `len` entries against
+    /// `max_memory`.
+    pub(crate) fn count_container(
+        &mut self,
+        object: bool,
+        len: usize,
+    ) -> Result<(), JSONParseError> {
+        if self.limits.max_memory.is_none() {
+            return Ok(());
+        }
+        let result = self.count_memory(allocation(container_bytes(object, len)));
+        self.limit(result)
+    }
+
+    /// Adds `bytes` to the estimated memory of the document.
+    fn count_memory(&mut self, bytes: usize) -> Result<(), LimitError> {
+        self.memory += bytes;
+        self.limits.check_memory(self.memory)
+    }
+
+    pub fn peek(&self) -> Option<u8> {
+        self.content.as_bytes().get(self.pos).copied()
+    }
+
+    pub fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.content.len() - self.pos
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.content[self.pos..]
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.content[start..end]
+    }
+
+    /// Consumes whitespace and returns the first other byte, consuming it too.
+    pub fn next_non_whitespace(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.next()
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        // Most gaps are zero or one byte, so only hand longer runs (such as
+        // indentation) to the bulk scanner.
+        if self.peek().is_some_and(is_whitespace) {
+            self.pos += 1 + simd::count_whitespace(&self.content.as_bytes()[self.pos + 1..]);
+        }
+    }
+
+    /// Advances to the next `"` or `\` without consuming it, returning false
+    /// if neither occurs before the end of input.
+    pub fn skip_to_quote_or_escape(&mut self) -> bool {
+        match simd::find_quote_or_escape(&self.content.as_bytes()[self.pos..]) {
+            Some(offset) => {
+                self.pos += offset;
+                true
+            }
+            None => {
+                self.pos = self.content.len();
+                false
+            }
+        }
+    }
+}
+
+pub fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
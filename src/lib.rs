@@ -0,0 +1,64 @@
+pub mod anonymize;
+#[cfg(feature = "cli")]
+pub mod archive;
+pub mod bson;
+pub mod cancel;
+pub mod canonical;
+pub mod case;
+pub mod chunked;
+pub mod codegen;
+pub mod commented;
+pub mod comments;
+pub mod compression;
+pub mod control;
+pub mod convert;
+pub mod cst;
+pub mod dates;
+#[cfg(feature = "bigdecimal")]
+pub mod decimal;
+pub mod dedup;
+#[cfg(feature = "cli")]
+pub mod diagnostics;
+pub mod diff;
+pub mod duplicates;
+pub mod edit;
+pub mod formats;
+pub mod grep;
+pub mod histogram;
+#[cfg(feature = "cli")]
+pub mod http;
+pub mod index;
+#[cfg(feature = "cli")]
+pub mod input;
+pub mod intern;
+pub mod jtd;
+pub mod lazy;
+mod lexer;
+pub mod limits;
+pub mod merge;
+mod parallel;
+pub mod parser;
+pub mod partial;
+pub mod patch;
+pub mod path;
+pub mod printer;
+pub mod project;
+pub mod redact;
+pub mod sample;
+mod simd;
+pub mod sort;
+pub mod spans;
+pub mod stats;
+pub mod stream;
+pub mod template;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+mod trace;
+pub mod tree;
+pub mod walk;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod xml;
+
+#[cfg(feature = "derive")]
+pub use json_parser_derive::{FromJson, ToJson};
@@ -0,0 +1,179 @@
+use crate::parser::{JSONValue, Key};
+use std::{fmt::Display, mem::size_of};
+
+/// Upper bounds on a document, checked by the parser as it reads, so
+/// untrusted input cannot exhaust memory or overflow the parser's stack.
+/// Parsing stops at the first value over a limit, and the error says where
+/// it is.
+#[derive(Debug, Default, Clone)]
+pub struct Limits {
+    pub max_depth: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub max_nodes: Option<usize>,
+    /// Longest string or key, in source characters with each escape
+    /// sequence counted as written.
+    pub max_string_len: Option<usize>,
+    pub max_members: Option<usize>,
+    pub max_array_len: Option<usize>,
+    /// Most bytes the parsed document may take in memory, as estimated from
+    /// its values, strings and the tables and arrays holding them. Input
+    /// that is small on disk can be many times larger once parsed.
+    pub max_memory: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LimitError {
+    Depth(usize),
+    Bytes(usize),
+    Nodes(usize),
+    StringLength(usize),
+    Members(usize),
+    ArrayLength(usize),
+    Memory(usize),
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::Depth(max) => {
+                write!(
+                    f,
+                    "limit exceeded: nesting is deeper than --max-depth {}",
+                    max
+                )
+            }
+            LimitError::Bytes(max) => {
+                write!(
+                    f,
+                    "limit exceeded: document is larger than --max-bytes {}",
+                    max
+                )
+            }
+            LimitError::Nodes(max) => {
+                write!(
+                    f,
+                    "limit exceeded: document has more than --max-nodes {} values",
+                    max
+                )
+            }
+            LimitError::StringLength(max) => {
+                write!(
+                    f,
+                    "limit exceeded: string is longer than --max-string-len {}",
+                    max
+                )
+            }
+            LimitError::Members(max) => {
+                write!(
+                    f,
+                    "limit exceeded: object has more than --max-members {} members",
+                    max
+                )
+            }
+            LimitError::ArrayLength(max) => {
+                write!(
+                    f,
+                    "limit exceeded: array has more than --max-array-len {} elements",
+                    max
+                )
+            }
+            LimitError::Memory(max) => {
+                write!(
+                    f,
+                    "limit exceeded: document would take more than --max-memory {} bytes once parsed",
+                    max
+                )
+            }
+        }
+    }
+}
+
+impl Limits {
+    pub fn check_bytes(&self, len: usize) -> Result<(), LimitError> {
+        match self.max_bytes {
+            Some(max) if len > max => Err(LimitError::Bytes(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether any limit beyond `max_bytes` is set, each of which is only
+    /// known while the document is parsed.
+    pub(crate) fn checks_structure(&self) -> bool {
+        self.max_depth.is_some()
+            || self.max_nodes.is_some()
+            || self.max_string_len.is_some()
+            || self.max_members.is_some()
+            || self.max_array_len.is_some()
+            || self.max_memory.is_some()
+    }
+
+    /// `levels` counts the root.
+    pub fn check_depth(&self, levels: usize) -> Result<(), LimitError> {
+        match self.max_depth {
+            Some(max) if levels > max => Err(LimitError::Depth(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_nodes(&self, nodes: usize) -> Result<(), LimitError> {
+        match self.max_nodes {
+            Some(max) if nodes > max => Err(LimitError::Nodes(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_string_len(&self, len: usize) -> Result<(), LimitError> {
+        match self.max_string_len {
+            Some(max) if len > max => Err(LimitError::StringLength(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_members(&self, members: usize) -> Result<(), LimitError> {
+        match self.max_members {
+            Some(max) if members > max => Err(LimitError::Members(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_array_len(&self, len: usize) -> Result<(), LimitError> {
+        match self.max_array_len {
+            Some(max) if len > max => Err(LimitError::ArrayLength(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_memory(&self, memory: usize) -> Result<(), LimitError> {
+        match self.max_memory {
+            Some(max) if memory > max => Err(LimitError::Memory(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The bytes an allocation of `len` bytes takes, with the bookkeeping the
+/// allocator keeps beside it, or nothing for an empty one.
+pub(crate) fn allocation(len: usize) -> usize {
+    match len {
+        0 => 0,
+        len => len + 2 * size_of::<usize>(),
+    }
+}
+
+/// Estimated heap bytes of an array or object of `len` entries as the parser
+/// builds them: a `Vec` that doubled as it grew, or a hash table of
+/// power-of-two buckets, each with a control byte, at most 7/8 full.
+pub(crate) fn container_bytes(object: bool, len: usize) -> usize {
+    match (object, len) {
+        (_, 0) => 0,
+        (false, len) => len.next_power_of_two().max(4) * size_of::<JSONValue>(),
+        (true, len) => {
+            let buckets = match len {
+                1..=3 => 4,
+                4..=7 => 8,
+                len => (len * 8).div_ceil(7).next_power_of_two(),
+            };
+            buckets * (size_of::<(Key, JSONValue)>() + 1)
+        }
+    }
+}
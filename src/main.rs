@@ -1,31 +1,1442 @@
-mod parser;
+mod bench;
+#[cfg(feature = "browse")]
+mod browse;
+mod config;
 mod utils;
 
-use parser::JSON;
-use std::process::exit;
-use utils::parse_args;
+use json_parser::{
+    archive,
+    codegen::TypeScript,
+    comments,
+    compression::Compression,
+    control::ControlPolicy,
+    diagnostics::{self, Diagnostic},
+    diff::render_changes,
+    duplicates, http,
+    index::Index,
+    input::{self, Input},
+    jtd::Schema,
+    lazy::Lazy,
+    parser::{self, get_line_column, ArgsParseError, JSONValue, ParseOptions, Parser, JSON},
+    patch,
+    printer::Style,
+    redact::KeyPattern,
+    stream::{self, JsonWriter},
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    process::exit,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use utils::{parse_args, Args, Command, Diagnostics, DiffFormat, Format};
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+#[derive(Default)]
+struct Summary {
+    valid: usize,
+    invalid: usize,
+    bytes: usize,
+}
 
 fn main() {
-    let files = match parse_args() {
+    let defaults = match config::load() {
+        Ok(defaults) => defaults,
+        Err(err) => {
+            eprintln!("json-parser: {}", err);
+            exit(1);
+        }
+    };
+
+    let mut args = match parse_args(defaults) {
         None => {
-            eprintln!("json-parser: usage: json-parser [file ...]");
+            eprintln!("json-parser: usage: json-parser bench [--iterations n] file ...");
+            eprintln!("       json-parser merge [--arrays replace|append|union] file ...");
+            eprintln!("       json-parser codegen [--lang ts] sample ...");
+            eprintln!("       json-parser diff [--tolerance x] [--diff-format text|json] old new");
+            eprintln!("       json-parser split --chunk-size n|--by-key --out-dir dir file");
+            eprintln!("       json-parser hash file ...");
+            eprintln!("       json-parser grep [-i] [--include-keys] regex file ...");
+            eprintln!("       json-parser index file ...");
+            eprintln!("       json-parser template --data file template");
+            eprintln!("       json-parser browse file");
+            eprintln!(
+                "       json-parser [--decompress] [--timeout secs] [--indent n|--pretty|--compact] \
+                 [--color|--no-color] [--escape-unicode] [--escape-slash] [--uppercase-hex] [--non-finite error|null|string|literal] [--lf|--crlf] [--final-newline|--no-final-newline] [--deterministic] [--strict|--lenient] [--ignore glob] [--summary-only] [--fail-fast|--max-errors n] [--jobs n [--unordered]] \
+                 [--max-depth n] [--max-bytes n] [--max-nodes n] \
+                 [--max-string-len n] [--max-members n] [--max-array-len n] [--max-memory n] [--mmap] [--threads n] [--get path [-r]] [--arg name value] [--argjson name json] [--sort-array-by path] \
+                 [--pick paths] [--omit paths] [--drop-nulls] [--drop-empty] \
+                 [--keys camel|snake|kebab] [--redact regex [--redact-ignore-case]] [--anonymize [--seed n]] \
+                 [--head n|--tail n|--sample n] \
+                 [--substitute-env [--allow-missing]] [--strip-comments] [--find-duplicates] [--tree] [--describe] [--type-histogram] \
+                 [--paths [--all-nodes] [--path-format dotted|pointer]] [--explode] [--jtd schema] [--validate-dates regex] [--check-formats format=regex] \
+                 [--control preserve|reject|strip] [--lossy] [--require-extension] [--diagnostics text|lsp] [--report sarif file] [--annotate github] \
+                 [--from json|xml] [--to json|bson|xml [--bson-root-array split|wrap|reject]] \
+                 [--slurp] [--check] [--stream] [file|url|archive|- ...]"
+            );
             exit(1);
         }
-        Some(files) => files,
+        Some(args) => args,
+    };
+
+    if let Some(pattern) = &args.redact_pattern {
+        match KeyPattern::new(pattern, args.redact_ignore_case) {
+            Ok(pattern) => args.redact = Some(pattern),
+            Err(err) => {
+                eprintln!("json-parser: --redact: {}", err);
+                exit(1);
+            }
+        }
+    }
+
+    if let Some(pattern) = &args.date_keys_pattern {
+        match KeyPattern::new(pattern, false) {
+            Ok(pattern) => args.date_keys = Some(pattern),
+            Err(err) => {
+                eprintln!("json-parser: --validate-dates: {}", err);
+                exit(1);
+            }
+        }
+    }
+
+    for (format, pattern) in &args.format_patterns {
+        match KeyPattern::new(pattern, false) {
+            Ok(pattern) => args.format_keys.push((*format, pattern)),
+            Err(err) => {
+                eprintln!("json-parser: --check-formats: {}", err);
+                exit(1);
+            }
+        }
+    }
+
+    if args.anonymize && args.seed.is_none() {
+        // Without `--seed`, each run replaces values differently.
+        let now = SystemTime::now().duration_since(UNIX_EPOCH);
+        args.seed = Some(now.map_or(0, |now| now.as_nanos() as u64));
+    }
+
+    match args.command {
+        Command::Bench => exit(bench::run(&args)),
+        Command::Merge => exit(merge(&args)),
+        Command::Codegen => exit(codegen(&args)),
+        Command::Diff => exit(diff(&args)),
+        Command::Split => exit(split(&args)),
+        Command::Hash => exit(hash(&args)),
+        Command::Grep => exit(grep(&args)),
+        Command::Index => exit(index(&args)),
+        Command::Template => exit(template(&args)),
+        Command::Browse => exit(browse(&args)),
+        Command::Validate => {}
+    }
+
+    let started = Instant::now();
+    let mut summary = Summary::default();
+    if args.stream {
+        validate_streams(&args, &mut summary);
+        report(&args, &summary, started);
+    }
+
+    let mut parser = Parser::default();
+    let schema = match &args.jtd {
+        Some(file) => match load_schema(file, &args, &mut parser) {
+            Ok(schema) => Some(schema),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let mut slurped = vec![];
+    let mut reported = vec![];
+    let mut handle = |outcome| handle(outcome, &args, &mut summary, &mut reported, &mut slurped);
+    if args.jobs > 1 && !args.files.is_empty() {
+        process_parallel(&args, schema.as_ref(), &mut handle);
+    } else {
+        // Files are read as they are reached, so stopping early skips the
+        // rest.
+        let stdin = args
+            .files
+            .is_empty()
+            .then(|| Input::from_stdin(args.decompress));
+        let inputs = stdin.into_iter().chain(
+            args.files
+                .iter()
+                .filter(|file| !args.is_ignored(file))
+                .flat_map(|file| read_inputs(file, &args)),
+        );
+        for input in inputs {
+            if handle(process(input, &args, &mut parser, schema.as_ref(), false)) {
+                break;
+            }
+        }
+    }
+
+    if args.slurp && summary.invalid == 0 {
+        let slurped = JSONValue::Array(slurped);
+        match render("<slurp>", &reshape(&slurped, &args), &args) {
+            Ok(output) if !args.summary_only && !args.check => {
+                let _ = io::stdout().write_all(&output);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = &args.sarif {
+        let style = Style {
+            sort_keys: true,
+            ..Style::default()
+        };
+        let log = match diagnostics::sarif(&reported).try_to_styled_string(&style) {
+            Ok(log) => log,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                exit(1);
+            }
+        };
+        if let Err(err) = fs::write(path, log + "\n") {
+            eprintln!("{}: {}", path, err);
+            exit(1);
+        }
+    }
+
+    report(&args, &summary, started);
+}
+
+/// What came of checking one input, held until it is reported.
+struct Outcome {
+    /// The name and, for `--diagnostics lsp`, the text that diagnostics are
+    /// reported against. An input that could not be read is named by its
+    /// error.
+    name: String,
+    text: Option<String>,
+    bytes: usize,
+    buffered: Buffered,
+    result: Result<Vec<u8>, ArgsParseError>,
+    /// `--slurp`: the input's documents, once all of them have passed.
+    slurped: Vec<JSONValue>,
+}
+
+/// What `validate_lines` writes as it goes, kept back when inputs are
+/// checked in parallel so that each input's output comes out in one piece.
+#[derive(Default)]
+struct Buffered {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// Checks one input. With `buffer`, nothing is written until the outcome is
+/// handled.
+fn process(
+    input: Result<Input, ArgsParseError>,
+    args: &Args,
+    parser: &mut Parser,
+    schema: Option<&Schema>,
+    buffer: bool,
+) -> Outcome {
+    let lsp = args.diagnostics == Diagnostics::Lsp;
+    let collect = lsp || args.sarif.is_some() || args.github_annotations;
+    let (name, text) = match &input {
+        Ok(input) if collect => (
+            input.name.clone(),
+            Some(&input.bytes[..])
+                .filter(|_| lsp && input.compression == Compression::None)
+                .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                .map(String::from),
+        ),
+        Err(err) if collect => (
+            err.to_string()
+                .split(": ")
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            None,
+        ),
+        _ => (String::new(), None),
+    };
+
+    let mut bytes = 0;
+    let mut buffered = Buffered::default();
+    let mut slurped = vec![];
+    let result = input.and_then(|input| {
+        bytes = input.bytes.len();
+        if args.find_duplicates {
+            find_duplicates(&input, args, parser)
+        } else if args.strip_comments {
+            strip_comments(&input, args, parser)
+        } else if args.slurp {
+            let name = input.name.clone();
+            let values = match args.from {
+                Format::Xml => vec![read_xml(&input, args)?],
+                _ => {
+                    let values = parser.parse_documents_from_input(input, &args.options);
+                    warn_replaced(&name, parser.replaced());
+                    values?
+                }
+            };
+            let mut values = values;
+            for value in values.iter_mut() {
+                if let Cow::Owned(substituted) = substitute_env(&name, value, args)? {
+                    *value = substituted;
+                }
+                check_control(&name, value, args)?;
+                if let Some(schema) = schema {
+                    check_schema(&name, schema, value)?;
+                }
+                check_formats(&name, value, args)?;
+            }
+            slurped = values;
+            Ok(vec![])
+        } else if args.get.is_empty() && args.from == Format::Json && input.is_ndjson() {
+            let buffered = Some(&mut buffered).filter(|_| buffer);
+            validate_lines(&input, args, parser, schema, buffered)
+        } else if args.get.is_empty() {
+            let name = input.name.clone();
+            let value = match args.from {
+                Format::Xml => read_xml(&input, args)?,
+                _ => {
+                    let value = parser.parse_from_input(input, &args.options);
+                    warn_replaced(&name, parser.replaced());
+                    value?
+                }
+            };
+            document_output(&name, &value, schema, args)
+        } else {
+            get_paths(&input, args)
+        }
+    });
+
+    Outcome {
+        name,
+        text,
+        bytes,
+        buffered,
+        result,
+        slurped,
+    }
+}
+
+/// Reports what came of one input and counts it, returning whether enough
+/// inputs have failed to stop.
+fn handle(
+    outcome: Outcome,
+    args: &Args,
+    summary: &mut Summary,
+    reported: &mut Vec<(String, Vec<Diagnostic>)>,
+    slurped: &mut Vec<JSONValue>,
+) -> bool {
+    let lsp = args.diagnostics == Diagnostics::Lsp;
+    let Outcome {
+        name,
+        text,
+        bytes,
+        buffered,
+        result,
+        slurped: values,
+    } = outcome;
+    summary.bytes += bytes;
+    let _ = io::stdout().write_all(&buffered.stdout);
+    let _ = io::stderr().write_all(&buffered.stderr);
+
+    if lsp || args.sarif.is_some() || args.github_annotations {
+        let diagnostics = match &result {
+            Ok(_) => vec![],
+            Err(err) => Diagnostic::from_errors(&name, &err.to_string()),
+        };
+        if lsp && !args.summary_only {
+            let uri = diagnostics::file_uri(&name);
+            let params = diagnostics::publish_diagnostics(&uri, &diagnostics, text.as_deref());
+            println!("{}", params);
+        }
+        if args.github_annotations && !args.summary_only {
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic.to_github(&name));
+            }
+        }
+        if args.sarif.is_some() {
+            reported.push((name, diagnostics));
+        }
+    }
+
+    match result {
+        Err(err) => {
+            summary.invalid += 1;
+            if !args.summary_only && !lsp && !args.github_annotations {
+                eprintln!("{}", err);
+            }
+        }
+        Ok(output) => {
+            summary.valid += 1;
+            if !args.summary_only && !args.check && !lsp {
+                let _ = io::stdout().write_all(&output);
+            }
+        }
+    };
+    slurped.extend(values);
+    args.should_stop(summary.invalid)
+}
+
+/// `--jobs`: checks the files on that many threads, each with a parser of
+/// its own. Outcomes are handled in argument order, those that finish early
+/// waiting for the ones before them, or with `--unordered` as soon as they
+/// are ready. Either way each input's output is written in one piece.
+fn process_parallel(
+    args: &Args,
+    schema: Option<&Schema>,
+    handle: &mut impl FnMut(Outcome) -> bool,
+) {
+    let files: Vec<&String> = args
+        .files
+        .iter()
+        .filter(|file| !args.is_ignored(file))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..args.jobs.min(files.len()) {
+            let (files, next, sender) = (&files, &next, sender.clone());
+            scope.spawn(move || {
+                let mut parser = Parser::default();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let file = match files.get(idx) {
+                        Some(file) => file,
+                        None => break,
+                    };
+                    let outcomes: Vec<Outcome> = read_inputs(file, args)
+                        .into_iter()
+                        .map(|input| process(input, args, &mut parser, schema, true))
+                        .collect();
+                    if sender.send((idx, outcomes)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Outcomes waiting for those of earlier files, by file index.
+        let mut waiting = BTreeMap::new();
+        let mut turn = 0;
+        for (idx, outcomes) in receiver {
+            let ready = if args.unordered {
+                vec![outcomes]
+            } else {
+                waiting.insert(idx, outcomes);
+                let mut ready = vec![];
+                while let Some(outcomes) = waiting.remove(&turn) {
+                    ready.push(outcomes);
+                    turn += 1;
+                }
+                ready
+            };
+            if ready.into_iter().flatten().any(&mut *handle) {
+                // Workers take no more files, and those still busy stop when
+                // they find no one listening.
+                next.store(files.len(), Ordering::Relaxed);
+                return;
+            }
+        }
+    });
+}
+
+/// Checks and renders one parsed document, applying everything that works
+/// on values rather than text.
+fn document_output(
+    name: &str,
+    value: &JSONValue,
+    schema: Option<&Schema>,
+    args: &Args,
+) -> Result<Vec<u8>, ArgsParseError> {
+    let value = substitute_env(name, value, args)?;
+    check_control(name, &value, args)?;
+    if let Some(schema) = schema {
+        check_schema(name, schema, &value)?;
+    }
+    check_formats(name, &value, args)?;
+    render(name, &reshape(&value, args), args)
+}
+
+/// Validates `*.jsonl` and `*.ndjson` inputs a line at a time: each good
+/// line is printed as it is checked, each bad one is reported as
+/// `file:line:col: message`, and the input fails at the end with a count of
+/// bad lines rather than at the first. With `--diagnostics lsp` or
+/// `--annotate github` the bad lines are returned together instead, to be
+/// reported one by one. With `buffered`, lines are kept there rather than
+/// written.
+fn validate_lines(
+    input: &Input,
+    args: &Args,
+    parser: &mut Parser,
+    schema: Option<&Schema>,
+    mut buffered: Option<&mut Buffered>,
+) -> Result<Vec<u8>, ArgsParseError> {
+    let content = JSON::decode_input(input, &args.options)?;
+    warn_replaced(&input.name, &content.replaced);
+
+    let lsp = args.diagnostics == Diagnostics::Lsp;
+    let collect = lsp || args.github_annotations;
+    let (mut lines, mut invalid) = (0, 0);
+    let mut errors = vec![];
+    for (line, result) in parser.parse_lines(&content, &args.options) {
+        lines += 1;
+        let name = format!("{}:{}", input.name, line);
+        let result = result
+            .map_err(|err| ArgsParseError(format!("{}:{}", input.name, err)))
+            .and_then(|value| document_output(&name, &value, schema, args));
+        match result {
+            Ok(output) => {
+                if !args.summary_only && !args.check && !lsp {
+                    let _ = match &mut buffered {
+                        Some(buffered) => buffered.stdout.write_all(&output),
+                        None => io::stdout().write_all(&output),
+                    };
+                }
+            }
+            Err(err) => {
+                invalid += 1;
+                if collect {
+                    errors.push(err.to_string());
+                } else if !args.summary_only {
+                    let _ = match &mut buffered {
+                        Some(buffered) => writeln!(buffered.stderr, "{}", err),
+                        None => writeln!(io::stderr(), "{}", err),
+                    };
+                }
+            }
+        }
+    }
+
+    if invalid == 0 {
+        Ok(vec![])
+    } else if collect {
+        // Every bad line becomes a diagnostic of its own.
+        Err(ArgsParseError(errors.join("\n")))
+    } else {
+        Err(ArgsParseError(format!(
+            "{}: {} of {} lines invalid",
+            input.name, invalid, lines
+        )))
+    }
+}
+
+/// `index`: writes beside each file an index of its top-level members or
+/// elements, for `IndexedFile` to read them without parsing the rest, and
+/// prints the index's name.
+fn index(args: &Args) -> i32 {
+    let mut status = 0;
+    for file in &args.files {
+        let sidecar = json_parser::index::sidecar(file);
+        let result = fs::File::open(file)
+            .map_err(|err| format!("{}: {}", file, err))
+            .and_then(|reader| Index::build(reader).map_err(|err| format!("{}:{}", file, err)))
+            .and_then(|index| {
+                fs::File::create(&sidecar)
+                    .and_then(|out| index.write_to(io::BufWriter::new(out)))
+                    .map_err(|err| format!("{}: {}", sidecar.display(), err))
+            });
+        match result {
+            Ok(()) => println!("{}", sidecar.display()),
+            Err(err) => {
+                eprintln!("{}", err);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// Prints the summary when asked for or when more than one document was
+/// processed, then exits with the run's status.
+fn report(args: &Args, summary: &Summary, started: Instant) -> ! {
+    let scanned = summary.valid + summary.invalid;
+    if args.summary_only || scanned > 1 {
+        eprintln!(
+            "{} files scanned, {} valid, {} invalid, {} bytes in {:.2}s",
+            scanned,
+            summary.valid,
+            summary.invalid,
+            summary.bytes,
+            started.elapsed().as_secs_f64()
+        );
+    }
+
+    exit(if summary.invalid > 0 { 1 } else { 0 });
+}
+
+/// `--stream`: validates each input as it is read, never holding a whole
+/// document in memory, and without `--check` writes it back out compact or
+/// indented as it goes, or with `--get` only the values at the paths.
+fn validate_streams(args: &Args, summary: &mut Summary) {
+    // Rejected control characters fail the stream where they are read.
+    let options = ParseOptions {
+        control: args.control,
+        ..args.options.clone()
+    };
+    let files: Vec<Option<&str>> = if args.files.is_empty() {
+        vec![None]
+    } else {
+        args.files
+            .iter()
+            .filter(|file| !args.is_ignored(file))
+            .map(|file| Some(file.as_str()).filter(|&file| file != "-"))
+            .collect()
     };
 
-    let mut status_code = 0;
     for file in files {
-        match JSON::parse_from_file(&file) {
+        let name = file.unwrap_or("<stdin>");
+        let result = match file {
+            Some(file) if http::is_url(file) || archive::is_archive(file) => Err(ArgsParseError(
+                format!("{}: --stream only supports files and stdin", file),
+            )),
+            _ => input::open_stream(file, args.decompress, args.require_extension).and_then(
+                |reader| {
+                    let stdout = io::BufWriter::new(io::stdout().lock());
+                    let mut writer = JsonWriter::styled(stdout, &args.style);
+                    match (args.check, args.get.is_empty()) {
+                        (true, true) => stream::validate_stream(reader, &options)
+                            .map_err(|err| ArgsParseError(format!("{}:{}", name, err))),
+                        (true, false) => {
+                            let sink = JsonWriter::compact(io::sink());
+                            select_stream(reader, sink, name, args, &options)
+                        }
+                        (false, true) => stream::transcode(reader, &mut writer, &options)
+                            .map_err(|err| ArgsParseError(format!("{}:{}", name, err))),
+                        (false, false) => select_stream(reader, writer, name, args, &options),
+                    }
+                },
+            ),
+        };
+
+        match result {
+            Ok(bytes) => {
+                summary.valid += 1;
+                summary.bytes += bytes as usize;
+            }
+            Err(err) => {
+                summary.invalid += 1;
+                if !args.summary_only {
+                    eprintln!("{}", err);
+                }
+            }
+        }
+        if args.should_stop(summary.invalid) {
+            break;
+        }
+    }
+}
+
+/// `--stream --get`: writes each value at the paths, which may use `*` or
+/// `[]`, as it is read, in document order rather than the order of the
+/// paths. Fails if a path without a wildcard has no value.
+fn select_stream(
+    reader: impl io::Read,
+    writer: impl stream::Sink,
+    name: &str,
+    args: &Args,
+    options: &ParseOptions,
+) -> Result<u64, ArgsParseError> {
+    let mut select = stream::Select::new(&args.get, writer);
+    let bytes = stream::transcode(reader, &mut select, options)
+        .map_err(|err| ArgsParseError(format!("{}:{}", name, err)))?;
+    if let Some(path) = select.missing().next() {
+        return Err(ArgsParseError(format!(
+            "{}: path not found: {}",
+            name, path
+        )));
+    }
+    Ok(bytes)
+}
+
+/// `--get`: prints only the values at the requested paths. Subtrees off the
+/// paths are skipped over rather than parsed.
+fn get_paths(input: &Input, args: &Args) -> Result<Vec<u8>, ArgsParseError> {
+    let content = JSON::decode_input(input, &args.options)?;
+    warn_replaced(&input.name, &content.replaced);
+    let root = Lazy::new(&content);
+    let located = |offset| {
+        let (line, column) = get_line_column(&content, offset);
+        ArgsParseError(format!(
+            "{}:{}:{}: {}",
+            input.name,
+            line,
+            column,
+            parser::JSONParseError
+        ))
+    };
+
+    let mut output = vec![];
+    for path in &args.get {
+        let value = match root.at(path).map_err(located)? {
+            Some(value) => value,
+            None => {
+                return Err(ArgsParseError(format!(
+                    "{}: path not found: {}",
+                    input.name, path
+                )))
+            }
+        };
+        let value = value.value().map_err(located)?;
+        let value = substitute_env(&input.name, value, args)?;
+        check_control(&input.name, &value, args)?;
+        let mut value = reshape(&value, args);
+        if args.control == ControlPolicy::Strip {
+            value.to_mut().strip_control_chars();
+        }
+        output.extend(render(&input.name, &value, args)?);
+    }
+
+    Ok(output)
+}
+
+/// Applies the flags that change a document before it is printed, copying it
+/// only when one of them is given.
+fn reshape<'a>(value: &'a JSONValue, args: &Args) -> Cow<'a, JSONValue> {
+    let mut value = Cow::Borrowed(value);
+    if !args.pick.is_empty() {
+        value.to_mut().pick(&args.pick);
+    }
+    if !args.omit.is_empty() {
+        value.to_mut().omit(&args.omit);
+    }
+    if args.drop_nulls {
+        value.to_mut().drop_nulls();
+    }
+    if args.drop_empty {
+        value.to_mut().drop_empty();
+    }
+    if let Some(case) = args.keys {
+        value.to_mut().rename_keys(case);
+    }
+    if let Some(pattern) = &args.redact {
+        value.to_mut().redact(pattern);
+    }
+    if let Some(path) = &args.sort_array_by {
+        value.to_mut().sort_arrays_by(path);
+    }
+    if let (true, Some(seed)) = (args.anonymize, args.seed) {
+        value.to_mut().anonymize(seed);
+    }
+    if let Some(sample) = args.sample {
+        value.to_mut().sample_arrays(sample);
+    }
+    value
+}
+
+/// Serializes a value in the `--to` format, ready to be written to stdout.
+/// With `--raw-output`, strings are written as-is, without quotes or escapes.
+/// With `--explode`, each element of an array is written on its own, as one
+/// line of compact JSON. With `--tree`, `--describe`, `--type-histogram` or
+/// `--paths`, only the structure is written.
+fn render(name: &str, value: &JSONValue, args: &Args) -> Result<Vec<u8>, ArgsParseError> {
+    if args.tree {
+        return Ok(value.to_tree().into_bytes());
+    }
+    if args.describe {
+        return Ok(format!("{}\n", value.describe()).into_bytes());
+    }
+    if args.type_histogram {
+        return Ok(value.type_histogram().to_string().into_bytes());
+    }
+    if args.paths {
+        let mut output = String::new();
+        for path in value.paths(args.all_nodes) {
+            match args.pointer_paths {
+                true => output.push_str(&patch::pointer(&path)),
+                false => output.push_str(&path.to_string()),
+            }
+            output.push('\n');
+        }
+        return Ok(output.into_bytes());
+    }
+    if !args.explode {
+        return render_one(name, value, &args.style, args);
+    }
+
+    let values = match value {
+        JSONValue::Array(values) => values,
+        _ => {
+            return Err(ArgsParseError(format!(
+                "{}: --explode needs an array",
+                name
+            )))
+        }
+    };
+    let style = Style {
+        color: args.style.color,
+        sort_keys: args.style.sort_keys,
+        escape_unicode: args.style.escape_unicode,
+        escape_slash: args.style.escape_slash,
+        uppercase_hex: args.style.uppercase_hex,
+        non_finite: args.style.non_finite,
+        // Every value is a line of its own, however the last one ends.
+        crlf: args.style.crlf,
+        final_newline: true,
+        ..Style::compact()
+    };
+    let mut output = vec![];
+    for value in values {
+        output.extend(render_one(name, value, &style, args)?);
+    }
+    Ok(output)
+}
+
+fn render_one(
+    name: &str,
+    value: &JSONValue,
+    style: &Style,
+    args: &Args,
+) -> Result<Vec<u8>, ArgsParseError> {
+    match args.to {
+        Format::Json => match value {
+            JSONValue::String(val) if args.raw_output => {
+                let end = if style.final_newline {
+                    style.line_ending()
+                } else {
+                    ""
+                };
+                Ok(format!("{}{}", val, end).into_bytes())
+            }
+            _ => match value.try_to_styled_string(style) {
+                Ok(text) => Ok(text.into_bytes()),
+                Err(err) => Err(ArgsParseError(format!("{}: {}", name, err))),
+            },
+        },
+        Format::Bson => value
+            .to_bson(args.bson_root_array)
+            .map_err(|err| ArgsParseError(format!("{}: {}", name, err))),
+        Format::Xml => Ok(value.to_xml(style.indent).into_bytes()),
+    }
+}
+
+/// `--lossy`: says where invalid UTF-8 was replaced, since the document
+/// parsed is no longer exactly the one on disk.
+fn warn_replaced(name: &str, offsets: &[usize]) {
+    if offsets.is_empty() {
+        return;
+    }
+    let offsets: Vec<String> = offsets.iter().map(usize::to_string).collect();
+    eprintln!(
+        "{}: warning: replaced invalid UTF-8 at byte offsets {}",
+        name,
+        offsets.join(", ")
+    );
+}
+
+/// `--find-duplicates`: one line per key repeated within an object. The
+/// document must still parse, but repeated keys are not an error.
+fn find_duplicates(
+    input: &Input,
+    args: &Args,
+    parser: &mut Parser,
+) -> Result<Vec<u8>, ArgsParseError> {
+    let content = JSON::decode_input(input, &args.options)?;
+    warn_replaced(&input.name, &content.replaced);
+    parser
+        .parse_from_string(&content, &args.options)
+        .map_err(|err| ArgsParseError(format!("{}:{}", input.name, err)))?;
+
+    let duplicates = duplicates::find_duplicate_keys(&content).map_err(|offset| {
+        let (line, column) = get_line_column(&content, offset);
+        ArgsParseError(format!(
+            "{}:{}:{}: {}",
+            input.name,
+            line,
+            column,
+            parser::JSONParseError
+        ))
+    })?;
+    let mut output = String::new();
+    for duplicate in duplicates {
+        output.push_str(&format!("{}: {}\n", input.name, duplicate));
+    }
+    Ok(output.into_bytes())
+}
+
+/// `--strip-comments`: the input as written, less its comments and
+/// trailing commas, once what is left is found to be valid JSON.
+fn strip_comments(
+    input: &Input,
+    args: &Args,
+    parser: &mut Parser,
+) -> Result<Vec<u8>, ArgsParseError> {
+    let located = |err| ArgsParseError(format!("{}:{}", input.name, err));
+    let content = JSON::decode_input(input, &args.options)?;
+    warn_replaced(&input.name, &content.replaced);
+    let stripped = comments::strip_comments(&content).map_err(located)?;
+    parser
+        .parse_from_string(&stripped, &args.options)
+        .map_err(located)?;
+    Ok(stripped.into_bytes())
+}
+
+/// `--substitute-env`: fills in `${VAR}` placeholders, failing on unset
+/// variables unless `--allow-missing` is given.
+fn substitute_env<'a>(
+    name: &str,
+    value: &'a JSONValue,
+    args: &Args,
+) -> Result<Cow<'a, JSONValue>, ArgsParseError> {
+    if !args.substitute_env {
+        return Ok(Cow::Borrowed(value));
+    }
+
+    let mut value = value.clone();
+    let unresolved: Vec<String> = value
+        .substitute_env()
+        .iter()
+        .map(|unresolved| format!("{}: {}", name, unresolved))
+        .collect();
+    if unresolved.is_empty() || args.allow_missing {
+        Ok(Cow::Owned(value))
+    } else {
+        Err(ArgsParseError(unresolved.join("\n")))
+    }
+}
+
+/// `--control reject`: fails with one line per control character found.
+fn check_control(name: &str, value: &JSONValue, args: &Args) -> Result<(), ArgsParseError> {
+    if args.control != ControlPolicy::Reject {
+        return Ok(());
+    }
+
+    let found: Vec<String> = value
+        .control_chars()
+        .iter()
+        .map(|control| format!("{}: {}", name, control))
+        .collect();
+    if found.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgsParseError(found.join("\n")))
+    }
+}
+
+/// `--jtd`: reads the JSON Type Definition documents are checked against.
+fn load_schema(file: &str, args: &Args, parser: &mut Parser) -> Result<Schema, ArgsParseError> {
+    let input = Input::from_file(file, args.require_extension.then_some(".json"), args.mmap)?;
+    match parser.parse_from_input(input, &args.options)? {
+        JSONValue::Object(root) => Schema::new(root)
+            .map_err(|err| ArgsParseError(format!("{}: invalid schema: {}", file, err))),
+        _ => Err(ArgsParseError(format!(
+            "{}: invalid schema: schemas must be objects",
+            file
+        ))),
+    }
+}
+
+fn check_schema(name: &str, schema: &Schema, value: &JSONValue) -> Result<(), ArgsParseError> {
+    let errors: Vec<String> = schema
+        .validate(value)
+        .iter()
+        .map(|err| format!("{}: {}", name, err))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgsParseError(errors.join("\n")))
+    }
+}
+
+/// `--validate-dates` and `--check-formats`: fails with every string under
+/// a matching key that is not in the format asked for.
+fn check_formats(name: &str, value: &JSONValue, args: &Args) -> Result<(), ArgsParseError> {
+    let mut errors = vec![];
+    if let Some(keys) = &args.date_keys {
+        for (path, text) in value.invalid_dates(keys) {
+            errors.push(format!(
+                "{}: {}: {} is not an RFC 3339 timestamp",
+                name,
+                path,
+                JSONValue::String(text.to_string())
+            ));
+        }
+    }
+    for (format, keys) in &args.format_keys {
+        for (path, text) in value.invalid_formats(keys, *format) {
+            errors.push(format!(
+                "{}: {}: {} does not match format `{}`",
+                name,
+                path,
+                JSONValue::String(text.to_string()),
+                format
+            ));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgsParseError(errors.join("\n")))
+    }
+}
+
+/// `--from xml`: reads the input as XML instead of JSON.
+fn read_xml(input: &Input, args: &Args) -> Result<JSONValue, ArgsParseError> {
+    let content = JSON::decode_input(input, &args.options)?;
+    warn_replaced(&input.name, &content.replaced);
+    match JSON::from_xml(&content) {
+        Ok(json) => Ok(JSONValue::Object(json)),
+        Err(err) => {
+            let (line, column) = get_line_column(&content, err.offset);
+            Err(ArgsParseError(format!(
+                "{}:{}:{}: {}",
+                input.name, line, column, err
+            )))
+        }
+    }
+}
+
+/// `merge`: deep-merges the given documents left to right and prints the
+/// result. Every document must have an object at its root.
+fn merge(args: &Args) -> i32 {
+    let mut parser = Parser::default();
+    let mut merged: Option<JSON> = None;
+    for input in args.files.iter().flat_map(|file| read_inputs(file, args)) {
+        let result = input.and_then(|input| {
+            let name = input.name.clone();
+            match parser.parse_from_input(input, &args.options)? {
+                JSONValue::Object(json) => Ok(json),
+                _ => Err(ArgsParseError(format!(
+                    "{}: only objects can be merged",
+                    name
+                ))),
+            }
+        });
+
+        match (result, merged.as_mut()) {
+            (Err(err), _) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+            (Ok(json), Some(merged)) => merged.deep_merge(json, args.arrays),
+            (Ok(json), None) => merged = Some(json),
+        }
+    }
+
+    if let Some(merged) = merged {
+        match merged.try_to_styled_string(&args.style) {
+            Ok(text) => print!("{}", text),
+            Err(err) => {
+                eprintln!("json-parser: {}", err);
+                return 1;
+            }
+        }
+    }
+    0
+}
+
+/// `codegen`: prints TypeScript interfaces describing the samples, which are
+/// treated as examples of one type.
+fn codegen(args: &Args) -> i32 {
+    let mut parser = Parser::default();
+    let mut typescript = TypeScript::new();
+    for input in args.files.iter().flat_map(|file| read_inputs(file, args)) {
+        match input.and_then(|input| parser.parse_from_input(input, &args.options)) {
+            Ok(value) => typescript.add_sample(&value),
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        }
+    }
+
+    print!("{}", typescript.render("Root"));
+    0
+}
+
+/// `template`: prints the template with its `{{path}}` placeholders filled in
+/// from the `--data` document, failing if any finds nothing.
+fn template(args: &Args) -> i32 {
+    let mut parser = Parser::default();
+    let documents = read_document(&mut parser, &args.files[0], args).and_then(|template| {
+        let (_, data) = read_document(&mut parser, &args.data, args)?;
+        Ok((template, data))
+    });
+    let ((name, mut template), data) = match documents {
+        Ok(documents) => documents,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    let unfilled = template.fill_template(&data);
+    for unfilled in &unfilled {
+        eprintln!("{}: {}", name, unfilled);
+    }
+    if !unfilled.is_empty() {
+        return 1;
+    }
+    match template.try_to_styled_string(&args.style) {
+        Ok(text) => print!("{}", text),
+        Err(err) => {
+            eprintln!("{}: {}", name, err);
+            return 1;
+        }
+    }
+    0
+}
+
+/// `browse`: opens the document in an interactive tree.
+#[cfg(feature = "browse")]
+fn browse(args: &Args) -> i32 {
+    use std::io::IsTerminal;
+
+    if !io::stdout().is_terminal() {
+        eprintln!("json-parser: browse needs a terminal");
+        return 1;
+    }
+    let result =
+        read_document(&mut Parser::default(), &args.files[0], args).and_then(|(name, value)| {
+            browse::run(&name, &value).map_err(|err| ArgsParseError(format!("{}: {}", name, err)))
+        });
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+#[cfg(not(feature = "browse"))]
+fn browse(_: &Args) -> i32 {
+    eprintln!("json-parser: browsing is not enabled, rebuild with `--features browse`");
+    1
+}
+
+/// `diff`: prints one line per difference between two documents, or with
+/// `--diff-format json` an array of them. Exits with
+/// 0 when they match, 1 when they differ and 2 when either cannot be read,
+/// as diff(1) does.
+fn diff(args: &Args) -> i32 {
+    let mut parser = Parser::default();
+    let mut documents = vec![];
+    for input in args.files.iter().flat_map(|file| read_inputs(file, args)) {
+        match input.and_then(|input| parser.parse_from_input(input, &args.options)) {
+            Ok(value) => documents.push(value),
             Err(err) => {
-                status_code = 1;
                 eprintln!("{}", err);
+                return 2;
+            }
+        }
+    }
+
+    let (old, new) = match documents.as_slice() {
+        [old, new] => (old, new),
+        _ => {
+            eprintln!("json-parser: diff needs exactly two documents");
+            return 2;
+        }
+    };
+    let changes = old.diff_with_tolerance(new, args.tolerance);
+    let rendered = match args.diff_format {
+        DiffFormat::Text => render_changes(&changes, &args.style),
+        DiffFormat::Json => {
+            let changes = changes.iter().map(|change| change.to_json().into());
+            JSONValue::Array(changes.collect()).try_to_styled_string(&args.style)
+        }
+    };
+    match rendered {
+        Ok(text) => print!("{}", text),
+        Err(err) => {
+            eprintln!("json-parser: {}", err);
+            return 2;
+        }
+    }
+    if changes.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+/// `split`: writes the elements of the document's top-level array into
+/// `<out-dir>/<name>-0001.json` and onwards, `--chunk-size` elements per
+/// file, or with `--by-key` each member of its top-level object into
+/// `<out-dir>/<key>.json`, and prints the path of each file written.
+fn split(args: &Args) -> i32 {
+    let file = &args.files[0];
+    let mut parser = Parser::default();
+    let values = match Input::from_file(file, args.extension(), args.mmap)
+        .and_then(|input| parser.parse_from_input(input, &args.options))
+    {
+        Ok(JSONValue::Object(json)) if args.by_key => return split_by_key(file, &json, args),
+        Ok(_) if args.by_key => {
+            eprintln!("{}: only objects can be split by key", file);
+            return 1;
+        }
+        Ok(JSONValue::Array(values)) => values,
+        Ok(_) => {
+            eprintln!("{}: only arrays can be split", file);
+            return 1;
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    let out_dir = std::path::Path::new(&args.out_dir);
+    if let Err(err) = fs::create_dir_all(out_dir) {
+        eprintln!("{}: {}", args.out_dir, err);
+        return 1;
+    }
+
+    let stem = std::path::Path::new(file)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.split('.').next().unwrap_or(name))
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or("part");
+    let chunks = values.chunks(args.chunk_size);
+    let width = chunks.len().to_string().len().max(4);
+    let style = Style {
+        color: false,
+        ..args.style.clone()
+    };
+    let mut texts = Vec::new();
+    for chunk in chunks {
+        match JSONValue::Array(chunk.to_vec()).try_to_styled_string(&style) {
+            Ok(text) => texts.push(text),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                return 1;
             }
-            Ok(json) => {
-                println!("{}", json);
+        }
+    }
+    for (idx, text) in texts.into_iter().enumerate() {
+        let path = out_dir.join(format!("{}-{:0width$}.json", stem, idx + 1, width = width));
+        if let Err(err) = fs::write(&path, text) {
+            eprintln!("{}: {}", path.display(), err);
+            return 1;
+        }
+        println!("{}", path.display());
+    }
+    0
+}
+
+/// `split --by-key`: writes each member in key order. Every key is checked
+/// before anything is written, since not all can name a file: bytes other
+/// than letters, digits, `-`, `_` and `.` are written `%XX`, as is a leading
+/// `.`, the empty key is refused, and so are keys differing only in case,
+/// which would name the same file on some systems.
+fn split_by_key(file: &str, json: &JSON, args: &Args) -> i32 {
+    let mut members: Vec<(&str, &JSONValue)> = json.iter().collect();
+    members.sort_by_key(|(key, _)| *key);
+
+    let mut names: HashMap<String, &str> = HashMap::new();
+    for &(key, _) in &members {
+        if key.is_empty() {
+            eprintln!("{}: the empty key cannot name a file", file);
+            return 1;
+        }
+        if let Some(other) = names.insert(key_file_name(key).to_lowercase(), key) {
+            eprintln!(
+                "{}: keys {} and {} would name the same file",
+                file,
+                JSONValue::String(other.to_string()),
+                JSONValue::String(key.to_string())
+            );
+            return 1;
+        }
+    }
+
+    let out_dir = std::path::Path::new(&args.out_dir);
+    if let Err(err) = fs::create_dir_all(out_dir) {
+        eprintln!("{}: {}", args.out_dir, err);
+        return 1;
+    }
+    let style = Style {
+        color: false,
+        ..args.style.clone()
+    };
+    let mut texts = Vec::new();
+    for (_, value) in &members {
+        match value.try_to_styled_string(&style) {
+            Ok(text) => texts.push(text),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                return 1;
+            }
+        }
+    }
+    for ((key, _), text) in members.iter().zip(texts) {
+        let path = out_dir.join(format!("{}.json", key_file_name(key)));
+        if let Err(err) = fs::write(&path, text) {
+            eprintln!("{}: {}", path.display(), err);
+            return 1;
+        }
+        println!("{}", path.display());
+    }
+    0
+}
+
+/// `key` made safe to use as a file name, as `split_by_key` describes.
+fn key_file_name(key: &str) -> String {
+    let mut name = String::new();
+    for (idx, byte) in key.bytes().enumerate() {
+        match byte {
+            b'.' if idx == 0 => name.push_str("%2E"),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => name.push(byte as char),
+            _ => name.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    name
+}
+
+/// `hash`: prints the SHA-256 of each document's canonical form next to its
+/// name, as sha256sum(1) does, so documents differing only in formatting or
+/// key order hash the same.
+#[cfg(feature = "hash")]
+fn hash(args: &Args) -> i32 {
+    let mut parser = Parser::default();
+    let mut status = 0;
+    for input in args.files.iter().flat_map(|file| read_inputs(file, args)) {
+        let result = input.and_then(|input| {
+            let name = input.name.clone();
+            Ok((parser.parse_from_input(input, &args.options)?, name))
+        });
+        match result {
+            Ok((value, name)) => match value.canonical_hash() {
+                Ok(hash) => println!("{}  {}", hash, name),
+                Err(err) => {
+                    eprintln!("{}: {}", name, err);
+                    status = 1;
+                }
+            },
+            Err(err) => {
+                eprintln!("{}", err);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+#[cfg(not(feature = "hash"))]
+fn hash(_: &Args) -> i32 {
+    eprintln!("json-parser: hashing is not enabled, rebuild with `--features hash`");
+    1
+}
+
+/// `grep`: prints `path: value` for every string matching the pattern, and
+/// with `--include-keys` every member whose key matches, each line led by
+/// the file name when there are several files. Exits with 0 when anything
+/// matched, 1 when nothing did and 2 on errors, as grep(1) does.
+fn grep(args: &Args) -> i32 {
+    let pattern = args.grep_pattern.as_deref().unwrap_or_default();
+    let pattern = match KeyPattern::new(pattern, args.grep_ignore_case) {
+        Ok(pattern) => pattern,
+        Err(err) => {
+            eprintln!("json-parser: grep: {}", err);
+            return 2;
+        }
+    };
+
+    let mut parser = Parser::default();
+    let (mut matched, mut failed) = (false, false);
+    for input in args.files.iter().flat_map(|file| read_inputs(file, args)) {
+        let result = input.and_then(|input| {
+            let name = input.name.clone();
+            Ok((parser.parse_from_input(input, &args.options)?, name))
+        });
+        let (value, name) = match result {
+            Ok(found) => found,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+                continue;
             }
         };
+
+        for (path, value) in value.grep(&pattern, args.grep_keys) {
+            matched = true;
+            let path = match path.to_string() {
+                path if path.is_empty() => String::from("<root>"),
+                path => path,
+            };
+            let value = match value.try_to_styled_string(&Style {
+                compact: true,
+                final_newline: false,
+                ..args.style.clone()
+            }) {
+                Ok(value) => value,
+                Err(err) => {
+                    eprintln!("{}: {}", name, err);
+                    failed = true;
+                    continue;
+                }
+            };
+            if args.files.len() > 1 {
+                println!("{}: {}: {}", name, path, value);
+            } else {
+                println!("{}: {}", path, value);
+            }
+        }
     }
 
-    exit(status_code);
+    match (failed, matched) {
+        (true, _) => 2,
+        (false, true) => 0,
+        (false, false) => 1,
+    }
+}
+
+/// The one document `file` holds, with the name errors should give it.
+fn read_document(
+    parser: &mut Parser,
+    file: &str,
+    args: &Args,
+) -> Result<(String, JSONValue), ArgsParseError> {
+    let mut inputs = read_inputs(file, args);
+    if inputs.len() != 1 {
+        return Err(ArgsParseError(format!("{}: expected one document", file)));
+    }
+    let input = inputs.remove(0)?;
+    let name = input.name.clone();
+    Ok((name, parser.parse_from_input(input, &args.options)?))
+}
+
+/// The documents `file` names: one for a file, a URL or `-` for stdin, and
+/// one per entry of an archive.
+fn read_inputs(file: &str, args: &Args) -> Vec<Result<Input, ArgsParseError>> {
+    if file == "-" {
+        return vec![Input::from_stdin(args.decompress)];
+    }
+    if http::is_url(file) {
+        return vec![Input::from_url(
+            file,
+            args.timeout,
+            args.options.limits.max_bytes,
+        )];
+    }
+    if !archive::is_archive(file) {
+        return vec![Input::from_file(file, args.extension(), args.mmap)];
+    }
+
+    match Input::from_archive(file, args.options.limits.max_bytes) {
+        Ok(inputs) => inputs
+            .into_iter()
+            .filter(|input| !args.is_ignored(&input.name[file.len() + 1..]))
+            .map(Ok)
+            .collect(),
+        Err(err) => vec![Err(err)],
+    }
 }
@@ -0,0 +1,60 @@
+use crate::parser::{JSONValue, JSON};
+
+/// How arrays found at the same path in both documents are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MergeStrategy {
+    /// The later document's array wins.
+    #[default]
+    Replace,
+    /// Elements of the later array are appended to the earlier one.
+    Append,
+    /// Like `Append`, but elements already present are skipped.
+    Union,
+}
+
+impl MergeStrategy {
+    pub fn from_name(name: &str) -> Option<MergeStrategy> {
+        match name {
+            "replace" => Some(MergeStrategy::Replace),
+            "append" => Some(MergeStrategy::Append),
+            "union" => Some(MergeStrategy::Union),
+            _ => None,
+        }
+    }
+}
+
+impl JSON {
+    /// Merges `other` into `self`. Objects present in both are merged
+    /// recursively, arrays are combined according to `strategy`, and any
+    /// other value in `other` replaces the one in `self`.
+    pub fn deep_merge(&mut self, other: JSON, strategy: MergeStrategy) {
+        for (key, value) in other.object {
+            match self.object.get_mut(&key) {
+                Some(existing) => merge_value(existing, value, strategy),
+                None => {
+                    self.object.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+fn merge_value(existing: &mut JSONValue, value: JSONValue, strategy: MergeStrategy) {
+    match (existing, value) {
+        (JSONValue::Object(existing), JSONValue::Object(value)) => {
+            existing.deep_merge(value, strategy)
+        }
+        (JSONValue::Array(existing), JSONValue::Array(values)) => match strategy {
+            MergeStrategy::Replace => *existing = values,
+            MergeStrategy::Append => existing.extend(values),
+            MergeStrategy::Union => {
+                for value in values {
+                    if !existing.contains(&value) {
+                        existing.push(value);
+                    }
+                }
+            }
+        },
+        (existing, value) => *existing = value,
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    lexer::{is_whitespace, Cursor},
+    parser::{Failure, JSONValue, ParseOptions, JSON},
+};
+use std::thread;
+
+/// Parses a root array by splitting it at its top-level commas and handing
+/// contiguous runs of elements to `threads` worker threads. The results are
+/// concatenated in document order.
+///
+/// Returns `None` when the array's outline is malformed, leaving the serial
+/// parser to find and report the error. Otherwise an error carries the byte
+/// offset of the failure within `content`. Every worker watches the options'
+/// cancellation token and deadline.
+pub fn parse_array(
+    content: &str,
+    options: &ParseOptions,
+) -> Option<Result<Vec<JSONValue>, Failure>> {
+    let spans = element_spans(content)?;
+    let chunk_len = spans.len().div_ceil(options.threads).max(1);
+
+    let results: Vec<Result<Vec<JSONValue>, Failure>> = thread::scope(|scope| {
+        let workers: Vec<_> = spans
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(|| parse_elements(content, chunk, options)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or(Err(Failure::At(0))))
+            .collect()
+    });
+
+    let mut array = Vec::with_capacity(spans.len());
+    for result in results {
+        match result {
+            Ok(mut elements) => array.append(&mut elements),
+            Err(offset) => return Some(Err(offset)),
+        }
+    }
+
+    Some(Ok(array))
+}
+
+/// Parses a worker's elements with one cursor, so they share its key
+/// interner as the elements of a serially parsed array do.
+fn parse_elements(
+    content: &str,
+    spans: &[(usize, usize)],
+    options: &ParseOptions,
+) -> Result<Vec<JSONValue>, Failure> {
+    let mut elements = Vec::with_capacity(spans.len());
+    let mut cursor = Cursor::new(content);
+    cursor.watch = options.watch();
+    cursor.control = options.control;
+    cursor.lenient = options.lenient;
+    for &(start, end) in spans {
+        cursor.pos = start;
+        match JSON::parse_value(&mut cursor) {
+            Ok(value) => elements.push(value),
+            Err(_) => return Err(cursor.failure()),
+        }
+
+        cursor.skip_whitespace();
+        if cursor.pos != end {
+            return Err(Failure::At(cursor.pos));
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Finds the byte range of every element of the root array with a single
+/// pass that only tracks strings and bracket depth.
+fn element_spans(content: &str) -> Option<Vec<(usize, usize)>> {
+    let bytes = content.as_bytes();
+    let mut spans = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 1;
+
+    for (idx, &byte) in bytes.iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    if byte != b']' || idx != bytes.len() - 1 {
+                        return None;
+                    }
+                    spans.push((start, idx));
+                }
+            }
+            b',' if depth == 1 => {
+                spans.push((start, idx));
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 {
+        return None;
+    }
+
+    let is_blank =
+        |&(start, end): &(usize, usize)| bytes[start..end].iter().all(|&b| is_whitespace(b));
+    match spans.as_slice() {
+        [only] if is_blank(only) => Some(vec![]),
+        _ if spans.iter().any(is_blank) => None,
+        _ => Some(spans),
+    }
+}
@@ -1,9 +1,38 @@
-use std::{collections::HashMap, fmt::Display, fs, iter::Peekable};
-
-#[derive(Debug)]
-enum JSONValue {
+#[cfg(feature = "cli")]
+use crate::input::Input;
+use crate::{
+    cancel::{CancelToken, Interrupted, Watch},
+    compression::{decompress, Compression},
+    control::{self, ControlPolicy},
+    intern::{Interner, SharedInterner},
+    lexer::Cursor,
+    limits::{LimitError, Limits},
+    parallel,
+    printer::{NonFinitePolicy, Style},
+    trace::{self, Node},
+};
+use std::{borrow::Cow, collections::HashMap, fmt::Display, ops::Deref, sync::Arc, time::Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JSONValue {
     String(String),
     Number(i32),
+    /// An integer too large for `Number`.
+    #[cfg(feature = "i128")]
+    Int128(i128),
+    /// An integer above `i128::MAX`, too large even for `Int128`.
+    #[cfg(feature = "i128")]
+    UInt128(u128),
+    /// A number with a fraction or exponent, or an integer too large for
+    /// any of the integer variants, kept exactly as written rather than
+    /// rounded to a float.
+    #[cfg(feature = "bigdecimal")]
+    Decimal(bigdecimal::BigDecimal),
+    /// NaN or an infinity, which JSON has no number for. Only made from an
+    /// `f64` or, when lenient, read from `NaN`, `Infinity` or `-Infinity`;
+    /// `Style::non_finite` decides how one is written.
+    #[cfg(feature = "bigdecimal")]
+    NonFinite(f64),
     Bool(bool),
     Null,
     Array(Vec<JSONValue>),
@@ -12,39 +41,99 @@ enum JSONValue {
 
 impl Display for JSONValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self {
-            JSONValue::String(val) => write!(f, "\"{}\"", val),
-            JSONValue::Number(val) => write!(f, "{}", val),
-            JSONValue::Bool(val) => write!(f, "{}", val),
-            JSONValue::Null => write!(f, "null"),
-            JSONValue::Array(vals) => {
-                let mut str_val = String::new();
-                for (idx, val) in vals.iter().enumerate() {
-                    str_val.push_str(&format!(
-                        "{}{}",
-                        val,
-                        if idx < vals.len() - 1 { "," } else { "" }
-                    ));
-                }
-                write!(f, "[{}]", str_val)
-            }
-            JSONValue::Object(json) => write!(f, "{}", json),
-        }
+        let style = if f.alternate() {
+            Style::default()
+        } else {
+            Style::compact()
+        };
+        // Formatting cannot fail, so NaN and the infinities are written as
+        // JavaScript writes them.
+        let style = Style {
+            non_finite: NonFinitePolicy::Literal,
+            ..style
+        };
+        self.write_styled(f, &style)
     }
 }
 
-#[derive(Debug)]
+/// Object keys are interned per document, so repeated keys share one
+/// allocation.
+pub type Key = Arc<str>;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct JSON {
-    object: HashMap<String, JSONValue>,
+    pub(crate) object: HashMap<Key, JSONValue>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    /// Tolerates trailing commas before a closing `]` or `}` and, with the
+    /// `bigdecimal` feature, reads `NaN`, `Infinity` and `-Infinity`.
+    pub lenient: bool,
+    pub limits: Limits,
+    /// Threads used to parse the elements of a root array; 0 or 1 parses
+    /// serially.
+    pub threads: usize,
+    /// Aborts the parse once cancelled.
+    pub cancel: Option<CancelToken>,
+    /// Aborts the parse once passed.
+    pub deadline: Option<Instant>,
+    /// Applied to control characters in strings and keys.
+    pub control: ControlPolicy,
+    /// Replaces invalid UTF-8 with U+FFFD instead of failing.
+    pub lossy: bool,
+}
+
+/// The text of an input, ready for parsing.
+pub struct Decoded<'a> {
+    pub text: Cow<'a, str>,
+    /// Byte offsets, in the decompressed input, of invalid UTF-8 sequences
+    /// replaced under `ParseOptions::lossy`.
+    pub replaced: Vec<usize>,
 }
 
-pub struct ArgsParseError(String);
+impl Deref for Decoded<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl ParseOptions {
+    pub(crate) fn watch(&self) -> Watch {
+        Watch::new(self.cancel.clone(), self.deadline)
+    }
+}
+
+/// How deeply arrays and objects may nest. The parser recurses once per
+/// level, so without a cap a document of nothing but `[` would overflow the
+/// stack; this one leaves ample room even on the 2 MiB stacks of the
+/// threads used by `--threads`. Documents nested deeper fail to parse with
+/// an error saying so.
+pub const MAX_DEPTH: usize = 512;
+
+/// Why a parse failed, with the byte offset the parser stopped on.
+#[derive(Clone, Copy)]
+pub(crate) enum Failure {
+    At(usize),
+    Interrupted(Interrupted, usize),
+    TooDeep(usize),
+    ControlChar(char, usize),
+    Limit(LimitError, usize),
+}
+
+#[derive(Debug)]
+pub struct ArgsParseError(pub String);
 impl Display for ArgsParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+impl std::error::Error for ArgsParseError {}
+
 pub struct JSONParseError;
 impl Display for JSONParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -52,366 +141,765 @@ impl Display for JSONParseError {
     }
 }
 
-impl JSON {
-    pub fn parse_from_file(file_name: &String) -> Result<JSON, ArgsParseError> {
-        if !file_name.ends_with(".json") {
-            return Err(ArgsParseError(format!("{} is not a JSON file", file_name)));
+/// Parses document after document while holding on to its buffers: the key
+/// interner's table and the scratch string used to unescape keys keep their
+/// capacity between calls, so a run over thousands of small files does not
+/// reallocate them for every one.
+///
+/// Parsing never panics or overflows the stack: any input, however
+/// malformed or deeply nested, comes back as an `Err`. The targets under
+/// `fuzz/` exercise this.
+#[derive(Default)]
+pub struct Parser {
+    interner: Interner,
+    scratch: String,
+    shared: Option<SharedInterner>,
+    replaced: Vec<usize>,
+}
+
+impl Parser {
+    /// A parser whose keys are interned in `shared` rather than per document.
+    pub fn with_shared_interner(shared: SharedInterner) -> Parser {
+        Parser {
+            shared: Some(shared),
+            ..Parser::default()
         }
+    }
+
+    /// Byte offsets of the invalid UTF-8 sequences replaced, under
+    /// `ParseOptions::lossy`, in the input last given to `parse_from_input`.
+    pub fn replaced(&self) -> &[usize] {
+        &self.replaced
+    }
+
+    #[cfg(feature = "cli")]
+    pub fn parse_from_input(
+        &mut self,
+        input: Input,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        let content = JSON::decode_input(&input, options)?;
+        self.parse_decoded(&input.name, content, options)
+    }
 
-        match fs::read_to_string(file_name) {
-            Ok(content) => JSON::parse_from_string(content),
-            Err(_) => Err(ArgsParseError(format!("{} does not exist!", file_name))),
+    /// Reads and parses a file, whatever it is named, decompressing it if it
+    /// is gzip or zstd compressed.
+    pub fn parse_from_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let span = trace::read(&name);
+        let result = std::fs::read(path);
+        span.record_result(&result, Vec::len);
+        let bytes = match result {
+            Ok(bytes) => bytes,
+            Err(err) => return Err(ArgsParseError(format!("{}: {}", name, err))),
+        };
+        let compression = match Compression::from_file_name(&name) {
+            Compression::None => Compression::from_magic(&bytes),
+            compression => compression,
+        };
+
+        let content = JSON::decode(&name, &bytes, compression, options)?;
+        self.parse_decoded(&name, content, options)
+    }
+
+    fn parse_decoded(
+        &mut self,
+        name: &str,
+        content: Decoded,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        self.replaced = content.replaced.clone();
+        match self.parse_from_string(&content, options) {
+            Ok(value) => Ok(value),
+            Err(err) => Err(ArgsParseError(format!("{}:{}", name, err))),
         }
     }
 
-    fn parse_from_string(content: String) -> Result<JSON, ArgsParseError> {
-        match JSON::parse(format!("{}", content.trim())) {
-            Ok(json) => Ok(json),
-            Err(err) => Err(ArgsParseError(format!("{}", err))),
+    /// Parses a document whose root is an object or an array. Errors are
+    /// reported as `line:col: message`, pointing at the character the parser
+    /// stopped on.
+    pub fn parse_from_string(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        let trimmed = content.trim_start();
+        let leading = content.len() - trimmed.len();
+
+        match self.parse(trimmed.trim_end(), options) {
+            Ok(value) => Ok(value),
+            Err(failure) => Err(describe(content, leading, failure)),
         }
     }
 
-    fn parse(content: String) -> Result<JSON, JSONParseError> {
-        if !content.starts_with('{') || !content.ends_with('}') {
-            Err(JSONParseError)
+    /// Reads every document in an input: the whole of it when it is one
+    /// document, otherwise one per non-blank line, as in NDJSON. When the
+    /// first line is not a document of its own either, the input is taken to
+    /// be a single malformed document and the error says where it breaks.
+    #[cfg(feature = "cli")]
+    pub fn parse_documents_from_input(
+        &mut self,
+        input: Input,
+        options: &ParseOptions,
+    ) -> Result<Vec<JSONValue>, ArgsParseError> {
+        let content = JSON::decode_input(&input, options)?;
+        self.replaced = content.replaced.clone();
+        let whole = match self.parse_from_string(&content, options) {
+            Ok(value) => return Ok(vec![value]),
+            Err(err) => ArgsParseError(format!("{}:{}", input.name, err)),
+        };
+
+        let mut values = vec![];
+        for (_, result) in self.parse_lines(&content, options) {
+            match result {
+                Ok(value) => values.push(value),
+                Err(_) if values.is_empty() => return Err(whole),
+                Err(err) => return Err(ArgsParseError(format!("{}:{}", input.name, err))),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Parses each non-blank line of NDJSON text as a document of its own,
+    /// yielding the 1-based line number with each result. A bad line does not
+    /// stop the ones after it, so every error in a file can be reported in one
+    /// pass. Errors are `line:col: message`, as with `parse_from_string`.
+    pub fn parse_lines<'p>(
+        &'p mut self,
+        content: &'p str,
+        options: &'p ParseOptions,
+    ) -> impl Iterator<Item = (usize, Result<JSONValue, ArgsParseError>)> + 'p {
+        content
+            .split_inclusive('\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(move |(idx, line)| {
+                let trimmed = line.trim_start();
+                let leading = line.len() - trimmed.len();
+                let result = self.parse(trimmed.trim_end(), options).map_err(|failure| {
+                    let (offset, message) = explain(failure);
+                    let (_, column) = get_line_column(line, leading + offset);
+                    ArgsParseError(format!("{}:{}: {}", idx + 1, column, message))
+                });
+                (idx + 1, result)
+            })
+    }
+
+    fn parse(&mut self, content: &str, options: &ParseOptions) -> Result<JSONValue, Failure> {
+        let span = trace::parse(content.len());
+        let result = self.parse_document(content, options);
+        match &result {
+            Ok(value) => span.record_nodes(Node::Value(value)),
+            Err(failure) => span.error(|| {
+                let (offset, message) = explain(*failure);
+                format!("{} at byte {}", message, offset)
+            }),
+        }
+        result
+    }
+
+    fn parse_document(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, Failure> {
+        if let Err(err) = options.limits.check_bytes(content.len()) {
+            return Err(Failure::Limit(err, 0));
+        }
+        // Workers count their own slices, so counts over the whole document
+        // need the single-threaded parser.
+        if options.threads > 1 && content.starts_with('[') && !options.limits.checks_structure() {
+            if let Some(result) = parallel::parse_array(content, options) {
+                return result.map(JSONValue::Array);
+            }
+        }
+
+        let shared = self.shared.clone();
+        let mut shared = shared.as_ref().map(SharedInterner::lock);
+        let interner = match shared.as_deref_mut() {
+            Some(interner) => std::mem::take(interner),
+            None => std::mem::take(&mut self.interner),
+        };
+        let scratch = std::mem::take(&mut self.scratch);
+        let mut cursor = Cursor::with_buffers(content, interner, scratch);
+        cursor.watch = options.watch();
+        cursor.control = options.control;
+        cursor.lenient = options.lenient;
+        cursor.limits = options.limits.clone();
+        let root = cursor.next();
+        let value = match root {
+            Some(b'{' | b'[') => cursor
+                .count_value()
+                .and_then(|()| cursor.limit(options.limits.check_depth(1))),
+            _ => Err(JSONParseError),
+        };
+        let value = value.and_then(|()| match root {
+            Some(b'{') => JSON::parse_object_value(&mut cursor).map(JSONValue::Object),
+            _ => JSON::parse_array_value(&mut cursor).map(JSONValue::Array),
+        });
+
+        let result = match value {
+            Ok(_) if cursor.remaining() > 0 => Err(Failure::At(cursor.pos)),
+            Ok(value) => Ok(value),
+            Err(_) => Err(cursor.failure()),
+        };
+
+        let (interner, scratch) = cursor.into_buffers();
+        self.scratch = scratch;
+        match shared.as_deref_mut() {
+            Some(shared) => *shared = interner,
+            None => {
+                self.interner = interner;
+                self.interner.clear();
+            }
+        }
+        result
+    }
+}
+
+impl JSON {
+    /// Decompresses an input, checks it against the configured limits and
+    /// returns its text ready for parsing. Uncompressed input is borrowed
+    /// rather than copied.
+    #[cfg(feature = "cli")]
+    pub fn decode_input<'a>(
+        input: &'a Input,
+        options: &ParseOptions,
+    ) -> Result<Decoded<'a>, ArgsParseError> {
+        JSON::decode(&input.name, &input.bytes, input.compression, options)
+    }
+
+    fn decode<'a>(
+        name: &str,
+        bytes: &'a [u8],
+        compression: Compression,
+        options: &ParseOptions,
+    ) -> Result<Decoded<'a>, ArgsParseError> {
+        let span = trace::decode(name, bytes.len());
+        let result = JSON::decode_bytes(name, bytes, compression, options);
+        span.record_result(&result, |decoded| decoded.len());
+        result
+    }
+
+    fn decode_bytes<'a>(
+        name: &str,
+        bytes: &'a [u8],
+        compression: Compression,
+        options: &ParseOptions,
+    ) -> Result<Decoded<'a>, ArgsParseError> {
+        let limits = &options.limits;
+        if let Err(err) = limits.check_bytes(bytes.len()) {
+            return Err(ArgsParseError(format!("{}: {}", name, err)));
+        }
+
+        let mut replaced = vec![];
+        let content = if compression == Compression::None {
+            match std::str::from_utf8(bytes) {
+                Ok(content) => Cow::Borrowed(content),
+                Err(_) if options.lossy => Cow::Owned(decode_lossy(bytes, &mut replaced)),
+                Err(_) => return Err(ArgsParseError(format!("{} is not valid UTF-8", name))),
+            }
         } else {
-            let mut json = JSON {
-                object: HashMap::new(),
+            let bytes = match decompress(bytes, compression, limits.max_bytes) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    return Err(ArgsParseError(format!(
+                        "{}: failed to decompress: {}",
+                        name, err
+                    )))
+                }
             };
+            if let Err(err) = limits.check_bytes(bytes.len()) {
+                return Err(ArgsParseError(format!("{}: {}", name, err)));
+            }
 
-            let mut tokens = content
-                .chars()
-                .skip(1)
-                .collect::<Vec<char>>()
-                .into_iter()
-                .peekable();
-
-            while tokens.len() > 1 {
-                match JSON::get_pair(&mut tokens) {
-                    Ok((key, value)) => {
-                        json.object.insert(key, value);
-                        match JSON::skip_whitspace(&mut tokens) {
-                            Some(ch) => match ch {
-                                '}' => {
-                                    return Ok(json);
-                                }
-                                ',' => {
-                                    while let Some(token) = tokens.peek() {
-                                        if *token == '}' {
-                                            return Err(JSONParseError);
-                                        }
-                                        if token.is_whitespace() {
-                                            tokens.next().unwrap();
-                                        } else {
-                                            break;
-                                        }
-                                    }
-                                }
-                                _ => return Err(JSONParseError),
-                            },
-                            None => return Err(JSONParseError),
-                        }
-                    }
-                    Err(err) => {
-                        return Err(err);
-                    }
+            match String::from_utf8(bytes) {
+                Ok(content) => Cow::Owned(content),
+                Err(err) if options.lossy => {
+                    Cow::Owned(decode_lossy(err.as_bytes(), &mut replaced))
                 }
+                Err(_) => return Err(ArgsParseError(format!("{} is not valid UTF-8", name))),
             }
+        };
+        Ok(Decoded {
+            text: content,
+            replaced,
+        })
+    }
 
-            Ok(json)
-        }
+    /// Reads and parses a single file with a fresh `Parser`.
+    pub fn parse_from_file(
+        path: impl AsRef<std::path::Path>,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        Parser::default().parse_from_file(path, options)
     }
 
-    fn get_pair<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<(String, JSONValue), JSONParseError> {
-        let key = match JSON::parse_key(tokens) {
-            Ok(key) => key,
-            Err(err) => return Err(err),
-        };
+    /// Parses a single document with a fresh `Parser`.
+    pub fn parse_from_string(
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, ArgsParseError> {
+        Parser::default().parse_from_string(content, options)
+    }
 
-        if let Some(err) = JSON::skip_colons(tokens) {
+    fn get_pair(cursor: &mut Cursor) -> Result<(Key, JSONValue), JSONParseError> {
+        let key = JSON::parse_key(cursor)?;
+
+        if let Some(err) = JSON::skip_colons(cursor) {
             return Err(err);
         }
 
-        let value = match JSON::parse_value(tokens) {
-            Ok(value) => value,
-            Err(err) => return Err(err),
-        };
+        let value = JSON::parse_value(cursor)?;
 
         Ok((key, value))
     }
 
-    fn skip_whitspace<I: Iterator<Item = char>>(tokens: &mut Peekable<I>) -> Option<char> {
-        while let Some(ch) = tokens.next() {
-            if !ch.is_whitespace() {
-                return Some(ch);
+    /// Keys without escapes are looked up in the interner straight from the
+    /// source text, so a repeated key costs no allocation.
+    pub(crate) fn parse_key(cursor: &mut Cursor) -> Result<Key, JSONParseError> {
+        if cursor.next_non_whitespace() != Some(b'"') {
+            return Err(JSONParseError);
+        }
+
+        let start = cursor.pos;
+        if cursor.skip_to_quote_or_escape() && cursor.peek() == Some(b'"') {
+            let key = cursor.slice(start, cursor.pos);
+            let plain = match cursor.control {
+                ControlPolicy::Preserve => key.bytes().all(|byte| byte >= 0x20),
+                _ => !key.chars().any(|ch| ch < ' ' || control::is_flagged(ch)),
+            };
+            if plain {
+                cursor.next();
+                cursor.count_string(start, true)?;
+                return Ok(cursor.interner.intern(key));
             }
         }
 
-        None
+        cursor.pos = start;
+        let mut scratch = std::mem::take(&mut cursor.scratch);
+        scratch.clear();
+        let key = JSON::decode_string_into(cursor, &mut scratch)
+            .and_then(|()| cursor.count_string(start, true))
+            .map(|()| cursor.interner.intern(&scratch));
+        cursor.scratch = scratch;
+        key
     }
 
-    fn parse_key<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<String, JSONParseError> {
-        let start = match JSON::skip_whitspace(tokens) {
-            None => return Err(JSONParseError),
-            Some(ch) => ch,
-        };
+    pub(crate) fn skip_colons(cursor: &mut Cursor) -> Option<JSONParseError> {
+        match cursor.next_non_whitespace() {
+            Some(b':') => None,
+            _ => Some(JSONParseError),
+        }
+    }
 
-        if start != '"' {
+    pub(crate) fn parse_value(cursor: &mut Cursor) -> Result<JSONValue, JSONParseError> {
+        if !cursor.watch.poll() {
             return Err(JSONParseError);
         }
 
-        let mut key = String::new();
-        let mut escaped = false;
+        let token = match cursor.next_non_whitespace() {
+            Some(byte) => byte,
+            None => return Err(JSONParseError),
+        };
+        cursor.count_value()?;
 
-        while let Some(ch) = tokens.next() {
-            if escaped {
-                key.push(ch);
-                escaped = false;
-            } else {
-                match ch {
-                    '"' => return Ok(key),
-                    '\\' => {
-                        escaped = true;
-                    }
-                    _ => key.push(ch),
+        match token {
+            b'"' => {
+                let start = cursor.pos;
+                let value = JSON::parse_string_value(cursor)?;
+                cursor.count_string(start, false)?;
+                Ok(JSONValue::String(value))
+            }
+            b'n' => JSON::parse_literal(cursor, "ull", JSONValue::Null),
+            b't' => JSON::parse_literal(cursor, "rue", JSONValue::Bool(true)),
+            b'f' => JSON::parse_literal(cursor, "alse", JSONValue::Bool(false)),
+            b'{' | b'[' => {
+                if !cursor.enter() {
+                    return Err(JSONParseError);
                 }
+                let value = if token == b'{' {
+                    JSON::parse_object_value(cursor).map(JSONValue::Object)
+                } else {
+                    JSON::parse_array_value(cursor).map(JSONValue::Array)
+                };
+                cursor.depth -= 1;
+                value
+            }
+            #[cfg(feature = "bigdecimal")]
+            b'N' if cursor.lenient => {
+                JSON::parse_literal(cursor, "aN", JSONValue::NonFinite(f64::NAN))
             }
+            #[cfg(feature = "bigdecimal")]
+            b'I' if cursor.lenient => {
+                JSON::parse_literal(cursor, "nfinity", JSONValue::NonFinite(f64::INFINITY))
+            }
+            #[cfg(feature = "bigdecimal")]
+            b'-' if cursor.lenient && cursor.peek() == Some(b'I') => {
+                JSON::parse_literal(cursor, "Infinity", JSONValue::NonFinite(f64::NEG_INFINITY))
+            }
+            b'-' | b'0'..=b'9' => JSON::parse_numeric_value(cursor),
+            _ => Err(JSONParseError),
         }
-
-        Err(JSONParseError)
     }
 
-    fn skip_colons<I: Iterator<Item = char>>(tokens: &mut Peekable<I>) -> Option<JSONParseError> {
-        match JSON::skip_whitspace(tokens) {
-            Some(ch) => match ch {
-                ':' => None,
-                _ => Some(JSONParseError),
-            },
-            None => Some(JSONParseError),
+    /// Matches the remainder of `null`, `true` or `false` after its first
+    /// letter has been consumed.
+    fn parse_literal(
+        cursor: &mut Cursor,
+        rest: &str,
+        value: JSONValue,
+    ) -> Result<JSONValue, JSONParseError> {
+        if cursor.rest().starts_with(rest) {
+            cursor.pos += rest.len();
+            Ok(value)
+        } else {
+            Err(JSONParseError)
         }
     }
 
-    fn parse_value<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<JSONValue, JSONParseError> {
-        let token = match JSON::skip_whitspace(tokens) {
-            Some(ch) => ch,
-            None => return Err(JSONParseError),
-        };
+    /// Parses the elements of an array whose opening `[` has already been
+    /// consumed, up to and including its closing `]`.
+    fn parse_array_value(cursor: &mut Cursor) -> Result<Vec<JSONValue>, JSONParseError> {
+        let mut array: Vec<JSONValue> = vec![];
 
-        match token {
-            '"' => match JSON::parse_string_value(tokens) {
-                Ok(val) => Ok(JSONValue::String(val)),
-                Err(err) => Err(err),
-            },
-            'n' => {
-                let mut str = String::from("n");
-                while let Some(ch) = tokens.next() {
-                    str.push(ch);
-                    if ch.is_whitespace() || (ch == 'l' && str.len() == 4) {
-                        break;
-                    }
-                }
+        cursor.skip_whitespace();
+        if cursor.peek() == Some(b']') {
+            cursor.next();
+            return Ok(array);
+        }
 
-                match str.as_str() {
-                    "null" => Ok(JSONValue::Null),
-                    _ => Err(JSONParseError),
-                }
-            }
-            't' => {
-                let mut str = String::from("t");
-                while let Some(ch) = tokens.next() {
-                    str.push(ch);
-                    if ch.is_whitespace() || ch == 'e' {
+        loop {
+            array.push(JSON::parse_value(cursor)?);
+            cursor.limit(cursor.limits.check_array_len(array.len()))?;
+            match cursor.next_non_whitespace() {
+                Some(b',') => {
+                    cursor.skip_whitespace();
+                    if cursor.lenient && cursor.peek() == Some(b']') {
+                        cursor.next();
                         break;
                     }
                 }
-
-                match str.as_str() {
-                    "true" => Ok(JSONValue::Bool(true)),
-                    _ => Err(JSONParseError),
-                }
+                Some(b']') => break,
+                _ => return Err(JSONParseError),
             }
-            'f' => {
-                let mut str = String::from("f");
-                while let Some(ch) = tokens.next() {
-                    str.push(ch);
-                    if ch.is_whitespace() || ch == 'e' {
+        }
+        cursor.count_container(false, array.len())?;
+        Ok(array)
+    }
+
+    /// Parses the members of an object whose opening `{` has already been
+    /// consumed, up to and including its closing `}`.
+    fn parse_object_value(cursor: &mut Cursor) -> Result<JSON, JSONParseError> {
+        let mut json = JSON {
+            object: HashMap::new(),
+        };
+
+        cursor.skip_whitespace();
+        if cursor.peek() == Some(b'}') {
+            cursor.next();
+            return Ok(json);
+        }
+
+        let mut members = 0;
+        loop {
+            let (key, value) = JSON::get_pair(cursor)?;
+            json.object.insert(key, value);
+            members += 1;
+            cursor.limit(cursor.limits.check_members(members))?;
+            match cursor.next_non_whitespace() {
+                Some(b'}') => break,
+                Some(b',') => {
+                    cursor.skip_whitespace();
+                    if cursor.peek() == Some(b'}') {
+                        cursor.next();
+                        if !cursor.lenient {
+                            return Err(JSONParseError);
+                        }
                         break;
                     }
                 }
-                match str.as_str() {
-                    "false" => Ok(JSONValue::Bool(false)),
-                    _ => Err(JSONParseError),
-                }
-            }
-            '{' => match JSON::parse_object_value(tokens) {
-                Ok(json) => Ok(JSONValue::Object(json)),
-                Err(err) => Err(err),
-            },
-            '[' => match JSON::parse_array_value(tokens) {
-                Ok(array) => Ok(JSONValue::Array(array)),
-                Err(err) => Err(err),
-            },
-            _ => {
-                if token.is_numeric() || token == '-' {
-                    match JSON::parse_numeric_value(token, tokens) {
-                        Ok(num) => Ok(JSONValue::Number(num)),
-                        Err(err) => Err(err),
-                    }
-                } else {
-                    Err(JSONParseError)
-                }
+                _ => return Err(JSONParseError),
             }
         }
+        cursor.count_container(true, json.object.len())?;
+        Ok(json)
     }
 
-    fn parse_array_value<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<Vec<JSONValue>, JSONParseError> {
-        let mut array: Vec<JSONValue> = vec![];
+    fn parse_numeric_value(cursor: &mut Cursor) -> Result<JSONValue, JSONParseError> {
+        let start = cursor.pos - 1;
 
-        while let Some(token) = tokens.peek() {
-            if *token == ']' {
-                tokens.next().unwrap();
-                return Ok(array);
-            }
-            match JSON::parse_value(tokens) {
-                Ok(val) => array.push(val),
-                Err(err) => return Err(err),
+        while cursor.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            cursor.next();
+        }
+
+        #[cfg(feature = "bigdecimal")]
+        if matches!(cursor.peek(), Some(b'.' | b'e' | b'E')) {
+            return JSON::parse_decimal_value(start, cursor);
+        }
+        let text = cursor.slice(start, cursor.pos);
+        // Not even a zero may follow a leading zero.
+        let integer = text.strip_prefix('-').unwrap_or(text);
+        if integer.len() > 1 && integer.starts_with('0') {
+            return Err(JSONParseError);
+        }
+        if let Ok(num) = text.parse::<i32>() {
+            return Ok(JSONValue::Number(num));
+        }
+        #[cfg(feature = "i128")]
+        {
+            if let Ok(num) = text.parse() {
+                return Ok(JSONValue::Int128(num));
             }
-            match JSON::skip_whitspace(tokens) {
-                None => return Err(JSONParseError),
-                Some(token) => match token {
-                    ',' => {}
-                    ']' => return Ok(array),
-                    _ => return Err(JSONParseError),
-                },
+            if let Ok(num) = text.parse() {
+                return Ok(JSONValue::UInt128(num));
             }
         }
 
+        #[cfg(feature = "bigdecimal")]
+        return JSON::parse_decimal_value(start, cursor);
+        #[cfg(not(feature = "bigdecimal"))]
         Err(JSONParseError)
     }
 
-    fn parse_object_value<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<JSON, JSONParseError> {
-        let mut object_str = String::from('{');
-        let mut in_string = false;
-        let mut opened = 0;
+    /// Reads the rest of a number with a fraction or exponent, or too large
+    /// for an `i32`, whose integer part starts at `start`, as an exact
+    /// decimal.
+    #[cfg(feature = "bigdecimal")]
+    fn parse_decimal_value(start: usize, cursor: &mut Cursor) -> Result<JSONValue, JSONParseError> {
+        let digits = |cursor: &mut Cursor| {
+            if !cursor.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                return Err(JSONParseError);
+            }
+            while cursor.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                cursor.next();
+            }
+            Ok(())
+        };
+        if cursor.peek() == Some(b'.') {
+            cursor.next();
+            digits(cursor)?;
+        }
+        if matches!(cursor.peek(), Some(b'e' | b'E')) {
+            cursor.next();
+            if matches!(cursor.peek(), Some(b'+' | b'-')) {
+                cursor.next();
+            }
+            digits(cursor)?;
+        }
 
-        while let Some(token) = tokens.next() {
-            object_str.push(token);
-            match token {
-                '"' => {
-                    in_string = !in_string;
-                }
-                '{' => {
-                    if !in_string {
-                        opened += 1
+        let text = cursor.slice(start, cursor.pos);
+        let integer = text.strip_prefix('-').unwrap_or(text);
+        let integer = &integer[..integer
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(integer.len())];
+        if integer.is_empty() || (integer.len() > 1 && integer.starts_with('0')) {
+            return Err(JSONParseError);
+        }
+        text.parse()
+            .map(JSONValue::Decimal)
+            .map_err(|_| JSONParseError)
+    }
+
+    pub(crate) fn parse_string_value(cursor: &mut Cursor) -> Result<String, JSONParseError> {
+        let mut value = String::new();
+        JSON::decode_string_into(cursor, &mut value)?;
+        Ok(value)
+    }
+
+    /// Reads up to the closing quote, copying whole runs between escapes
+    /// rather than going byte by byte, and decoding escape sequences.
+    fn decode_string_into(cursor: &mut Cursor, value: &mut String) -> Result<(), JSONParseError> {
+        let mut start = cursor.pos;
+
+        while cursor.skip_to_quote_or_escape() {
+            let run = cursor.slice(start, cursor.pos);
+            if let Some(idx) = run.bytes().position(|byte| byte < 0x20) {
+                cursor.pos = start + idx + 1;
+                return Err(JSONParseError);
+            }
+            if cursor.control == ControlPolicy::Preserve {
+                value.push_str(run);
+            } else {
+                // Only DEL and the C1 controls can appear unescaped.
+                for (idx, ch) in run.char_indices() {
+                    if !control::is_flagged(ch) {
+                        value.push(ch);
+                    } else if cursor.control == ControlPolicy::Reject {
+                        cursor.pos = start + idx + 1;
+                        return Err(cursor.reject_control(ch));
                     }
                 }
-                '}' => {
-                    if !in_string {
-                        if opened == 0 {
-                            break;
-                        }
-                        opened -= 1;
-                    }
+            }
+
+            if cursor.next() == Some(b'"') {
+                return Ok(());
+            }
+            let ch = match cursor.next() {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{c}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => JSON::parse_unicode_escape(cursor)?,
+                _ => return Err(JSONParseError),
+            };
+            match cursor.control {
+                ControlPolicy::Reject if control::is_flagged(ch) => {
+                    return Err(cursor.reject_control(ch))
                 }
-                _ => {}
+                ControlPolicy::Strip if control::is_flagged(ch) => {}
+                _ => value.push(ch),
             }
+            start = cursor.pos;
         }
 
-        match JSON::parse_from_string(object_str) {
-            Ok(json) => Ok(json),
-            Err(_) => Err(JSONParseError),
+        Err(JSONParseError)
+    }
+
+    /// Decodes the `XXXX` of a `\uXXXX` escape, joining surrogate pairs.
+    /// Unpaired surrogates cannot be held in a `String` and become U+FFFD.
+    fn parse_unicode_escape(cursor: &mut Cursor) -> Result<char, JSONParseError> {
+        let high = JSON::parse_hex4(cursor)?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return Ok(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        if !cursor.rest().starts_with("\\u") {
+            return Ok(char::REPLACEMENT_CHARACTER);
         }
+        let resume = cursor.pos;
+        cursor.pos += 2;
+        let low = JSON::parse_hex4(cursor)?;
+        if !(0xdc00..0xe000).contains(&low) {
+            cursor.pos = resume;
+            return Ok(char::REPLACEMENT_CHARACTER);
+        }
+
+        let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
     }
 
-    fn parse_numeric_value<I: Iterator<Item = char>>(
-        digit: char,
-        tokens: &mut Peekable<I>,
-    ) -> Result<i32, JSONParseError> {
-        let mut value = String::from(digit);
+    fn parse_hex4(cursor: &mut Cursor) -> Result<u32, JSONParseError> {
+        let digits = cursor.rest().get(..4).ok_or(JSONParseError)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(JSONParseError);
+        }
+        cursor.pos += 4;
+        u32::from_str_radix(digits, 16).map_err(|_| JSONParseError)
+    }
+}
 
-        while let Some(ch) = tokens.peek() {
-            if !ch.is_numeric() {
-                break;
+/// Decodes `bytes` replacing each invalid UTF-8 sequence with U+FFFD, and
+/// records the offset of each one in `replaced`.
+fn decode_lossy(mut bytes: &[u8], replaced: &mut Vec<usize>) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    let mut offset = 0;
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return text;
+            }
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                text.push(char::REPLACEMENT_CHARACTER);
+                replaced.push(offset + valid.len());
+
+                let skip = err.error_len().unwrap_or(rest.len());
+                offset += valid.len() + skip;
+                bytes = &rest[skip..];
             }
-            value.push(tokens.next().unwrap());
         }
+    }
+}
 
-        return match value.parse::<i32>() {
-            Ok(num) => {
-                if num != 0 && digit == '0' {
-                    Err(JSONParseError)
-                } else {
-                    Ok(num)
-                }
+/// Drops commas that are directly followed (ignoring whitespace) by a closing
+/// bracket, leaving string contents untouched.
+pub(crate) fn strip_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut output = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (idx, &ch) in chars.iter().enumerate() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
             }
-            Err(_) => Err(JSONParseError),
-        };
+        } else if ch == '"' {
+            in_string = true;
+        } else if ch == ',' {
+            let next = chars[idx + 1..].iter().find(|ch| !ch.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        output.push(ch);
     }
 
-    fn parse_string_value<I: Iterator<Item = char>>(
-        tokens: &mut Peekable<I>,
-    ) -> Result<String, JSONParseError> {
-        let mut value = String::new();
-        let mut escaped = false;
+    output
+}
 
-        while let Some(ch) = tokens.next() {
-            if escaped {
-                value.push(ch);
-                escaped = false;
-            } else {
-                match ch {
-                    '"' => return Ok(value),
-                    '\\' => {
-                        escaped = true;
-                    }
-                    _ => value.push(ch),
-                }
-            }
-        }
+/// Turns a failure at `offset` into `content[leading..]` into a
+/// `line:col: message` error.
+fn describe(content: &str, leading: usize, failure: Failure) -> ArgsParseError {
+    let (offset, message) = explain(failure);
+    let (line, column) = get_line_column(content, leading + offset);
+    ArgsParseError(format!("{}:{}: {}", line, column, message))
+}
 
-        Err(JSONParseError)
+/// Where a parse failed and why.
+fn explain(failure: Failure) -> (usize, String) {
+    match failure {
+        Failure::At(offset) => (offset, JSONParseError.to_string()),
+        Failure::Interrupted(why, offset) => (offset, why.to_string()),
+        Failure::TooDeep(offset) => (
+            offset,
+            format!("nesting is deeper than {} levels", MAX_DEPTH),
+        ),
+        Failure::ControlChar(ch, offset) => (
+            offset,
+            format!("control character U+{:04X} in string", ch as u32),
+        ),
+        Failure::Limit(err, offset) => (offset, err.to_string()),
     }
 }
 
-fn get_padded_string(str: String) -> String {
-    let mut output = String::new();
-    for line in str.lines() {
-        output.push_str("  ");
-        output.push_str(line);
-        output.push('\n');
+/// Maps a byte offset to a 1-based line and column, counted in characters.
+pub fn get_line_column(content: &str, mut offset: usize) -> (usize, usize) {
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
     }
-    output
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&ch| ch != '\n').count() + 1;
+    (line, column)
 }
+
 impl Display for JSON {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.object.len() == 0 {
-            write!(f, "{}{}", '{', '}')
+        let style = if f.alternate() {
+            Style::default()
         } else {
-            let mut json_str = String::new();
-            for (idx, key) in self.object.keys().enumerate() {
-                if key.contains(' ') {
-                    json_str.push_str(&format!("\"{}\"", key));
-                } else {
-                    json_str.push_str(key);
-                }
-                json_str.push_str(": ");
-                json_str.push_str(&format!("{}", self.object.get(key).unwrap()));
-                if idx < self.object.len() - 1 {
-                    json_str.push(',');
-                }
-                json_str.push('\n');
-            }
-            write!(f, "{}\n{}{}", '{', get_padded_string(json_str), '}')
-        }
+            Style::compact()
+        };
+        let style = Style {
+            non_finite: NonFinitePolicy::Literal,
+            ..style
+        };
+        self.write_styled(f, &style)
     }
 }
@@ -0,0 +1,146 @@
+use crate::{
+    lexer::Cursor,
+    parser::{ArgsParseError, JSONValue, ParseOptions, Parser, JSON},
+};
+use std::collections::HashMap;
+
+/// What could be salvaged from a document that failed to parse.
+pub struct Partial {
+    /// The document up to where it broke: every value read in full, and the
+    /// objects and arrays open at that point closed as they stood. `None`
+    /// when the break came before the root was opened.
+    pub value: Option<JSONValue>,
+    pub error: ArgsParseError,
+}
+
+impl Parser {
+    /// Parses a document as `parse_from_string` does, but on failure keeps
+    /// what came before the error rather than discarding it, as when
+    /// recovering the intact start of a truncated log.
+    ///
+    /// A member or element whose value is cut short is dropped unless that
+    /// value is itself an object or array, which is kept as far as it goes.
+    pub fn parse_partial(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<JSONValue, Partial> {
+        let error = match self.parse_from_string(content, options) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        let mut cursor = Cursor::new(content.trim());
+        cursor.watch = options.watch();
+        cursor.control = options.control;
+        cursor.lenient = options.lenient;
+        let value = match cursor.next() {
+            Some(b'{') => Some(JSONValue::Object(
+                salvage_object(&mut cursor).unwrap_or_else(|json| json),
+            )),
+            Some(b'[') => Some(JSONValue::Array(
+                salvage_array(&mut cursor).unwrap_or_else(|array| array),
+            )),
+            _ => None,
+        };
+        Err(Partial { value, error })
+    }
+}
+
+impl JSON {
+    /// Parses a single document with a fresh `Parser`, keeping what came
+    /// before the error on failure.
+    pub fn parse_partial(content: &str, options: &ParseOptions) -> Result<JSONValue, Partial> {
+        Parser::default().parse_partial(content, options)
+    }
+}
+
+/// Reads a value, returning on failure what was read of it if it is an
+/// object or array.
+fn salvage_value(cursor: &mut Cursor) -> Result<JSONValue, Option<JSONValue>> {
+    cursor.skip_whitespace();
+    let token = cursor.peek();
+    if token != Some(b'{') && token != Some(b'[') {
+        return JSON::parse_value(cursor).map_err(|_| None);
+    }
+
+    cursor.next();
+    if !cursor.enter() {
+        return Err(None);
+    }
+    let value = if token == Some(b'{') {
+        salvage_object(cursor)
+            .map(JSONValue::Object)
+            .map_err(|json| Some(JSONValue::Object(json)))
+    } else {
+        salvage_array(cursor)
+            .map(JSONValue::Array)
+            .map_err(|array| Some(JSONValue::Array(array)))
+    };
+    cursor.depth -= 1;
+    value
+}
+
+/// Reads the elements of an array whose `[` has been consumed, returning
+/// those read so far on failure.
+fn salvage_array(cursor: &mut Cursor) -> Result<Vec<JSONValue>, Vec<JSONValue>> {
+    let mut array = vec![];
+
+    cursor.skip_whitespace();
+    if cursor.peek() == Some(b']') {
+        cursor.next();
+        return Ok(array);
+    }
+
+    loop {
+        match salvage_value(cursor) {
+            Ok(value) => array.push(value),
+            Err(partial) => {
+                array.extend(partial);
+                return Err(array);
+            }
+        }
+        match cursor.next_non_whitespace() {
+            Some(b',') => {}
+            Some(b']') => return Ok(array),
+            _ => return Err(array),
+        }
+    }
+}
+
+/// Reads the members of an object whose `{` has been consumed, returning
+/// those read so far on failure.
+fn salvage_object(cursor: &mut Cursor) -> Result<JSON, JSON> {
+    let mut json = JSON {
+        object: HashMap::new(),
+    };
+
+    cursor.skip_whitespace();
+    if cursor.peek() == Some(b'}') {
+        cursor.next();
+        return Ok(json);
+    }
+
+    loop {
+        let key = match JSON::parse_key(cursor) {
+            Ok(key) if JSON::skip_colons(cursor).is_none() => key,
+            _ => return Err(json),
+        };
+        match salvage_value(cursor) {
+            Ok(value) => {
+                json.object.insert(key, value);
+            }
+            Err(partial) => {
+                if let Some(value) = partial {
+                    json.object.insert(key, value);
+                }
+                return Err(json);
+            }
+        }
+        match cursor.next_non_whitespace() {
+            Some(b'}') => return Ok(json),
+            Some(b',') => {}
+            _ => return Err(json),
+        }
+    }
+}
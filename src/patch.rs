@@ -0,0 +1,127 @@
+use crate::{
+    diff::{Change, ChangeKind},
+    parser::{JSONValue, JSON},
+    path::{Path, Segment},
+};
+use std::collections::HashMap;
+
+impl JSON {
+    /// Builds an RFC 6902 JSON Patch that turns `self` into `target`.
+    ///
+    /// Removed object members whose value reappears unchanged under another
+    /// key become `move` operations. Array elements are never moved, since
+    /// indexes shift as the patch is applied.
+    pub fn create_patch(&self, target: &JSON) -> JSONValue {
+        let mut changes = self.diff(target);
+        reverse_array_removals(&mut changes);
+
+        let mut moved = vec![false; changes.len()];
+        let mut operations = vec![];
+        for (idx, change) in changes.iter().enumerate() {
+            let operation = match &change.kind {
+                ChangeKind::Added(value) => match find_move(&changes, &moved, change, value) {
+                    Some(from) => {
+                        moved[from] = true;
+                        operation(vec![
+                            ("op", "move".into()),
+                            ("from", pointer(&changes[from].path).into()),
+                            ("path", pointer(&change.path).into()),
+                        ])
+                    }
+                    None => operation(vec![
+                        ("op", "add".into()),
+                        ("path", pointer(&change.path).into()),
+                        ("value", value.clone()),
+                    ]),
+                },
+                ChangeKind::Removed(_) => operation(vec![
+                    ("op", "remove".into()),
+                    ("path", pointer(&change.path).into()),
+                ]),
+                ChangeKind::Modified { new, .. } => operation(vec![
+                    ("op", "replace".into()),
+                    ("path", pointer(&change.path).into()),
+                    ("value", new.clone()),
+                ]),
+            };
+            operations.push((idx, operation));
+        }
+
+        JSONValue::Array(
+            operations
+                .into_iter()
+                .filter(|(idx, _)| !moved[*idx])
+                .map(|(_, operation)| operation)
+                .collect(),
+        )
+    }
+}
+
+/// `diff` lists trailing array removals in ascending order, but each removal
+/// shifts the elements after it, so they have to be applied back to front.
+fn reverse_array_removals(changes: &mut [Change]) {
+    let is_element_removal = |change: &Change| {
+        matches!(change.kind, ChangeKind::Removed(_))
+            && matches!(change.path.segments().last(), Some(Segment::Index(_)))
+    };
+    let parent = |change: &Change| {
+        let segments = change.path.segments();
+        segments[..segments.len() - 1].to_vec()
+    };
+
+    let mut start = 0;
+    while start < changes.len() {
+        let mut end = start;
+        if is_element_removal(&changes[start]) {
+            end += 1;
+            while end < changes.len()
+                && is_element_removal(&changes[end])
+                && parent(&changes[end]) == parent(&changes[start])
+            {
+                end += 1;
+            }
+            changes[start..end].reverse();
+        }
+        start = end.max(start + 1);
+    }
+}
+
+/// Finds an object member removed elsewhere in the diff that held exactly
+/// `value`, so the addition can be expressed as a move.
+fn find_move(
+    changes: &[Change],
+    moved: &[bool],
+    added: &Change,
+    value: &JSONValue,
+) -> Option<usize> {
+    if !matches!(added.path.segments().last(), Some(Segment::Key(_))) {
+        return None;
+    }
+
+    changes.iter().enumerate().position(|(idx, change)| {
+        !moved[idx]
+            && matches!(change.path.segments().last(), Some(Segment::Key(_)))
+            && matches!(&change.kind, ChangeKind::Removed(removed) if removed == value)
+    })
+}
+
+fn operation(members: Vec<(&str, JSONValue)>) -> JSONValue {
+    let object: HashMap<_, _> = members
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect();
+    JSONValue::Object(JSON { object })
+}
+
+/// Renders a path as an RFC 6901 JSON Pointer.
+pub fn pointer(path: &Path) -> String {
+    let mut pointer = String::new();
+    for segment in path.segments() {
+        pointer.push('/');
+        match segment {
+            Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            Segment::Index(idx) => pointer.push_str(&idx.to_string()),
+        }
+    }
+    pointer
+}
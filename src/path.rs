@@ -0,0 +1,117 @@
+use crate::parser::JSONValue;
+use std::{collections::HashMap, fmt::Display};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A location inside a document, written `users[3].name` or, as jq writes
+/// it, `.users[3].name`. Keys containing `.` or `[` can be quoted:
+/// `["a.b"].c`. Where a pattern is wanted, `*` or `[]` stands for any key or
+/// index, and is kept as a `*` key.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path(pub Vec<Segment>);
+
+impl Path {
+    pub fn parse(path: &str) -> Result<Path, String> {
+        Path::parse_with_vars(path, &HashMap::new())
+    }
+
+    /// Parses `path` where, as in jq, `[$name]` is the key or index bound to
+    /// `name` in `vars`: a string is a key and a non-negative integer an
+    /// index.
+    pub fn parse_with_vars(path: &str, vars: &HashMap<String, JSONValue>) -> Result<Path, String> {
+        let mut segments = vec![];
+        let mut chars = path.strip_prefix('.').unwrap_or(path).chars().peekable();
+        let mut key = String::new();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '.' => {
+                    if !key.is_empty() {
+                        segments.push(Segment::Key(std::mem::take(&mut key)));
+                    } else if segments.is_empty() {
+                        return Err(format!("invalid path `{}`", path));
+                    }
+                }
+                '[' => {
+                    if !key.is_empty() {
+                        segments.push(Segment::Key(std::mem::take(&mut key)));
+                    }
+                    let mut inner = String::new();
+                    for ch in chars.by_ref() {
+                        if ch == ']' {
+                            break;
+                        }
+                        inner.push(ch);
+                    }
+                    let quoted = inner.len() >= 2 && inner.starts_with('"') && inner.ends_with('"');
+                    if let Some(name) = inner.trim().strip_prefix('$') {
+                        segments.push(match vars.get(name) {
+                            Some(JSONValue::String(key)) => Segment::Key(key.to_string()),
+                            Some(value) => match value.as_usize_checked() {
+                                Ok(idx) => Segment::Index(idx),
+                                Err(_) => {
+                                    return Err(format!(
+                                        "${} in `{}` is neither a key nor an index",
+                                        name, path
+                                    ))
+                                }
+                            },
+                            None => {
+                                return Err(format!("undefined variable ${} in `{}`", name, path))
+                            }
+                        });
+                    } else if matches!(inner.trim(), "" | "*") {
+                        segments.push(Segment::Key(String::from("*")));
+                    } else if quoted {
+                        segments.push(Segment::Key(inner[1..inner.len() - 1].to_string()));
+                    } else {
+                        match inner.trim().parse() {
+                            Ok(idx) => segments.push(Segment::Index(idx)),
+                            Err(_) => {
+                                return Err(format!("invalid index `{}` in `{}`", inner, path))
+                            }
+                        }
+                    }
+                }
+                _ => key.push(ch),
+            }
+        }
+
+        if !key.is_empty() {
+            segments.push(Segment::Key(key));
+        }
+        Ok(Path(segments))
+    }
+
+    pub fn push(&mut self, segment: Segment) {
+        self.0.push(segment);
+    }
+
+    pub fn pop(&mut self) -> Option<Segment> {
+        self.0.pop()
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if key.contains(['.', '[', ']']) || key.is_empty() => {
+                    write!(f, "[\"{}\"]", key)?
+                }
+                Segment::Key(key) if idx == 0 => write!(f, "{}", key)?,
+                Segment::Key(key) => write!(f, ".{}", key)?,
+                Segment::Index(idx) => write!(f, "[{}]", idx)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{
+    control,
+    parser::{JSONValue, JSON},
+    trace::{self, Node, Tally},
+};
+use std::fmt::{self, Write};
+
+const KEY_COLOR: &str = "\x1b[1;34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[33m";
+const LITERAL_COLOR: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+pub(crate) const NON_FINITE: &str = "NaN and infinite numbers have no JSON form; use --non-finite null, string or literal to write them";
+
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub indent: usize,
+    pub color: bool,
+    /// Prints everything on one line with no spaces, ignoring `indent`.
+    pub compact: bool,
+    /// Leaves control characters (as `control::is_flagged` defines them) out
+    /// of strings and keys instead of escaping them.
+    pub strip_control: bool,
+    /// Writes object members in key order, comparing keys by their UTF-16
+    /// code units as RFC 8785 does, rather than in the map's order.
+    pub sort_keys: bool,
+    /// Writes characters outside ASCII as `\uXXXX` escapes, as a surrogate
+    /// pair beyond the Basic Multilingual Plane, instead of as UTF-8.
+    pub escape_unicode: bool,
+    /// Writes `/` as `\/`.
+    pub escape_slash: bool,
+    /// Writes the hex digits of `\uXXXX` escapes as `A`-`F` instead of
+    /// `a`-`f`.
+    pub uppercase_hex: bool,
+    /// Ends lines with `\r\n` instead of `\n`.
+    pub crlf: bool,
+    /// Ends the output with a line ending, as text files conventionally
+    /// are.
+    pub final_newline: bool,
+    /// Only `bigdecimal` builds have numbers that can be NaN or infinite.
+    pub non_finite: NonFinitePolicy,
+    /// Rounds numbers to the nearest double and writes them as ECMAScript's
+    /// `Number::toString` does, so `1.0` and `1` are both written `1`.
+    /// Numbers too large for a double fail as NaN does under
+    /// `NonFinitePolicy::Error`.
+    pub ecmascript_numbers: bool,
+}
+
+/// How NaN and the infinities, which JSON has no number for, are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum NonFinitePolicy {
+    /// Fails, so invalid JSON is never written by accident.
+    #[default]
+    Error,
+    /// Writes `null`, as `JSON.stringify` does.
+    Null,
+    /// Writes `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+    /// Writes `NaN`, `Infinity` or `-Infinity` unquoted, which is not JSON
+    /// but what JavaScript and lenient parsers, this one included, read.
+    Literal,
+}
+
+impl NonFinitePolicy {
+    pub fn from_name(name: &str) -> Option<NonFinitePolicy> {
+        match name {
+            "error" => Some(NonFinitePolicy::Error),
+            "null" => Some(NonFinitePolicy::Null),
+            "string" => Some(NonFinitePolicy::String),
+            "literal" => Some(NonFinitePolicy::Literal),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            indent: 2,
+            color: false,
+            compact: false,
+            strip_control: false,
+            sort_keys: false,
+            escape_unicode: false,
+            escape_slash: false,
+            uppercase_hex: false,
+            crlf: false,
+            final_newline: false,
+            non_finite: NonFinitePolicy::Error,
+            ecmascript_numbers: false,
+        }
+    }
+}
+
+impl Style {
+    /// The line ending `crlf` picks.
+    pub fn line_ending(&self) -> &'static str {
+        if self.crlf {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// The style used by `{}`; `{:#}` uses the default, pretty style.
+    pub fn compact() -> Style {
+        Style {
+            indent: 0,
+            color: false,
+            compact: true,
+            strip_control: false,
+            sort_keys: false,
+            escape_unicode: false,
+            escape_slash: false,
+            uppercase_hex: false,
+            crlf: false,
+            final_newline: false,
+            non_finite: NonFinitePolicy::Error,
+            ecmascript_numbers: false,
+        }
+    }
+}
+
+impl JSONValue {
+    /// Fails only when `style.non_finite` is `NonFinitePolicy::Error` and the
+    /// value holds a NaN or infinity.
+    pub fn try_to_styled_string(&self, style: &Style) -> Result<String, String> {
+        let mut output = String::new();
+        match self.write_styled(&mut output, style) {
+            Ok(()) => Ok(output),
+            Err(_) => Err(String::from(NON_FINITE)),
+        }
+    }
+
+    /// The length in bytes of `try_to_styled_string(style)`, worked out
+    /// without building the string.
+    pub fn serialized_len(&self, style: &Style) -> Result<usize, String> {
+        let mut counter = Counter(0);
+        match self.write_styled(&mut counter, style) {
+            Ok(()) => Ok(counter.0),
+            Err(_) => Err(String::from(NON_FINITE)),
+        }
+    }
+
+    pub fn write_styled(&self, out: &mut impl Write, style: &Style) -> fmt::Result {
+        let span = trace::serialize();
+        span.record_nodes(Node::Value(self));
+        let mut out = Tally::new(out);
+        let mut printer = Printer {
+            out: &mut out,
+            style,
+            depth: 0,
+        };
+        let result = printer.value(self).and_then(|_| printer.end());
+        span.record_bytes(out.len());
+        result
+    }
+}
+
+impl JSON {
+    pub fn try_to_styled_string(&self, style: &Style) -> Result<String, String> {
+        let mut output = String::new();
+        match self.write_styled(&mut output, style) {
+            Ok(()) => Ok(output),
+            Err(_) => Err(String::from(NON_FINITE)),
+        }
+    }
+
+    pub fn serialized_len(&self, style: &Style) -> Result<usize, String> {
+        let mut counter = Counter(0);
+        match self.write_styled(&mut counter, style) {
+            Ok(()) => Ok(counter.0),
+            Err(_) => Err(String::from(NON_FINITE)),
+        }
+    }
+
+    pub fn write_styled(&self, out: &mut impl Write, style: &Style) -> fmt::Result {
+        let span = trace::serialize();
+        span.record_nodes(Node::Object(self));
+        let mut out = Tally::new(out);
+        let mut printer = Printer {
+            out: &mut out,
+            style,
+            depth: 0,
+        };
+        let result = printer.object(self).and_then(|_| printer.end());
+        span.record_bytes(out.len());
+        result
+    }
+}
+
+/// Counts the bytes written to it and discards them.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+struct Printer<'a, W: Write> {
+    out: &'a mut W,
+    style: &'a Style,
+    /// Number of enclosing objects; every line inside one is indented.
+    depth: usize,
+}
+
+impl<W: Write> Printer<'_, W> {
+    fn value(&mut self, value: &JSONValue) -> fmt::Result {
+        match value {
+            JSONValue::String(val) => self.paint(STRING_COLOR, |printer| printer.string(val)),
+            JSONValue::Number(val) => {
+                self.paint(NUMBER_COLOR, |printer| write!(printer.out, "{}", val))
+            }
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(val) if self.style.ecmascript_numbers => {
+                self.ecmascript_number(Some(*val as f64))
+            }
+            #[cfg(feature = "i128")]
+            JSONValue::UInt128(val) if self.style.ecmascript_numbers => {
+                self.ecmascript_number(Some(*val as f64))
+            }
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(val) => {
+                self.paint(NUMBER_COLOR, |printer| write!(printer.out, "{}", val))
+            }
+            #[cfg(feature = "i128")]
+            JSONValue::UInt128(val) => {
+                self.paint(NUMBER_COLOR, |printer| write!(printer.out, "{}", val))
+            }
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(val) if self.style.ecmascript_numbers => {
+                self.ecmascript_number(bigdecimal::ToPrimitive::to_f64(val))
+            }
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(val) => self.paint(NUMBER_COLOR, |printer| {
+                // `BigDecimal` writes `1e+400` but `1E-400`.
+                let text = val.to_string();
+                printer.out.write_str(&text.replace('E', "e"))
+            }),
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::NonFinite(val) => self.non_finite(*val),
+            JSONValue::Bool(val) => {
+                self.paint(LITERAL_COLOR, |printer| write!(printer.out, "{}", val))
+            }
+            JSONValue::Null => self.paint(LITERAL_COLOR, |printer| printer.out.write_str("null")),
+            JSONValue::Array(vals) => {
+                self.out.write_char('[')?;
+                for (idx, val) in vals.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.write_char(',')?;
+                    }
+                    self.value(val)?;
+                }
+                self.out.write_char(']')
+            }
+            JSONValue::Object(json) => self.object(json),
+        }
+    }
+
+    fn object(&mut self, json: &JSON) -> fmt::Result {
+        if json.object.is_empty() {
+            return self.out.write_str("{}");
+        }
+
+        let mut members: Vec<_> = json.object.iter().collect();
+        if self.style.sort_keys {
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+        }
+
+        if self.style.compact {
+            self.out.write_char('{')?;
+            for (idx, (key, value)) in members.iter().enumerate() {
+                if idx > 0 {
+                    self.out.write_char(',')?;
+                }
+                self.paint(KEY_COLOR, |printer| printer.string(key))?;
+                self.out.write_char(':')?;
+                self.value(value)?;
+            }
+            return self.out.write_char('}');
+        }
+
+        self.out.write_char('{')?;
+        self.newline()?;
+        self.depth += 1;
+        for (idx, (key, value)) in members.iter().enumerate() {
+            self.pad()?;
+            self.paint(KEY_COLOR, |printer| printer.string(key))?;
+            self.out.write_str(": ")?;
+            self.value(value)?;
+            if idx < json.object.len() - 1 {
+                self.out.write_char(',')?;
+            }
+            self.newline()?;
+        }
+        self.depth -= 1;
+        self.pad()?;
+        self.out.write_char('}')
+    }
+
+    #[cfg(feature = "bigdecimal")]
+    fn non_finite(&mut self, val: f64) -> fmt::Result {
+        let name = if val.is_nan() {
+            "NaN"
+        } else if val > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        };
+        match self.style.non_finite {
+            NonFinitePolicy::Error => Err(fmt::Error),
+            NonFinitePolicy::Null => {
+                self.paint(LITERAL_COLOR, |printer| printer.out.write_str("null"))
+            }
+            NonFinitePolicy::String => self.paint(STRING_COLOR, |printer| printer.string(name)),
+            NonFinitePolicy::Literal => {
+                self.paint(NUMBER_COLOR, |printer| printer.out.write_str(name))
+            }
+        }
+    }
+
+    /// Writes `val` as `Number::toString` does (ECMA-262, 6.1.6.1.20): the
+    /// shortest digits that read back as `val`, in plain notation when the
+    /// decimal point falls within 21 digits of them and in `1.5e+21` form
+    /// otherwise.
+    #[cfg(any(feature = "bigdecimal", feature = "i128"))]
+    fn ecmascript_number(&mut self, val: Option<f64>) -> fmt::Result {
+        let val = match val {
+            Some(val) if val.is_finite() => val,
+            _ => return Err(fmt::Error),
+        };
+        self.paint(NUMBER_COLOR, |printer| {
+            if val == 0.0 {
+                return printer.out.write_char('0');
+            }
+            if val < 0.0 {
+                printer.out.write_char('-')?;
+            }
+            let scientific = format!("{:e}", val.abs());
+            let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+            let digits = mantissa.replace('.', "");
+            let k = digits.len() as i32;
+            let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+            let out = &mut printer.out;
+            if k <= n && n <= 21 {
+                write!(out, "{}{:0<2$}", digits, "", (n - k) as usize)
+            } else if 0 < n && n <= 21 {
+                let (whole, fraction) = digits.split_at(n as usize);
+                write!(out, "{}.{}", whole, fraction)
+            } else if -6 < n && n <= 0 {
+                write!(out, "0.{:0<1$}{2}", "", -n as usize, digits)
+            } else {
+                let (first, rest) = digits.split_at(1);
+                let sign = if n > 0 { '+' } else { '-' };
+                if rest.is_empty() {
+                    write!(out, "{}e{}{}", first, sign, (n - 1).abs())
+                } else {
+                    write!(out, "{}.{}e{}{}", first, rest, sign, (n - 1).abs())
+                }
+            }
+        })
+    }
+
+    fn paint(&mut self, color: &str, write: impl FnOnce(&mut Self) -> fmt::Result) -> fmt::Result {
+        if self.style.color {
+            self.out.write_str(color)?;
+            write(self)?;
+            self.out.write_str(RESET)
+        } else {
+            write(self)
+        }
+    }
+
+    /// Writes a quoted string, escaping quotes, backslashes and control
+    /// characters, and whatever else the style asks to. Runs that need no
+    /// escaping are written in one piece.
+    fn string(&mut self, text: &str) -> fmt::Result {
+        if self.style.strip_control && text.chars().any(control::is_flagged) {
+            let stripped: String = text
+                .chars()
+                .filter(|&ch| !control::is_flagged(ch))
+                .collect();
+            return self.string(&stripped);
+        }
+
+        self.out.write_char('"')?;
+        let mut start = 0;
+        for (idx, ch) in text.char_indices() {
+            let escape = match ch {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '/' if self.style.escape_slash => "\\/",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                '\u{8}' => "\\b",
+                '\u{c}' => "\\f",
+                '\0'..='\u{1f}' => "",
+                _ if self.style.escape_unicode && !ch.is_ascii() => "",
+                _ => continue,
+            };
+
+            self.out.write_str(&text[start..idx])?;
+            if escape.is_empty() {
+                for unit in ch.encode_utf16(&mut [0; 2]) {
+                    if self.style.uppercase_hex {
+                        write!(self.out, "\\u{:04X}", unit)?;
+                    } else {
+                        write!(self.out, "\\u{:04x}", unit)?;
+                    }
+                }
+            } else {
+                self.out.write_str(escape)?;
+            }
+            start = idx + ch.len_utf8();
+        }
+        self.out.write_str(&text[start..])?;
+        self.out.write_char('"')
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        self.out.write_str(self.style.line_ending())
+    }
+
+    /// Writes the final line ending, if the style asks for one.
+    fn end(&mut self) -> fmt::Result {
+        if self.style.final_newline {
+            self.newline()?;
+        }
+        Ok(())
+    }
+
+    fn pad(&mut self) -> fmt::Result {
+        write!(self.out, "{:1$}", "", self.depth * self.style.indent)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    parser::JSONValue,
+    path::{Path, Segment},
+};
+
+/// Whether `path` matches the first `path.len()` segments of `pattern`, a
+/// `*` segment in the pattern matching any key or index.
+fn matches_prefix(pattern: &Path, path: &Path) -> bool {
+    let pattern = pattern.segments();
+    let path = path.segments();
+    path.len() <= pattern.len()
+        && pattern.iter().zip(path).all(|pair| match pair {
+            (Segment::Key(wildcard), _) if wildcard == "*" => true,
+            (pattern, segment) => pattern == segment,
+        })
+}
+
+fn matches(pattern: &Path, path: &Path) -> bool {
+    pattern.segments().len() == path.segments().len() && matches_prefix(pattern, path)
+}
+
+impl JSONValue {
+    /// Keeps only the values at `paths`, along with the objects and arrays
+    /// leading to them, so `pick(["name", "owner.id"])` leaves `{"name": ...,
+    /// "owner": {"id": ...}}`. A `*` segment matches any key or index, as in
+    /// `users.*.name`. Kept array elements close ranks.
+    pub fn pick(&mut self, paths: &[Path]) {
+        if !paths.iter().any(|pattern| pattern.segments().is_empty()) {
+            pick_children(self, &mut Path::default(), paths);
+        }
+    }
+
+    /// Removes the values at `paths`, which may use `*` segments as in
+    /// `pick`.
+    pub fn omit(&mut self, paths: &[Path]) {
+        omit_children(self, &mut Path::default(), paths);
+    }
+}
+
+/// Visits the members or elements of `value` with `keep`, dropping those it
+/// returns false for.
+fn retain_children(
+    value: &mut JSONValue,
+    path: &mut Path,
+    keep: &mut impl FnMut(&mut JSONValue, &mut Path) -> bool,
+) {
+    match value {
+        JSONValue::Object(json) => json.object.retain(|key, value| {
+            path.push(Segment::Key(key.to_string()));
+            let kept = keep(value, path);
+            path.pop();
+            kept
+        }),
+        JSONValue::Array(values) => {
+            let mut idx = 0;
+            values.retain_mut(|value| {
+                path.push(Segment::Index(idx));
+                idx += 1;
+                let kept = keep(value, path);
+                path.pop();
+                kept
+            });
+        }
+        _ => {}
+    }
+}
+
+fn pick_children(value: &mut JSONValue, path: &mut Path, patterns: &[Path]) {
+    retain_children(value, path, &mut |child, path| {
+        if patterns.iter().any(|pattern| matches(pattern, path)) {
+            return true;
+        }
+        if !patterns.iter().any(|pattern| matches_prefix(pattern, path)) {
+            return false;
+        }
+        pick_children(child, path, patterns);
+        // A container kept only on the way to something that is not there.
+        match child {
+            JSONValue::Object(json) => !json.object.is_empty(),
+            JSONValue::Array(values) => !values.is_empty(),
+            _ => false,
+        }
+    });
+}
+
+fn omit_children(value: &mut JSONValue, path: &mut Path, patterns: &[Path]) {
+    retain_children(value, path, &mut |child, path| {
+        if patterns.iter().any(|pattern| matches(pattern, path)) {
+            return false;
+        }
+        if patterns.iter().any(|pattern| matches_prefix(pattern, path)) {
+            omit_children(child, path, patterns);
+        }
+        true
+    });
+}
+
+impl JSONValue {
+    /// Removes every `null` member and array element, at any depth.
+    pub fn drop_nulls(&mut self) {
+        retain_children(self, &mut Path::default(), &mut |child, _| {
+            child.drop_nulls();
+            *child != JSONValue::Null
+        });
+    }
+
+    /// Removes every `{}`, `[]` and `""` member and array element, at any
+    /// depth. Containers left empty by the removal go too.
+    pub fn drop_empty(&mut self) {
+        retain_children(self, &mut Path::default(), &mut |child, _| {
+            child.drop_empty();
+            !match child {
+                JSONValue::Object(json) => json.object.is_empty(),
+                JSONValue::Array(values) => values.is_empty(),
+                JSONValue::String(text) => text.is_empty(),
+                _ => false,
+            }
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use crate::parser::JSONValue;
+
+/// What redacted values are replaced with.
+pub const REDACTED: &str = "***";
+
+/// A regular expression picking the keys whose values `redact` hides, also
+/// used by `grep` to search strings. It matches anywhere in the text, so
+/// `token` catches `access_token`.
+pub struct KeyPattern {
+    #[cfg(feature = "regex")]
+    regex: regex::Regex,
+    /// Without the `regex` feature no pattern can be built.
+    #[cfg(not(feature = "regex"))]
+    never: std::convert::Infallible,
+}
+
+impl KeyPattern {
+    #[cfg(feature = "regex")]
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<KeyPattern, String> {
+        match regex::RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+        {
+            Ok(regex) => Ok(KeyPattern { regex }),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    pub fn new(_: &str, _: bool) -> Result<KeyPattern, String> {
+        Err(String::from(
+            "key patterns are not enabled, rebuild with `--features regex`",
+        ))
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn is_match(&self, key: &str) -> bool {
+        self.regex.is_match(key)
+    }
+
+    #[cfg(not(feature = "regex"))]
+    pub fn is_match(&self, _: &str) -> bool {
+        match self.never {}
+    }
+}
+
+impl JSONValue {
+    /// Replaces the value of every member whose key matches `pattern`, at
+    /// any depth, with `"***"`. Objects and arrays are replaced whole.
+    pub fn redact(&mut self, pattern: &KeyPattern) {
+        match self {
+            JSONValue::Object(json) => {
+                for (key, value) in json.object.iter_mut() {
+                    if pattern.is_match(key) {
+                        *value = JSONValue::String(String::from(REDACTED));
+                    } else {
+                        value.redact(pattern);
+                    }
+                }
+            }
+            JSONValue::Array(values) => {
+                for value in values {
+                    value.redact(pattern);
+                }
+            }
+            _ => {}
+        }
+    }
+}
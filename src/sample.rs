@@ -0,0 +1,85 @@
+use crate::parser::JSONValue;
+
+/// Which elements of an oversized array to keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArraySample {
+    /// The first n.
+    Head(usize),
+    /// The last n.
+    Tail(usize),
+    /// n spread evenly from first to last, so the same document always
+    /// gives the same sample.
+    Sample(usize),
+}
+
+impl ArraySample {
+    fn len(self) -> usize {
+        match self {
+            ArraySample::Head(len) | ArraySample::Tail(len) | ArraySample::Sample(len) => len,
+        }
+    }
+}
+
+impl JSONValue {
+    /// Cuts every array longer than the sample, at any depth, down to the
+    /// elements it picks. A string such as `"... 199990 of 200000 elements
+    /// omitted"` takes the place of the rest, at the end, or at the start for
+    /// `Tail`, so the output is not mistaken for the whole array.
+    pub fn sample_arrays(&mut self, sample: ArraySample) {
+        match self {
+            JSONValue::Array(values) => {
+                for value in values.iter_mut() {
+                    value.sample_arrays(sample);
+                }
+                sample_array(values, sample);
+            }
+            JSONValue::Object(json) => {
+                for value in json.object.values_mut() {
+                    value.sample_arrays(sample);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn sample_array(values: &mut Vec<JSONValue>, sample: ArraySample) {
+    let (len, keep) = (values.len(), sample.len());
+    if len <= keep {
+        return;
+    }
+
+    let note = JSONValue::String(format!("... {} of {} elements omitted", len - keep, len));
+    match sample {
+        ArraySample::Head(_) => {
+            values.truncate(keep);
+            values.push(note);
+        }
+        ArraySample::Tail(_) => {
+            values.drain(..len - keep);
+            values.insert(0, note);
+        }
+        ArraySample::Sample(_) => {
+            // Element i of the sample is the one at i * (len - 1) / (keep - 1),
+            // which starts at the first element and ends at the last.
+            let mut idx = 0;
+            let mut next = 0;
+            values.retain(|_| {
+                let picked = next < keep && idx == pick(next, len, keep);
+                if picked {
+                    next += 1;
+                }
+                idx += 1;
+                picked
+            });
+            values.push(note);
+        }
+    }
+}
+
+fn pick(nth: usize, len: usize, keep: usize) -> usize {
+    match keep {
+        1 => 0,
+        _ => nth * (len - 1) / (keep - 1),
+    }
+}
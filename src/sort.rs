@@ -0,0 +1,80 @@
+use crate::{parser::JSONValue, path::Path};
+use std::cmp::Ordering;
+
+impl JSONValue {
+    /// Sorts every array in the document, at any depth, whose elements have
+    /// a value at `path` (as in `name` or `owner.id`) by that value. When
+    /// every such value is a number or a numeric string the order is
+    /// numeric, otherwise it is by text, ignoring case. Elements without one
+    /// keep their relative order after the rest.
+    pub fn sort_arrays_by(&mut self, path: &Path) {
+        match self {
+            JSONValue::Array(values) => {
+                for value in values.iter_mut() {
+                    value.sort_arrays_by(path);
+                }
+                sort_by_field(values, path);
+            }
+            JSONValue::Object(json) => {
+                for value in json.object.values_mut() {
+                    value.sort_arrays_by(path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn sort_by_field(values: &mut [JSONValue], path: &Path) {
+    let mut fields = values.iter().filter_map(|value| value.at(path)).peekable();
+    if fields.peek().is_none() {
+        return;
+    }
+
+    if fields.all(|field| number(field).is_some()) {
+        values.sort_by(|a, b| {
+            let a = a.at(path).and_then(number);
+            let b = b.at(path).and_then(number);
+            compare_present(a, b, f64::total_cmp)
+        });
+    } else {
+        values.sort_by_cached_key(|value| {
+            let field = value.at(path);
+            let text = field.map(text).unwrap_or_default();
+            (field.is_none(), text.to_lowercase(), text)
+        });
+    }
+}
+
+/// Orders present values by `cmp`, ahead of missing ones.
+fn compare_present<T>(a: Option<T>, b: Option<T>, cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn number(value: &JSONValue) -> Option<f64> {
+    match value {
+        JSONValue::Number(num) => Some(f64::from(*num)),
+        #[cfg(feature = "i128")]
+        JSONValue::Int128(num) => Some(*num as f64),
+        #[cfg(feature = "i128")]
+        JSONValue::UInt128(num) => Some(*num as f64),
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::Decimal(num) => bigdecimal::ToPrimitive::to_f64(num),
+        #[cfg(feature = "bigdecimal")]
+        JSONValue::NonFinite(num) => Some(*num).filter(|num| !num.is_nan()),
+        JSONValue::String(text) => text.trim().parse().ok().filter(|num: &f64| !num.is_nan()),
+        _ => None,
+    }
+}
+
+fn text(value: &JSONValue) -> String {
+    match value {
+        JSONValue::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    lazy::Lazy,
+    parser::{get_line_column, ArgsParseError, JSONValue, ParseOptions, Parser, JSON},
+    path::{Path, Segment},
+};
+
+/// Where a value lies in its document. `line` and `column` are 1-based and
+/// point at `start`, columns being counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A parsed value that remembers where each of its values came from, so a
+/// linter can point at the one at fault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    span: Span,
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// A string, number, boolean or null.
+    Scalar(JSONValue),
+    Array(Vec<Spanned>),
+    /// Members in document order, repeated keys included.
+    Object(Vec<(String, Spanned)>),
+}
+
+impl Parser {
+    /// Parses a document as `parse_from_string` does, recording the span of
+    /// every value. This reads the document twice, so it is slower than
+    /// parsing alone.
+    pub fn parse_with_spans(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<Spanned, ArgsParseError> {
+        self.parse_from_string(content, options)?;
+        let lines = Lines::new(content);
+        spanned(&Lazy::new(content), &lines).map_err(|offset| {
+            let (line, column) = get_line_column(content, offset);
+            ArgsParseError(format!("{}:{}: Invalid JSON file!", line, column))
+        })
+    }
+}
+
+impl JSON {
+    /// Parses a single document with a fresh `Parser`, recording the span of
+    /// every value.
+    pub fn parse_with_spans(
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<Spanned, ArgsParseError> {
+        Parser::default().parse_with_spans(content, options)
+    }
+}
+
+impl Spanned {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// The member at `key`, the last one if it is repeated, as parsing keeps.
+    pub fn get(&self, key: &str) -> Option<&Spanned> {
+        match &self.node {
+            Node::Object(members) => members
+                .iter()
+                .rev()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn index(&self, idx: usize) -> Option<&Spanned> {
+        match &self.node {
+            Node::Array(values) => values.get(idx),
+            _ => None,
+        }
+    }
+
+    pub fn at(&self, path: &Path) -> Option<&Spanned> {
+        path.segments()
+            .iter()
+            .try_fold(self, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(idx) => value.index(*idx),
+            })
+    }
+
+    /// The value with its spans left out.
+    pub fn to_value(&self) -> JSONValue {
+        match &self.node {
+            Node::Scalar(value) => value.clone(),
+            Node::Array(values) => JSONValue::Array(values.iter().map(Spanned::to_value).collect()),
+            Node::Object(members) => {
+                let mut json = JSON::new();
+                for (key, value) in members {
+                    json.insert(key, value.to_value());
+                }
+                JSONValue::Object(json)
+            }
+        }
+    }
+}
+
+/// Where each line starts, to turn offsets into lines and columns without
+/// rescanning the document for every value.
+struct Lines<'a> {
+    content: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(content: &'a str) -> Lines<'a> {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Lines { content, starts }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        let line = self
+            .starts
+            .partition_point(|&line_start| line_start <= start);
+        let line_start = self.starts[line - 1];
+        Span {
+            start,
+            end,
+            line,
+            column: self.content[line_start..start].chars().count() + 1,
+        }
+    }
+}
+
+fn spanned(value: &Lazy, lines: &Lines) -> Result<Spanned, usize> {
+    let start = value.offset();
+    let span = lines.span(start, value.end());
+    let node = match value.raw().as_bytes().first() {
+        Some(b'[') => Node::Array(
+            value
+                .members()?
+                .iter()
+                .map(|(_, value)| spanned(value, lines))
+                .collect::<Result<_, _>>()?,
+        ),
+        Some(b'{') => Node::Object(
+            value
+                .members()?
+                .iter()
+                .map(|(key, value)| {
+                    let key = key.unwrap_or_default().to_string();
+                    spanned(value, lines).map(|value| (key, value))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => Node::Scalar(value.value()?.clone()),
+    };
+    Ok(Spanned { span, node })
+}
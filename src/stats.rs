@@ -0,0 +1,85 @@
+use crate::parser::{ArgsParseError, JSONValue, ParseOptions, Parser, JSON};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// Figures about one parse, for logging per request to see how payloads grow.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParseStats {
+    /// Length of the text parsed.
+    pub bytes_read: usize,
+    /// Values in the document, objects and arrays included. A key repeated
+    /// in one object counts once, as only its last value is kept.
+    pub nodes: usize,
+    /// Most objects and arrays nested one inside another, the root included.
+    pub peak_depth: usize,
+    /// Bytes of the string values and keys once escapes are decoded.
+    pub string_bytes: usize,
+    /// Time spent parsing, not counting working out the other figures.
+    pub duration: Duration,
+}
+
+impl Display for ParseStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} bytes, {} nodes, depth {}, {} string bytes in {:?}",
+            self.bytes_read, self.nodes, self.peak_depth, self.string_bytes, self.duration
+        )
+    }
+}
+
+impl Parser {
+    /// Parses a document as `parse_from_string` does, also returning
+    /// figures about it.
+    pub fn parse_with_stats(
+        &mut self,
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<(JSONValue, ParseStats), ArgsParseError> {
+        let start = Instant::now();
+        let value = self.parse_from_string(content, options)?;
+        let mut stats = ParseStats {
+            bytes_read: content.len(),
+            duration: start.elapsed(),
+            ..ParseStats::default()
+        };
+        stats.add(&value, 1);
+        Ok((value, stats))
+    }
+}
+
+impl JSON {
+    /// Parses a single document with a fresh `Parser`, also returning
+    /// figures about it.
+    pub fn parse_with_stats(
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<(JSONValue, ParseStats), ArgsParseError> {
+        Parser::default().parse_with_stats(content, options)
+    }
+}
+
+impl ParseStats {
+    fn add(&mut self, value: &JSONValue, depth: usize) {
+        self.nodes += 1;
+        match value {
+            JSONValue::String(value) => self.string_bytes += value.len(),
+            JSONValue::Array(values) => {
+                self.peak_depth = self.peak_depth.max(depth);
+                for value in values {
+                    self.add(value, depth + 1);
+                }
+            }
+            JSONValue::Object(json) => {
+                self.peak_depth = self.peak_depth.max(depth);
+                for (key, value) in &json.object {
+                    self.string_bytes += key.len();
+                    self.add(value, depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,1039 @@
+use crate::{
+    control::{self, ControlPolicy},
+    lexer::{is_whitespace, Cursor},
+    limits::{allocation, container_bytes, LimitError, Limits},
+    parser::{JSONValue, ParseOptions, JSON, MAX_DEPTH},
+    path::{Path, Segment},
+    printer::{NonFinitePolicy, Style, NON_FINITE},
+};
+use std::mem::size_of;
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+#[derive(Debug)]
+pub struct StreamError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Number {
+    Minus,
+    Zero,
+    Integer,
+    FractionStart,
+    Fraction,
+    ExponentStart,
+    ExponentSign,
+    Exponent,
+}
+
+impl Number {
+    fn is_complete(self) -> bool {
+        matches!(
+            self,
+            Number::Zero | Number::Integer | Number::Fraction | Number::Exponent
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    ArrayValueOrEnd,
+    ObjectKeyOrEnd,
+    ObjectKey,
+    Colon,
+    AfterValue,
+    String {
+        key: bool,
+    },
+    Escape {
+        key: bool,
+    },
+    /// `code` holds the hex digits read so far.
+    Unicode {
+        key: bool,
+        remaining: u8,
+        code: u32,
+    },
+    Number(Number),
+    Literal {
+        word: &'static [u8],
+        matched: usize,
+    },
+}
+
+/// Checks that `reader` holds exactly one document that the parser would
+/// accept with `options`, without building a DOM: an object or array whose
+/// numbers fit this build's number variants, within `options.limits`.
+/// Memory use is bounded by the read buffer, the longest number and a few
+/// words per level of nesting, so arbitrarily large documents can be
+/// validated. Returns the number of bytes read.
+pub fn validate_stream(reader: impl Read, options: &ParseOptions) -> Result<u64, StreamError> {
+    run(
+        reader,
+        &mut Ignore,
+        Validator::with_options(false, options),
+        options,
+    )
+}
+
+/// Checks the grammar alone: any value may be the root, and numbers of any
+/// size or precision are accepted.
+pub(crate) fn validate_syntax(reader: impl Read) -> Result<u64, StreamError> {
+    run(
+        reader,
+        &mut Ignore,
+        Validator::new(false),
+        &ParseOptions::default(),
+    )
+}
+
+/// A piece of a document, as `transcode` reads it. Strings and keys are
+/// given as written, without their quotes but with their escapes, and
+/// numbers as written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(&'a str),
+    String(&'a str),
+    Number(&'a str),
+    Bool(bool),
+    Null,
+}
+
+/// Receives the events of a document as `transcode` reads them, to write
+/// it out in another form without ever holding all of it.
+pub trait Sink {
+    fn event(&mut self, event: Event) -> io::Result<()>;
+
+    /// Called once the document has been read in full.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Validates `reader` as `validate_stream` does, handing each piece of the
+/// document to `sink` as soon as it is complete. Memory use is bounded by
+/// the longest string or number rather than by the document, so a
+/// document larger than memory can be converted. Events already handed on
+/// stand even if the document turns out to be malformed further on.
+///
+/// Under `options.lenient`, `NaN`, `Infinity` and `-Infinity` are read in
+/// `bigdecimal` builds and handed on as numbers.
+pub fn transcode(
+    reader: impl Read,
+    sink: &mut impl Sink,
+    options: &ParseOptions,
+) -> Result<u64, StreamError> {
+    run(
+        reader,
+        sink,
+        Validator::with_options(true, options),
+        options,
+    )
+}
+
+/// Writes the events it receives as JSON, compact or indented.
+pub struct JsonWriter<W: Write> {
+    out: W,
+    /// Spaces per level, or `None` for everything on one line.
+    indent: Option<usize>,
+    /// Whether each open container already has a member or element.
+    filled: Vec<bool>,
+    after_key: bool,
+    line_ending: &'static str,
+    /// Whether each document ends with `line_ending`.
+    final_newline: bool,
+    non_finite: NonFinitePolicy,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn compact(out: W) -> JsonWriter<W> {
+        JsonWriter {
+            out,
+            indent: None,
+            filled: vec![],
+            after_key: false,
+            line_ending: "\n",
+            final_newline: true,
+            non_finite: NonFinitePolicy::Error,
+        }
+    }
+
+    pub fn indented(out: W, indent: usize) -> JsonWriter<W> {
+        JsonWriter {
+            indent: Some(indent),
+            ..JsonWriter::compact(out)
+        }
+    }
+
+    /// Writes compact or indented as `style` asks, with its line endings and
+    /// its way of writing NaN and the infinities.
+    pub fn styled(out: W, style: &Style) -> JsonWriter<W> {
+        let writer = if style.compact {
+            JsonWriter::compact(out)
+        } else {
+            JsonWriter::indented(out, style.indent)
+        };
+        JsonWriter {
+            line_ending: style.line_ending(),
+            final_newline: style.final_newline,
+            non_finite: style.non_finite,
+            ..writer
+        }
+    }
+
+    /// Writes what goes between the previous value and the next one: a comma
+    /// and, when indenting, a line break and the indentation.
+    fn separate(&mut self) -> io::Result<()> {
+        if std::mem::take(&mut self.after_key) {
+            return Ok(());
+        }
+        if let Some(filled) = self.filled.last_mut() {
+            if std::mem::replace(filled, true) {
+                self.out.write_all(b",")?;
+            }
+            self.newline(self.filled.len())?;
+        }
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) -> io::Result<()> {
+        if let Some(indent) = self.indent {
+            write!(
+                self.out,
+                "{}{:width$}",
+                self.line_ending,
+                "",
+                width = depth * indent
+            )?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, bracket: &[u8]) -> io::Result<()> {
+        if self.filled.pop() == Some(true) {
+            self.newline(self.filled.len())?;
+        }
+        self.out.write_all(bracket)
+    }
+}
+
+impl<W: Write> Sink for JsonWriter<W> {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::EndObject => return self.close(b"}"),
+            Event::EndArray => return self.close(b"]"),
+            _ => self.separate()?,
+        }
+
+        match event {
+            Event::StartObject | Event::StartArray => {
+                self.filled.push(false);
+                let open = if event == Event::StartObject {
+                    b"{"
+                } else {
+                    b"["
+                };
+                self.out.write_all(open)
+            }
+            Event::Key(key) => {
+                self.after_key = true;
+                let colon = if self.indent.is_some() { "\": " } else { "\":" };
+                write!(self.out, "\"{}{}", key, colon)
+            }
+            Event::String(text) => write!(self.out, "\"{}\"", text),
+            Event::Number(number) if !number.ends_with(|ch: char| ch.is_ascii_digit()) => {
+                match self.non_finite {
+                    NonFinitePolicy::Error => Err(io::Error::other(NON_FINITE)),
+                    NonFinitePolicy::Null => self.out.write_all(b"null"),
+                    NonFinitePolicy::String => write!(self.out, "\"{}\"", number),
+                    NonFinitePolicy::Literal => self.out.write_all(number.as_bytes()),
+                }
+            }
+            Event::Number(number) => self.out.write_all(number.as_bytes()),
+            Event::Bool(val) => write!(self.out, "{}", val),
+            Event::Null => self.out.write_all(b"null"),
+            Event::EndObject | Event::EndArray => unreachable!("handled above"),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.final_newline {
+            self.out.write_all(self.line_ending.as_bytes())?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Passes on to `sink` only the values at `paths`, each as a document of its
+/// own, so a query can run over a document too large to hold. A `*` segment
+/// matches any key or index, as in `pick`. Values are passed on in document
+/// order as soon as they are read, and a value inside one already selected
+/// only as part of it.
+pub struct Select<'a, S: Sink> {
+    paths: &'a [Path],
+    sink: S,
+    /// The current key or index in each open container outside a selected
+    /// value.
+    frames: Vec<Frame>,
+    /// How many containers of a selected value are open, 0 outside one.
+    depth: usize,
+    found: Vec<bool>,
+}
+
+enum Frame {
+    Array(usize),
+    Object(String),
+}
+
+impl<'a, S: Sink> Select<'a, S> {
+    pub fn new(paths: &'a [Path], sink: S) -> Select<'a, S> {
+        Select {
+            paths,
+            sink,
+            frames: vec![],
+            depth: 0,
+            found: vec![false; paths.len()],
+        }
+    }
+
+    /// The paths without a `*` that no value was found at.
+    pub fn missing(&self) -> impl Iterator<Item = &'a Path> + '_ {
+        self.paths
+            .iter()
+            .zip(&self.found)
+            .filter(|(path, found)| {
+                !**found
+                    && !path
+                        .segments()
+                        .iter()
+                        .any(|segment| *segment == Segment::Key(String::from("*")))
+            })
+            .map(|(path, _)| path)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+
+    /// Whether the value starting now is at one of the paths.
+    fn select(&mut self) -> bool {
+        let mut selected = false;
+        for (path, found) in self.paths.iter().zip(&mut self.found) {
+            let matches = path.segments().len() == self.frames.len()
+                && path
+                    .segments()
+                    .iter()
+                    .zip(&self.frames)
+                    .all(|pair| match pair {
+                        (Segment::Key(wildcard), _) if wildcard == "*" => true,
+                        (Segment::Key(key), Frame::Object(current)) => key == current,
+                        (Segment::Index(idx), Frame::Array(current)) => idx == current,
+                        _ => false,
+                    });
+            if matches {
+                *found = true;
+                selected = true;
+            }
+        }
+        selected
+    }
+
+    /// Moves on from a value that has ended.
+    fn next_element(&mut self) {
+        if let Some(Frame::Array(idx)) = self.frames.last_mut() {
+            *idx += 1;
+        }
+    }
+}
+
+impl<S: Sink> Sink for Select<'_, S> {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        let opens = matches!(event, Event::StartObject | Event::StartArray);
+        let closes = matches!(event, Event::EndObject | Event::EndArray);
+        if self.depth > 0 {
+            self.sink.event(event)?;
+            if opens {
+                self.depth += 1;
+            } else if closes {
+                self.depth -= 1;
+            }
+            if self.depth == 0 {
+                self.sink.finish()?;
+                self.next_element();
+            }
+            return Ok(());
+        }
+
+        match event {
+            Event::Key(key) => {
+                if let Some(Frame::Object(current)) = self.frames.last_mut() {
+                    current.clear();
+                    if key.contains('\\') {
+                        current.push_str(&unescape(key));
+                    } else {
+                        current.push_str(key);
+                    }
+                }
+            }
+            _ if closes => {
+                self.frames.pop();
+                self.next_element();
+            }
+            _ if self.select() => {
+                self.sink.event(event)?;
+                if opens {
+                    self.depth = 1;
+                } else {
+                    self.sink.finish()?;
+                    self.next_element();
+                }
+            }
+            Event::StartArray => self.frames.push(Frame::Array(0)),
+            Event::StartObject => self.frames.push(Frame::Object(String::new())),
+            _ => self.next_element(),
+        }
+        Ok(())
+    }
+}
+
+/// A key as it reads once its escapes are decoded.
+pub(crate) fn unescape(key: &str) -> String {
+    let quoted = format!("\"{}\"", key);
+    match JSON::parse_key(&mut Cursor::new(&quoted)) {
+        Ok(key) => key.to_string(),
+        Err(_) => key.to_string(),
+    }
+}
+
+/// The sink `validate_stream` uses, which never asks for tokens.
+pub(crate) struct Ignore;
+
+impl Sink for Ignore {
+    fn event(&mut self, _: Event) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run(
+    mut reader: impl Read,
+    sink: &mut impl Sink,
+    mut validator: Validator,
+    options: &ParseOptions,
+) -> Result<u64, StreamError> {
+    let mut watch = options.watch();
+    let mut buffer = [0; 64 * 1024];
+    let mut read = 0;
+    loop {
+        let len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(validator.error(&err.to_string())),
+        };
+        read += len as u64;
+        if let Err(err) = options.limits.check_bytes(read as usize) {
+            return Err(validator.error(&err.to_string()));
+        }
+        for &byte in &buffer[..len] {
+            if !watch.poll() {
+                let why = watch.interrupted().map(|why| why.to_string());
+                return Err(validator.error(&why.unwrap_or_default()));
+            }
+            validator.feed(byte, sink)?;
+        }
+    }
+
+    validator.finish(sink)?;
+    sink.finish()
+        .map_err(|err| validator.error(&err.to_string()))?;
+    Ok(read)
+}
+
+pub(crate) struct Validator {
+    /// `b'{'` or `b'['` for every open container.
+    stack: Vec<u8>,
+    /// Members or elements read so far in every open container.
+    counts: Vec<usize>,
+    state: State,
+    utf8_remaining: u8,
+    /// The bytes the next continuation byte may be, narrower than
+    /// `0x80..=0xbf` after a lead byte that could otherwise start an
+    /// overlong form, a surrogate or a code point past U+10FFFF.
+    utf8_range: (u8, u8),
+    /// The byte before this one, which tells the C1 controls U+0080 to
+    /// U+009F, encoded as 0xc2 and one more byte, apart.
+    previous: u8,
+    line: usize,
+    column: usize,
+    /// Whether strings and numbers are collected into `token` for the sink.
+    capture: bool,
+    /// The string or number being read.
+    token: Vec<u8>,
+    /// What the parser accepts, or `None` to check the grammar alone.
+    rules: Option<Rules>,
+    /// Values read, with the estimated memory of the document once parsed,
+    /// counted as `Limits` counts them.
+    nodes: usize,
+    memory: usize,
+    /// Characters and bytes of the string or key being read, as written.
+    string_chars: usize,
+    string_bytes: usize,
+}
+
+/// The parser's rules beyond the grammar, as `ParseOptions` sets them: the
+/// root must be an object or array, numbers must fit a number variant, and
+/// nesting is capped at `MAX_DEPTH`.
+struct Rules {
+    lenient: bool,
+    reject_control: bool,
+    limits: Limits,
+}
+
+impl Validator {
+    pub(crate) fn new(capture: bool) -> Validator {
+        Validator {
+            stack: vec![],
+            counts: vec![],
+            state: State::Value,
+            utf8_remaining: 0,
+            utf8_range: (0x80, 0xbf),
+            previous: 0,
+            line: 1,
+            column: 0,
+            capture,
+            token: vec![],
+            rules: None,
+            nodes: 0,
+            memory: size_of::<JSONValue>(),
+            string_chars: 0,
+            string_bytes: 0,
+        }
+    }
+
+    /// Accepts what the parser accepts with `options`.
+    pub(crate) fn with_options(capture: bool, options: &ParseOptions) -> Validator {
+        Validator {
+            rules: Some(Rules {
+                lenient: options.lenient,
+                reject_control: options.control == ControlPolicy::Reject,
+                limits: options.limits.clone(),
+            }),
+            ..Validator::new(capture)
+        }
+    }
+
+    /// Whether a value has been read to its end, so that only whitespace
+    /// may follow it.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.state == State::AfterValue && self.stack.is_empty()
+    }
+
+    /// Whether no value has been started since `new` or `expect_value`.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.state == State::Value && self.stack.is_empty()
+    }
+
+    /// Readies the validator for another value, dropping whatever of the
+    /// current one has been read, as in a stream of concatenated documents.
+    /// Lines and columns keep counting.
+    pub(crate) fn expect_value(&mut self) {
+        self.stack.clear();
+        self.counts.clear();
+        self.state = State::Value;
+        self.utf8_remaining = 0;
+        self.previous = 0;
+        self.token.clear();
+        self.nodes = 0;
+        self.memory = size_of::<JSONValue>();
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Whether the next byte that is not whitespace starts a value, or in an
+    /// empty array may close it.
+    pub(crate) fn awaits_value(&self) -> bool {
+        matches!(self.state, State::Value | State::ArrayValueOrEnd)
+    }
+
+    /// Whether the last byte read was part of a number, which only the byte
+    /// after it can end.
+    pub(crate) fn in_number(&self) -> bool {
+        matches!(self.state, State::Number(_))
+    }
+
+    /// Whether a value has just been read to its end.
+    pub(crate) fn after_value(&self) -> bool {
+        self.state == State::AfterValue
+    }
+
+    /// Whether the last byte read was part of a key, quotes aside.
+    pub(crate) fn in_key(&self) -> bool {
+        matches!(
+            self.state,
+            State::String { key: true }
+                | State::Escape { key: true }
+                | State::Unicode { key: true, .. }
+        )
+    }
+
+    /// The line and column of the last byte read.
+    pub(crate) fn position(&self) -> (usize, usize) {
+        (self.line, self.column.max(1))
+    }
+
+    fn emit(&self, sink: &mut impl Sink, event: Event) -> Result<(), StreamError> {
+        sink.event(event)
+            .map_err(|err| self.error(&err.to_string()))
+    }
+
+    /// The token read so far, which is valid UTF-8 once it is complete.
+    fn token(&self) -> &str {
+        std::str::from_utf8(&self.token).unwrap_or_default()
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.capture {
+            self.token.push(byte);
+        }
+    }
+
+    pub(crate) fn error(&self, message: &str) -> StreamError {
+        StreamError {
+            line: self.line,
+            column: self.column.max(1),
+            message: message.to_string(),
+        }
+    }
+
+    fn unexpected(&self, byte: u8) -> StreamError {
+        if byte.is_ascii_graphic() {
+            self.error(&format!("unexpected `{}`", byte as char))
+        } else {
+            self.error(&format!("unexpected byte 0x{:02x}", byte))
+        }
+    }
+
+    pub(crate) fn feed(&mut self, byte: u8, sink: &mut impl Sink) -> Result<(), StreamError> {
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 0;
+        } else if byte & 0xc0 != 0x80 {
+            self.column += 1;
+        }
+
+        self.check_utf8(byte)?;
+        let previous = std::mem::replace(&mut self.previous, byte);
+
+        // A number has no terminator of its own, so the byte that ends it is
+        // handled again in the state that follows.
+        if let State::Number(number) = self.state {
+            if let Some(next) = next_number_state(number, byte) {
+                self.state = State::Number(next);
+                self.push_number(byte);
+                return Ok(());
+            }
+            if number == Number::Minus && byte == b'I' && self.reads_non_finite() {
+                self.state = State::Literal {
+                    word: b"-Infinity",
+                    matched: 2,
+                };
+                return Ok(());
+            }
+            if !number.is_complete() {
+                return Err(self.unexpected(byte));
+            }
+            self.end_number(sink)?;
+        }
+
+        match self.state {
+            State::String { key } => match byte {
+                b'"' => {
+                    self.end_string(key)?;
+                    if key {
+                        self.emit(sink, Event::Key(self.token()))?;
+                        self.state = State::Colon;
+                    } else {
+                        self.emit(sink, Event::String(self.token()))?;
+                        self.state = State::AfterValue;
+                    }
+                }
+                b'\\' => {
+                    self.count_string_byte(byte)?;
+                    self.push(byte);
+                    self.state = State::Escape { key };
+                }
+                0..=0x1f => return Err(self.error("control character in string")),
+                _ => {
+                    self.count_string_byte(byte)?;
+                    // Only DEL and the C1 controls can appear unescaped.
+                    match (previous, byte) {
+                        (_, 0x7f) => self.check_control(0x7f)?,
+                        (0xc2, 0x80..=0x9f) => self.check_control(u32::from(byte))?,
+                        _ => {}
+                    }
+                    self.push(byte);
+                }
+            },
+            State::Escape { key } => {
+                self.count_string_byte(byte)?;
+                self.push(byte);
+                match byte {
+                    b'"' | b'\\' | b'/' | b'n' | b'r' | b't' => self.state = State::String { key },
+                    b'b' | b'f' => {
+                        self.check_control(if byte == b'b' { 0x8 } else { 0xc })?;
+                        self.state = State::String { key };
+                    }
+                    b'u' => {
+                        self.state = State::Unicode {
+                            key,
+                            remaining: 4,
+                            code: 0,
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                }
+            }
+            State::Unicode {
+                key,
+                remaining,
+                code,
+            } => {
+                let digit = match (byte as char).to_digit(16) {
+                    Some(digit) => digit,
+                    None => return Err(self.error("invalid \\u escape")),
+                };
+                self.count_string_byte(byte)?;
+                self.push(byte);
+                let code = code * 16 + digit;
+                self.state = if remaining == 1 {
+                    self.check_control(code)?;
+                    State::String { key }
+                } else {
+                    State::Unicode {
+                        key,
+                        remaining: remaining - 1,
+                        code,
+                    }
+                };
+            }
+            State::Literal { word, matched } => {
+                if word[matched] != byte {
+                    return Err(self.unexpected(byte));
+                }
+                self.state = if matched + 1 == word.len() {
+                    let event = match word {
+                        b"true" => Event::Bool(true),
+                        b"false" => Event::Bool(false),
+                        b"null" => Event::Null,
+                        // `NaN`, `Infinity` or `-Infinity`, read when lenient.
+                        word => Event::Number(std::str::from_utf8(word).unwrap_or_default()),
+                    };
+                    self.emit(sink, event)?;
+                    State::AfterValue
+                } else {
+                    State::Literal {
+                        word,
+                        matched: matched + 1,
+                    }
+                };
+            }
+            _ if is_whitespace(byte) => {}
+            State::Value | State::ArrayValueOrEnd => {
+                if byte == b']' && self.state == State::ArrayValueOrEnd {
+                    self.close(byte, sink)?;
+                } else {
+                    self.start_value(byte, sink)?;
+                }
+            }
+            State::ObjectKeyOrEnd | State::ObjectKey => match byte {
+                b'"' => {
+                    if let Some(members) = self.counts.last_mut() {
+                        *members += 1;
+                    }
+                    self.check(|limits| {
+                        limits.check_members(self.counts.last().copied().unwrap_or(0))
+                    })?;
+                    self.start_string();
+                    self.state = State::String { key: true };
+                }
+                b'}' if self.state == State::ObjectKeyOrEnd => self.close(byte, sink)?,
+                _ => return Err(self.unexpected(byte)),
+            },
+            State::Colon => match byte {
+                b':' => self.state = State::Value,
+                _ => return Err(self.unexpected(byte)),
+            },
+            // When lenient, a comma may be followed by the closing bracket.
+            State::AfterValue => match (self.stack.last(), byte) {
+                (Some(b'{'), b',') if self.lenient() => self.state = State::ObjectKeyOrEnd,
+                (Some(b'{'), b',') => self.state = State::ObjectKey,
+                (Some(b'['), b',') if self.lenient() => self.state = State::ArrayValueOrEnd,
+                (Some(b'['), b',') => self.state = State::Value,
+                (Some(_), b'}' | b']') => self.close(byte, sink)?,
+                _ => return Err(self.unexpected(byte)),
+            },
+            State::Number(_) => unreachable!("numbers are handled above"),
+        }
+
+        Ok(())
+    }
+
+    fn start_value(&mut self, byte: u8, sink: &mut impl Sink) -> Result<(), StreamError> {
+        if self.rules.is_some() && self.stack.is_empty() && byte != b'{' && byte != b'[' {
+            return Err(self.error("a document must be an object or an array"));
+        }
+        self.nodes += 1;
+        self.check(|limits| limits.check_nodes(self.nodes))?;
+        if let (Some(b'['), Some(elements)) = (self.stack.last(), self.counts.last_mut()) {
+            *elements += 1;
+        }
+        if self.stack.last() == Some(&b'[') {
+            self.check(|limits| limits.check_array_len(self.counts.last().copied().unwrap_or(0)))?;
+        }
+
+        self.token.clear();
+        self.state = match byte {
+            b'{' | b'[' => {
+                self.stack.push(byte);
+                self.counts.push(0);
+                self.check_depth()?;
+                if byte == b'{' {
+                    self.emit(sink, Event::StartObject)?;
+                    State::ObjectKeyOrEnd
+                } else {
+                    self.emit(sink, Event::StartArray)?;
+                    State::ArrayValueOrEnd
+                }
+            }
+            b'"' => {
+                self.start_string();
+                State::String { key: false }
+            }
+            b'-' => State::Number(Number::Minus),
+            b'0' => State::Number(Number::Zero),
+            b'1'..=b'9' => State::Number(Number::Integer),
+            b't' => State::Literal {
+                word: b"true",
+                matched: 1,
+            },
+            b'f' => State::Literal {
+                word: b"false",
+                matched: 1,
+            },
+            b'n' => State::Literal {
+                word: b"null",
+                matched: 1,
+            },
+            b'N' if self.reads_non_finite() => State::Literal {
+                word: b"NaN",
+                matched: 1,
+            },
+            b'I' if self.reads_non_finite() => State::Literal {
+                word: b"Infinity",
+                matched: 1,
+            },
+            _ => return Err(self.unexpected(byte)),
+        };
+        if matches!(self.state, State::Number(_)) {
+            self.push_number(byte);
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self, byte: u8, sink: &mut impl Sink) -> Result<(), StreamError> {
+        let expected = if byte == b'}' { b'{' } else { b'[' };
+        match self.stack.pop() {
+            Some(open) if open == expected => {
+                let len = self.counts.pop().unwrap_or(0);
+                self.memory += allocation(container_bytes(byte == b'}', len));
+                self.check(|limits| limits.check_memory(self.memory))?;
+                let event = if byte == b'}' {
+                    Event::EndObject
+                } else {
+                    Event::EndArray
+                };
+                self.emit(sink, event)?;
+                self.state = State::AfterValue;
+                Ok(())
+            }
+            _ => Err(self.unexpected(byte)),
+        }
+    }
+
+    /// Fails if `check` breaks one of the limits, when the parser's rules
+    /// apply.
+    fn check(
+        &self,
+        check: impl FnOnce(&Limits) -> Result<(), LimitError>,
+    ) -> Result<(), StreamError> {
+        match &self.rules {
+            Some(rules) => check(&rules.limits).map_err(|err| self.error(&err.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails once the container just opened nests deeper than the parser
+    /// allows.
+    fn check_depth(&self) -> Result<(), StreamError> {
+        if self.rules.is_some() && self.stack.len() > MAX_DEPTH {
+            let message = format!("nesting is deeper than {} levels", MAX_DEPTH);
+            return Err(self.error(&message));
+        }
+        self.check(|limits| limits.check_depth(self.stack.len()))
+    }
+
+    fn lenient(&self) -> bool {
+        self.rules.as_ref().is_some_and(|rules| rules.lenient)
+    }
+
+    /// Whether `NaN`, `Infinity` and `-Infinity` are read, as the parser
+    /// reads them.
+    fn reads_non_finite(&self) -> bool {
+        cfg!(feature = "bigdecimal") && self.lenient()
+    }
+
+    /// Fails on a control character in a string or key under
+    /// `ControlPolicy::Reject`, as the parser does.
+    fn check_control(&self, code: u32) -> Result<(), StreamError> {
+        match char::from_u32(code) {
+            Some(ch)
+                if control::is_flagged(ch)
+                    && self
+                        .rules
+                        .as_ref()
+                        .is_some_and(|rules| rules.reject_control) =>
+            {
+                let message = format!("control character U+{:04X} in string", code);
+                Err(self.error(&message))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn start_string(&mut self) {
+        self.token.clear();
+        self.string_chars = 0;
+        self.string_bytes = 0;
+    }
+
+    /// Counts a byte of a string or key, as written, against
+    /// `max_string_len`.
+    fn count_string_byte(&mut self, byte: u8) -> Result<(), StreamError> {
+        self.string_bytes += 1;
+        if byte & 0xc0 != 0x80 {
+            self.string_chars += 1;
+        }
+        self.check(|limits| limits.check_string_len(self.string_chars))
+    }
+
+    fn end_string(&mut self, key: bool) -> Result<(), StreamError> {
+        // Keys are `Arc<str>`s, with their counts.
+        let extra = if key { 2 * size_of::<usize>() } else { 0 };
+        self.memory += allocation(self.string_bytes + extra);
+        self.check(|limits| limits.check_memory(self.memory))
+    }
+
+    /// Numbers are kept whole while the parser's rules apply, to be checked
+    /// against them once read.
+    fn push_number(&mut self, byte: u8) {
+        if self.capture || self.rules.is_some() {
+            self.token.push(byte);
+        }
+    }
+
+    /// Hands on the number just read, failing first if the parser has no
+    /// variant for it: without the `bigdecimal` feature one with a fraction
+    /// or exponent, or too large for an `i32` (or with `i128`, a `u128`).
+    fn end_number(&mut self, sink: &mut impl Sink) -> Result<(), StreamError> {
+        if self.rules.is_some() {
+            let mut cursor = Cursor::new(self.token());
+            let parsed = JSON::parse_value(&mut cursor);
+            if parsed.is_err() || cursor.remaining() > 0 {
+                return Err(self.error("number is out of range or not supported by this build"));
+            }
+        }
+        self.emit(sink, Event::Number(self.token()))?;
+        self.state = State::AfterValue;
+        Ok(())
+    }
+
+    /// Accepts only the shortest encoding of each code point, and none for
+    /// the surrogates U+D800 to U+DFFF, as Rust strings do.
+    fn check_utf8(&mut self, byte: u8) -> Result<(), StreamError> {
+        if self.utf8_remaining > 0 {
+            let (low, high) = self.utf8_range;
+            if byte < low || byte > high {
+                return Err(self.error("invalid UTF-8"));
+            }
+            self.utf8_remaining -= 1;
+            self.utf8_range = (0x80, 0xbf);
+            return Ok(());
+        }
+
+        (self.utf8_remaining, self.utf8_range) = match byte {
+            0x00..=0x7f => (0, (0x80, 0xbf)),
+            0xc2..=0xdf => (1, (0x80, 0xbf)),
+            0xe0 => (2, (0xa0, 0xbf)),
+            0xed => (2, (0x80, 0x9f)),
+            0xe1..=0xef => (2, (0x80, 0xbf)),
+            0xf0 => (3, (0x90, 0xbf)),
+            0xf1..=0xf3 => (3, (0x80, 0xbf)),
+            0xf4 => (3, (0x80, 0x8f)),
+            _ => return Err(self.error("invalid UTF-8")),
+        };
+        Ok(())
+    }
+
+    fn finish(&mut self, sink: &mut impl Sink) -> Result<(), StreamError> {
+        if let State::Number(number) = self.state {
+            if number.is_complete() {
+                self.end_number(sink)?;
+            }
+        }
+
+        if self.utf8_remaining > 0 {
+            Err(self.error("invalid UTF-8"))
+        } else if self.state != State::AfterValue || !self.stack.is_empty() {
+            Err(self.error("unexpected end of input"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn next_number_state(number: Number, byte: u8) -> Option<Number> {
+    match (number, byte) {
+        (Number::Minus, b'0') => Some(Number::Zero),
+        (Number::Minus, b'1'..=b'9') => Some(Number::Integer),
+        (Number::Integer, b'0'..=b'9') => Some(Number::Integer),
+        (Number::Zero | Number::Integer, b'.') => Some(Number::FractionStart),
+        (Number::FractionStart | Number::Fraction, b'0'..=b'9') => Some(Number::Fraction),
+        (Number::Zero | Number::Integer | Number::Fraction, b'e' | b'E') => {
+            Some(Number::ExponentStart)
+        }
+        (Number::ExponentStart, b'+' | b'-') => Some(Number::ExponentSign),
+        (Number::ExponentStart | Number::ExponentSign | Number::Exponent, b'0'..=b'9') => {
+            Some(Number::Exponent)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    parser::JSONValue,
+    path::{Path, Segment},
+};
+use std::fmt::Display;
+
+/// A `${VAR}` placeholder naming a variable that is not set, found in the
+/// string at `path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unresolved {
+    pub path: Path,
+    pub name: String,
+}
+
+impl Display for Unresolved {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "<root>" } else { &path };
+        write!(f, "{}: undefined variable {}", path, self.name)
+    }
+}
+
+/// A `{{path}}` placeholder whose path is missing from the data, or is not
+/// a path at all, found in the string at `path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unfilled {
+    pub path: Path,
+    pub expression: String,
+}
+
+impl Display for Unfilled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string();
+        let path = if path.is_empty() { "<root>" } else { &path };
+        write!(f, "{}: no value for {{{{{}}}}}", path, self.expression)
+    }
+}
+
+impl JSONValue {
+    /// Fills in `{{path}}` placeholders in string values, at any depth, from
+    /// `data`. A string that is only a placeholder becomes the value found,
+    /// whatever its type, so `"{{port}}"` can become `8080`; elsewhere in a
+    /// string, strings are inserted as they are and other values as compact
+    /// JSON. Keys are left alone.
+    ///
+    /// Placeholders that find nothing are left in place and returned, ordered
+    /// by path.
+    pub fn fill_template(&mut self, data: &JSONValue) -> Vec<Unfilled> {
+        let mut unfilled = vec![];
+        fill_in_value(self, data, &mut Path::default(), &mut unfilled);
+        unfilled.sort_by_cached_key(|unfilled| unfilled.path.to_string());
+        unfilled
+    }
+
+    /// Fills in the placeholders in every string value from the environment.
+    /// See `substitute_vars`.
+    pub fn substitute_env(&mut self) -> Vec<Unresolved> {
+        self.substitute_vars(&|name| std::env::var(name).ok())
+    }
+
+    /// Replaces `${VAR}` in string values, at any depth, with `lookup(VAR)`,
+    /// and `${VAR:-default}` with the default when `VAR` is unset or empty.
+    /// `$${` stands for a literal `${`. Keys are left alone.
+    ///
+    /// Placeholders naming unset variables without a default become empty
+    /// and are returned, ordered by path, for the caller to reject or accept.
+    pub fn substitute_vars(&mut self, lookup: &impl Fn(&str) -> Option<String>) -> Vec<Unresolved> {
+        let mut unresolved = vec![];
+        substitute_in_value(self, lookup, &mut Path::default(), &mut unresolved);
+        unresolved.sort_by_cached_key(|unresolved| unresolved.path.to_string());
+        unresolved
+    }
+}
+
+fn substitute_in_value(
+    value: &mut JSONValue,
+    lookup: &impl Fn(&str) -> Option<String>,
+    path: &mut Path,
+    unresolved: &mut Vec<Unresolved>,
+) {
+    match value {
+        JSONValue::String(text) if text.contains("${") => {
+            *text = substitute(text, lookup, &mut |name| {
+                unresolved.push(Unresolved {
+                    path: path.clone(),
+                    name: String::from(name),
+                })
+            });
+        }
+        JSONValue::Array(values) => {
+            for (idx, value) in values.iter_mut().enumerate() {
+                path.push(Segment::Index(idx));
+                substitute_in_value(value, lookup, path, unresolved);
+                path.pop();
+            }
+        }
+        JSONValue::Object(json) => {
+            for (key, value) in json.object.iter_mut() {
+                path.push(Segment::Key(key.to_string()));
+                substitute_in_value(value, lookup, path, unresolved);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn substitute(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    missing: &mut impl FnMut(&str),
+) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix("${")
+            .and_then(|body| Some(&body[..body.find('}')?]))
+            .filter(|body| is_name(body.split(":-").next().unwrap_or(body)));
+        let body = match placeholder {
+            Some(body) => body,
+            None => {
+                output.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        let (name, default) = match body.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (body, None),
+        };
+        match (lookup(name).filter(|value| !value.is_empty()), default) {
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) if lookup(name).is_some() => {}
+            (None, None) => missing(name),
+        }
+        rest = &rest[body.len() + 3..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn fill_in_value(
+    value: &mut JSONValue,
+    data: &JSONValue,
+    path: &mut Path,
+    unfilled: &mut Vec<Unfilled>,
+) {
+    match value {
+        JSONValue::String(text) if text.contains("{{") => {
+            let mut missing = |expression: &str| {
+                unfilled.push(Unfilled {
+                    path: path.clone(),
+                    expression: String::from(expression),
+                })
+            };
+            let whole = text
+                .strip_prefix("{{")
+                .and_then(|body| body.strip_suffix("}}"))
+                .filter(|body| !body.contains("}}"));
+            if let Some(expression) = whole {
+                match lookup_path(data, expression) {
+                    Some(found) => *value = found.clone(),
+                    None => missing(expression.trim()),
+                }
+            } else {
+                *text = fill(text, data, &mut missing);
+            }
+        }
+        JSONValue::Array(values) => {
+            for (idx, value) in values.iter_mut().enumerate() {
+                path.push(Segment::Index(idx));
+                fill_in_value(value, data, path, unfilled);
+                path.pop();
+            }
+        }
+        JSONValue::Object(json) => {
+            for (key, value) in json.object.iter_mut() {
+                path.push(Segment::Key(key.to_string()));
+                fill_in_value(value, data, path, unfilled);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn fill(text: &str, data: &JSONValue, missing: &mut impl FnMut(&str)) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find("}}") else {
+            break;
+        };
+
+        let placeholder = &rest[..end + 2];
+        match lookup_path(data, &placeholder[2..end]) {
+            Some(JSONValue::String(found)) => output.push_str(found),
+            Some(found) => output.push_str(&found.to_string()),
+            None => {
+                missing(placeholder[2..end].trim());
+                output.push_str(placeholder);
+            }
+        }
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn lookup_path<'a>(data: &'a JSONValue, expression: &str) -> Option<&'a JSONValue> {
+    let expression = Some(expression.trim()).filter(|expression| !expression.is_empty())?;
+    data.at(&Path::parse(expression).ok()?)
+}
+
+/// Whether `name` is a shell-style variable name: a letter or underscore,
+/// then letters, digits and underscores.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
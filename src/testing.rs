@@ -0,0 +1,137 @@
+//! Helpers for property tests, built with the `test-utils` feature: a
+//! generator of arbitrary documents and a check that printing and parsing
+//! them again changes nothing.
+
+use crate::{
+    parser::{JSONValue, ParseOptions, JSON},
+    printer::Style,
+};
+
+/// Characters that printers tend to get wrong, mixed into generated strings
+/// and keys far more often than chance would.
+const AWKWARD: [char; 12] = [
+    '"', '\\', '/', '\n', '\r', '\t', '\u{0}', '\u{1f}', '\u{7f}', 'é', '\u{2028}', '😀',
+];
+
+/// Generates arbitrary `JSONValue` trees from a seed. The same seed always
+/// gives the same trees, so a failing case can be replayed.
+pub struct Gen {
+    state: u64,
+    /// Deepest nesting generated, the root included.
+    pub max_depth: usize,
+    /// Most members or elements in one container.
+    pub max_len: usize,
+}
+
+impl Gen {
+    pub fn new(seed: u64) -> Gen {
+        Gen {
+            // xorshift gets stuck on zero.
+            state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+            max_depth: 6,
+            max_len: 6,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    pub fn value(&mut self) -> JSONValue {
+        self.value_at(1)
+    }
+
+    /// An object, as the root of a document usually is.
+    pub fn object(&mut self) -> JSON {
+        self.object_at(1)
+    }
+
+    fn value_at(&mut self, depth: usize) -> JSONValue {
+        let kinds = if depth < self.max_depth { 7 } else { 5 };
+        match self.below(kinds) {
+            0 => JSONValue::Null,
+            1 => JSONValue::Bool(self.below(2) == 0),
+            2 => JSONValue::Number(self.number()),
+            3 | 4 => JSONValue::String(self.string()),
+            5 => JSONValue::Array(
+                (0..self.below(self.max_len + 1))
+                    .map(|_| self.value_at(depth + 1))
+                    .collect(),
+            ),
+            _ => JSONValue::Object(self.object_at(depth + 1)),
+        }
+    }
+
+    fn object_at(&mut self, depth: usize) -> JSON {
+        let mut json = JSON::new();
+        for _ in 0..self.below(self.max_len + 1) {
+            let key = self.string();
+            let value = if depth < self.max_depth {
+                self.value_at(depth)
+            } else {
+                JSONValue::Null
+            };
+            json.insert(&key, value);
+        }
+        json
+    }
+
+    fn number(&mut self) -> i32 {
+        match self.below(4) {
+            0 => [0, -1, 1, i32::MIN, i32::MAX][self.below(5)],
+            1 => self.below(10) as i32,
+            _ => self.next() as i32,
+        }
+    }
+
+    fn string(&mut self) -> String {
+        (0..self.below(8))
+            .map(|_| match self.below(3) {
+                0 => AWKWARD[self.below(AWKWARD.len())],
+                _ => (b'a' + self.below(26) as u8) as char,
+            })
+            .collect()
+    }
+}
+
+/// Panics unless `value` prints, compactly and prettily, to text that parses
+/// back to `value`. Since documents must have an object or array at the
+/// root, any other value is checked inside an array.
+pub fn assert_round_trip(value: &JSONValue) {
+    let value = match value {
+        JSONValue::Object(_) | JSONValue::Array(_) => value.clone(),
+        _ => JSONValue::Array(vec![value.clone()]),
+    };
+
+    let options = ParseOptions::default();
+    for style in [Style::compact(), Style::default()] {
+        let printed = match value.try_to_styled_string(&style) {
+            Ok(printed) => printed,
+            Err(err) => panic!("{:?} does not print: {}", value, err),
+        };
+        match JSON::parse_from_string(&printed, &options) {
+            Ok(parsed) if parsed == value => {}
+            Ok(parsed) => panic!(
+                "{:?} printed as {} but parsed back as {:?}",
+                value, printed, parsed
+            ),
+            Err(err) => panic!("{:?} printed as {}, which fails: {}", value, printed, err),
+        }
+    }
+}
+
+/// Round-trips `cases` documents generated from `seed`.
+pub fn check_round_trips(seed: u64, cases: usize) {
+    let mut gen = Gen::new(seed);
+    for _ in 0..cases {
+        assert_round_trip(&gen.value());
+    }
+}
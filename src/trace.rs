@@ -0,0 +1,173 @@
+//! Spans around reading, decoding, parsing and printing a document, sent to
+//! `tracing` with the `tracing` feature and compiled away without it. There
+//! is no span for lexing: the parser reads tokens as it goes, so that time
+//! is part of `parse`.
+//!
+//! Every span has a `bytes` field, the size of the document it produced or,
+//! for `parse`, consumed. `parse` and `serialize` also count `nodes`, the
+//! values in the document, but only when a subscriber wants the span.
+
+use crate::parser::{JSONValue, JSON};
+use std::fmt::{self, Display, Write};
+
+/// An entered span, exited when dropped.
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)*) => {
+        Span {
+            span: tracing::debug_span!(
+                $name,
+                $($field = $value,)*
+                bytes = tracing::field::Empty,
+                nodes = tracing::field::Empty,
+            )
+            .entered(),
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)*) => {{
+        $(let _ = $value;)*
+        Span {}
+    }};
+}
+
+/// Reading the raw bytes of a file, stdin or URL.
+pub(crate) fn read(source: &str) -> Span {
+    span!("read", source = source)
+}
+
+/// Decompressing an input and checking that it is UTF-8.
+pub(crate) fn decode(source: &str, input_bytes: usize) -> Span {
+    span!("decode", source = source, input_bytes = input_bytes)
+}
+
+pub(crate) fn parse(bytes: usize) -> Span {
+    let span = span!("parse");
+    span.record_bytes(bytes);
+    span
+}
+
+pub(crate) fn serialize() -> Span {
+    span!("serialize")
+}
+
+impl Span {
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        #[cfg(feature = "tracing")]
+        self.span.record("bytes", bytes);
+        #[cfg(not(feature = "tracing"))]
+        let _ = bytes;
+    }
+
+    /// Records how many values `value` holds, if anyone is listening.
+    pub(crate) fn record_nodes(&self, value: Node) {
+        #[cfg(feature = "tracing")]
+        if !self.span.is_disabled() {
+            self.span.record("nodes", value.count());
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = value;
+    }
+
+    /// Records the size of what the stage produced, or why it failed.
+    pub(crate) fn record_result<T, E: Display>(
+        &self,
+        result: &Result<T, E>,
+        bytes: impl FnOnce(&T) -> usize,
+    ) {
+        match result {
+            Ok(value) => self.record_bytes(bytes(value)),
+            Err(err) => self.error(|| err.to_string()),
+        }
+    }
+
+    /// Emits an event for a stage that failed.
+    pub(crate) fn error(&self, message: impl FnOnce() -> String) {
+        #[cfg(feature = "tracing")]
+        if !self.span.is_disabled() {
+            tracing::debug!(error = %message(), "failed");
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = message;
+    }
+}
+
+/// A document whose values `Span::record_nodes` counts.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) enum Node<'a> {
+    Value(&'a JSONValue),
+    Object(&'a JSON),
+}
+
+impl Node<'_> {
+    #[cfg(feature = "tracing")]
+    fn count(self) -> usize {
+        match self {
+            Node::Value(JSONValue::Object(json)) | Node::Object(json) => {
+                1 + json
+                    .object
+                    .values()
+                    .map(|value| Node::Value(value).count())
+                    .sum::<usize>()
+            }
+            Node::Value(JSONValue::Array(values)) => {
+                1 + values
+                    .iter()
+                    .map(|value| Node::Value(value).count())
+                    .sum::<usize>()
+            }
+            Node::Value(_) => 1,
+        }
+    }
+}
+
+/// Passes writes through, counting their bytes for `serialize` spans.
+pub(crate) struct Tally<'a, W: Write> {
+    out: &'a mut W,
+    #[cfg(feature = "tracing")]
+    len: usize,
+}
+
+impl<'a, W: Write> Tally<'a, W> {
+    pub(crate) fn new(out: &'a mut W) -> Tally<'a, W> {
+        Tally {
+            out,
+            #[cfg(feature = "tracing")]
+            len: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        #[cfg(feature = "tracing")]
+        return self.len;
+        #[cfg(not(feature = "tracing"))]
+        0
+    }
+}
+
+impl<W: Write> Write for Tally<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        #[cfg(feature = "tracing")]
+        {
+            self.len += s.len();
+        }
+        self.out.write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        #[cfg(feature = "tracing")]
+        {
+            self.len += c.len_utf8();
+        }
+        self.out.write_char(c)
+    }
+}
@@ -0,0 +1,126 @@
+//! Outlines a document's structure without its values.
+
+use crate::parser::JSONValue;
+
+/// What was seen at one place in the document. The elements of an array
+/// are merged into a single node, so an array of a thousand records is
+/// outlined once, with the keys of all of them.
+#[derive(Default)]
+struct Node {
+    object: bool,
+    /// The shortest and longest array seen.
+    array: Option<(usize, usize)>,
+    scalars: Vec<&'static str>,
+    children: Vec<(String, Node)>,
+}
+
+impl Node {
+    fn from_value(value: &JSONValue) -> Node {
+        let mut node = Node::default();
+        match value {
+            JSONValue::Object(json) => {
+                node.object = true;
+                node.children = json
+                    .object
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), Node::from_value(value)))
+                    .collect();
+            }
+            JSONValue::Array(values) => {
+                node.array = Some((values.len(), values.len()));
+                let elements = values.iter().map(Node::from_value).reduce(Node::merge);
+                node.children
+                    .extend(elements.map(|node| (String::from("[]"), node)));
+            }
+            JSONValue::String(_) => node.scalars.push("string"),
+            JSONValue::Number(_) => node.scalars.push("number"),
+            #[cfg(feature = "i128")]
+            JSONValue::Int128(_) | JSONValue::UInt128(_) => node.scalars.push("number"),
+            #[cfg(feature = "bigdecimal")]
+            JSONValue::Decimal(_) | JSONValue::NonFinite(_) => node.scalars.push("number"),
+            JSONValue::Bool(_) => node.scalars.push("boolean"),
+            JSONValue::Null => node.scalars.push("null"),
+        }
+        node
+    }
+
+    fn merge(mut self, other: Node) -> Node {
+        self.object |= other.object;
+        self.array = match (self.array, other.array) {
+            (Some((a_min, a_max)), Some((b_min, b_max))) => {
+                Some((a_min.min(b_min), a_max.max(b_max)))
+            }
+            (a, b) => a.or(b),
+        };
+        for scalar in other.scalars {
+            if !self.scalars.contains(&scalar) {
+                self.scalars.push(scalar);
+            }
+        }
+
+        for (label, child) in other.children {
+            match self
+                .children
+                .iter()
+                .position(|(existing, _)| *existing == label)
+            {
+                Some(idx) => {
+                    let existing = std::mem::take(&mut self.children[idx].1);
+                    self.children[idx].1 = existing.merge(child);
+                }
+                None => self.children.push((label, child)),
+            }
+        }
+        self
+    }
+
+    /// `object`, `array[3]`, `array[0..12]`, `string | null` and so on.
+    fn kind(&self) -> String {
+        let mut kinds = vec![];
+        if self.object {
+            kinds.push(String::from("object"));
+        }
+        match self.array {
+            Some((min, max)) if min == max => kinds.push(format!("array[{}]", min)),
+            Some((min, max)) => kinds.push(format!("array[{}..{}]", min, max)),
+            None => {}
+        }
+        kinds.extend(self.scalars.iter().map(|scalar| scalar.to_string()));
+        kinds.join(" | ")
+    }
+
+    /// Writes the children in key order, each under `prefix`.
+    fn write(&self, prefix: &str, output: &mut String) {
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (idx, (label, child)) in children.iter().enumerate() {
+            let last = idx == children.len() - 1;
+            output.push_str(prefix);
+            output.push_str(if last { "└ " } else { "├ " });
+            output.push_str(&format!("{}: {}\n", label, child.kind()));
+            child.write(
+                &format!("{}{}", prefix, if last { "  " } else { "│ " }),
+                output,
+            );
+        }
+    }
+}
+
+impl JSONValue {
+    /// The document as an indented tree of keys annotated with their types
+    /// and array lengths, leaving out the values:
+    ///
+    /// ```text
+    /// object
+    /// └ users: array[1532]
+    ///   └ []: object
+    ///     ├ email: string | null
+    ///     └ name: string
+    /// ```
+    pub fn to_tree(&self) -> String {
+        let root = Node::from_value(self);
+        let mut output = format!("{}\n", root.kind());
+        root.write("", &mut output);
+        output
+    }
+}
@@ -1,13 +1,583 @@
-use std::env;
+use json_parser::{
+    bson::RootArrayPolicy,
+    case::KeyCase,
+    control::ControlPolicy,
+    formats::StringFormat,
+    merge::MergeStrategy,
+    parser::{JSONValue, ParseOptions, JSON},
+    path::Path,
+    printer::{NonFinitePolicy, Style},
+    redact::KeyPattern,
+    sample::ArraySample,
+};
+use std::{collections::HashMap, env, time::Duration};
 
-pub fn parse_args() -> Option<Vec<String>> {
-    let mut args = env::args();
+#[derive(Default, PartialEq)]
+pub enum Command {
+    #[default]
+    Validate,
+    Bench,
+    Merge,
+    Codegen,
+    Diff,
+    Split,
+    Hash,
+    Grep,
+    Index,
+    Template,
+    Browse,
+}
+
+#[derive(Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Json,
+    Bson,
+    Xml,
+}
+
+/// `diff --diff-format`: aligned lines for people or a JSON array for
+/// programs.
+#[derive(Default, PartialEq)]
+pub enum DiffFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// `--diagnostics`: errors as plain lines on stderr, or as LSP
+/// `publishDiagnostics` params on stdout, one line per input.
+#[derive(Default, PartialEq)]
+pub enum Diagnostics {
+    #[default]
+    Text,
+    Lsp,
+}
+
+#[derive(Default)]
+pub struct Args {
+    pub command: Command,
+    pub files: Vec<String>,
+    pub decompress: bool,
+    pub timeout: Option<Duration>,
+    pub style: Style,
+    pub options: ParseOptions,
+    pub ignore: Vec<String>,
+    pub summary_only: bool,
+    /// Stops after this many inputs fail, 1 with `--fail-fast`, rather than
+    /// checking every input, as it does by default, to report them all.
+    pub max_errors: Option<usize>,
+    /// Files checked at once. Their output still comes in argument order
+    /// unless `unordered`, when each file's is written as soon as it is done.
+    pub jobs: usize,
+    pub unordered: bool,
+    pub iterations: usize,
+    pub mmap: bool,
+    pub check: bool,
+    pub stream: bool,
+    pub get: Vec<Path>,
+    pub arrays: MergeStrategy,
+    pub to: Format,
+    pub from: Format,
+    pub bson_root_array: RootArrayPolicy,
+    pub raw_output: bool,
+    pub explode: bool,
+    pub jtd: Option<String>,
+    /// `--validate-dates`: strings under keys matching this must be RFC 3339
+    /// timestamps.
+    pub date_keys_pattern: Option<String>,
+    /// Built by `main` from the field above.
+    pub date_keys: Option<KeyPattern>,
+    /// `--check-formats format=regex`: strings under keys matching each
+    /// pattern must be in its format.
+    pub format_patterns: Vec<(StringFormat, String)>,
+    /// Built by `main` from the field above.
+    pub format_keys: Vec<(StringFormat, KeyPattern)>,
+    /// `--control`: stripping happens while parsing, while rejected
+    /// characters are all reported once the document is parsed. Values read
+    /// with `--get` are stripped as they are printed.
+    pub control: ControlPolicy,
+    /// Output that is the same bytes on every run and platform: keys sorted
+    /// and no color, whatever other flags say. Lines already always end in
+    /// `\n`, output ends with one, and numbers, being integers, have one form.
+    pub deterministic: bool,
+    /// Only reads files named after their format, as in `*.json`.
+    pub require_extension: bool,
+    /// With `Lsp`, reports replace the documents on stdout, even for inputs
+    /// that are valid, so an editor can clear what it showed before.
+    pub diagnostics: Diagnostics,
+    /// `--report sarif`: where to write a SARIF log of the run's problems.
+    pub sarif: Option<String>,
+    /// `--annotate github`: errors are printed as GitHub Actions workflow
+    /// commands on stdout instead of lines on stderr.
+    pub github_annotations: bool,
+    /// `diff --tolerance`: how far apart numbers may be and still match.
+    pub tolerance: f64,
+    pub diff_format: DiffFormat,
+    /// `grep`: the regular expression searched for, matched against keys too
+    /// with `--include-keys`.
+    pub grep_pattern: Option<String>,
+    pub grep_keys: bool,
+    pub grep_ignore_case: bool,
+    pub sort_array_by: Option<Path>,
+    pub pick: Vec<Path>,
+    pub omit: Vec<Path>,
+    pub drop_nulls: bool,
+    pub drop_empty: bool,
+    pub keys: Option<KeyCase>,
+    pub redact_pattern: Option<String>,
+    pub redact_ignore_case: bool,
+    /// Built by `main` from the two fields above.
+    pub redact: Option<KeyPattern>,
+    /// `--anonymize`, with the seed given by `--seed` or else picked by
+    /// `main`.
+    pub anonymize: bool,
+    pub seed: Option<u64>,
+    /// `--head`, `--tail` or `--sample`, at most one of them.
+    pub sample: Option<ArraySample>,
+    /// Prints the documents' structure instead of their values.
+    pub tree: bool,
+    /// Prints a one-line type signature instead of the documents.
+    pub describe: bool,
+    /// Prints the path of every leaf, or with `--all-nodes` of every value,
+    /// instead of the documents, written as JSON Pointers with
+    /// `--path-format pointer`.
+    pub paths: bool,
+    pub all_nodes: bool,
+    pub pointer_paths: bool,
+    /// Prints how often each type occurs at each field instead of the
+    /// documents.
+    pub type_histogram: bool,
+    /// Reports repeated keys instead of printing the documents.
+    pub find_duplicates: bool,
+    /// Prints each input with its comments removed instead of reformatting it.
+    pub strip_comments: bool,
+    /// Fills in `${VAR}` placeholders from the environment.
+    pub substitute_env: bool,
+    /// Lets placeholders naming unset variables become empty.
+    pub allow_missing: bool,
+    /// Every document read, from every input, goes into one array.
+    pub slurp: bool,
+    /// `split --chunk-size`: elements per file.
+    pub chunk_size: usize,
+    /// `split --out-dir`: where the chunks are written.
+    pub out_dir: String,
+    /// `split --by-key`: one file per member of a top-level object instead
+    /// of chunks of an array.
+    pub by_key: bool,
+    /// `template --data`: the document placeholders are filled in from.
+    pub data: String,
+}
+
+impl Args {
+    /// The extension input files must have, with `--require-extension`.
+    pub fn extension(&self) -> Option<&'static str> {
+        match (self.require_extension, &self.from) {
+            (false, _) => None,
+            (true, Format::Xml) => Some(".xml"),
+            (true, _) => Some(".json"),
+        }
+    }
+
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.ignore.iter().any(|glob| glob_matches(glob, path))
+    }
+
+    /// Whether `invalid` failed inputs are enough to stop at.
+    pub fn should_stop(&self, invalid: usize) -> bool {
+        self.max_errors.is_some_and(|max| invalid >= max)
+    }
+}
+
+pub fn parse_args(defaults: Args) -> Option<Args> {
+    // `env::args` panics on arguments that are not UTF-8; such a file name
+    // is reported as unreadable instead.
+    let mut args = env::args_os().map(|arg| arg.to_string_lossy().into_owned());
     args.next();
 
-    let files: Vec<String> = args.collect();
-    if files.len() == 0 {
+    let mut parsed = defaults;
+    let mut args = args.peekable();
+    match args.peek().map(String::as_str) {
+        Some("bench") => {
+            args.next();
+            parsed.command = Command::Bench;
+            parsed.iterations = 100;
+        }
+        Some("merge") => {
+            args.next();
+            parsed.command = Command::Merge;
+        }
+        Some("codegen") => {
+            args.next();
+            parsed.command = Command::Codegen;
+        }
+        Some("diff") => {
+            args.next();
+            parsed.command = Command::Diff;
+        }
+        Some("split") => {
+            args.next();
+            parsed.command = Command::Split;
+        }
+        Some("hash") => {
+            args.next();
+            parsed.command = Command::Hash;
+        }
+        Some("grep") => {
+            args.next();
+            parsed.command = Command::Grep;
+        }
+        Some("index") => {
+            args.next();
+            parsed.command = Command::Index;
+        }
+        Some("template") => {
+            args.next();
+            parsed.command = Command::Template;
+        }
+        Some("browse") => {
+            args.next();
+            parsed.command = Command::Browse;
+        }
+        _ => {}
+    }
+
+    // Paths are read once every `--arg` is known, wherever it appears.
+    let mut vars = HashMap::new();
+    let (mut get, mut pick, mut omit, mut sort_array_by) = (vec![], vec![], vec![], None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--decompress" => parsed.decompress = true,
+            "--mmap" => parsed.mmap = true,
+            "--check" => parsed.check = true,
+            "--threads" => parsed.options.threads = args.next()?.parse().ok()?,
+            "--stream" => parsed.stream = true,
+            "--slurp" => parsed.slurp = true,
+            "--tree" => parsed.tree = true,
+            "--describe" => parsed.describe = true,
+            "--type-histogram" => parsed.type_histogram = true,
+            "--paths" => parsed.paths = true,
+            "--all-nodes" => parsed.all_nodes = true,
+            "--path-format" => {
+                parsed.pointer_paths = match args.next()?.as_str() {
+                    "dotted" => false,
+                    "pointer" => true,
+                    _ => return None,
+                }
+            }
+            "--find-duplicates" => parsed.find_duplicates = true,
+            "--strip-comments" => parsed.strip_comments = true,
+            "--substitute-env" => parsed.substitute_env = true,
+            "--allow-missing" => parsed.allow_missing = true,
+            "--to" => {
+                parsed.to = match args.next()?.as_str() {
+                    "json" => Format::Json,
+                    "bson" => Format::Bson,
+                    "xml" => Format::Xml,
+                    _ => return None,
+                }
+            }
+            "--from" => {
+                parsed.from = match args.next()?.as_str() {
+                    "json" => Format::Json,
+                    "xml" => Format::Xml,
+                    _ => return None,
+                }
+            }
+            "--bson-root-array" => {
+                parsed.bson_root_array = RootArrayPolicy::from_name(&args.next()?)?
+            }
+            "-r" | "--raw-output" => parsed.raw_output = true,
+            "--explode" => parsed.explode = true,
+            "--jtd" => parsed.jtd = Some(args.next()?),
+            "--validate-dates" => parsed.date_keys_pattern = Some(args.next()?),
+            "--check-formats" => {
+                let check = args.next()?;
+                let (format, pattern) = check.split_once('=')?;
+                parsed
+                    .format_patterns
+                    .push((StringFormat::from_name(format)?, pattern.to_string()));
+            }
+            "--control" => {
+                parsed.control = ControlPolicy::from_name(&args.next()?)?;
+                if parsed.control == ControlPolicy::Strip {
+                    parsed.options.control = ControlPolicy::Strip;
+                }
+            }
+            "--get" => get.push(args.next()?),
+            "--arg" => {
+                let name = args.next()?;
+                vars.insert(name, JSONValue::from(args.next()?));
+            }
+            "--argjson" => {
+                let name = args.next()?;
+                vars.insert(name, parse_scalar(&args.next()?)?);
+            }
+            "--head" | "--tail" | "--sample" if parsed.sample.is_none() => {
+                let len = args.next()?.parse().ok()?;
+                parsed.sample = Some(match arg.as_str() {
+                    "--head" => ArraySample::Head(len),
+                    "--tail" => ArraySample::Tail(len),
+                    _ => ArraySample::Sample(len),
+                });
+            }
+            "--anonymize" => parsed.anonymize = true,
+            "--seed" => parsed.seed = Some(args.next()?.parse().ok()?),
+            "--redact" => parsed.redact_pattern = Some(args.next()?),
+            "--redact-ignore-case" => parsed.redact_ignore_case = true,
+            "--keys" => parsed.keys = Some(KeyCase::from_name(&args.next()?)?),
+            "--drop-nulls" => parsed.drop_nulls = true,
+            "--drop-empty" => parsed.drop_empty = true,
+            "--pick" => pick.push(args.next()?),
+            "--omit" => omit.push(args.next()?),
+            "--sort-array-by" => sort_array_by = Some(args.next()?),
+            "--timeout" => {
+                let secs = args.next()?.parse::<f64>().ok()?;
+                parsed.timeout = Some(Duration::try_from_secs_f64(secs).ok()?);
+            }
+            "--indent" => {
+                parsed.style.indent = args.next()?.parse().ok()?;
+                parsed.style.compact = false;
+            }
+            "--pretty" => parsed.style.compact = false,
+            "--compact" => parsed.style.compact = true,
+            "--color" => parsed.style.color = true,
+            "--no-color" => parsed.style.color = false,
+            "--escape-unicode" => parsed.style.escape_unicode = true,
+            "--escape-slash" => parsed.style.escape_slash = true,
+            "--uppercase-hex" => parsed.style.uppercase_hex = true,
+            "--non-finite" => {
+                parsed.style.non_finite = NonFinitePolicy::from_name(&args.next()?)?;
+            }
+            "--crlf" => parsed.style.crlf = true,
+            "--lf" => parsed.style.crlf = false,
+            "--final-newline" => parsed.style.final_newline = true,
+            "--no-final-newline" => parsed.style.final_newline = false,
+            "--deterministic" => parsed.deterministic = true,
+            "--strict" => parsed.options.lenient = false,
+            "--lenient" => parsed.options.lenient = true,
+            "--lossy" => parsed.options.lossy = true,
+            "--require-extension" => parsed.require_extension = true,
+            "--ignore" => parsed.ignore.push(args.next()?),
+            "--summary-only" => parsed.summary_only = true,
+            "--jobs" => parsed.jobs = args.next()?.parse().ok().filter(|&jobs| jobs > 0)?,
+            "--unordered" => parsed.unordered = true,
+            "--fail-fast" => parsed.max_errors = Some(1),
+            "--max-errors" => {
+                parsed.max_errors = Some(args.next()?.parse().ok().filter(|&max| max > 0)?)
+            }
+            "--max-depth" => parsed.options.limits.max_depth = Some(args.next()?.parse().ok()?),
+            "--max-bytes" => parsed.options.limits.max_bytes = Some(args.next()?.parse().ok()?),
+            "--iterations" if parsed.command == Command::Bench => {
+                parsed.iterations = args.next()?.parse().ok()?
+            }
+            "--lang" if parsed.command == Command::Codegen => match args.next()?.as_str() {
+                "ts" | "typescript" => {}
+                _ => return None,
+            },
+            "--tolerance" if parsed.command == Command::Diff => {
+                parsed.tolerance = args
+                    .next()?
+                    .parse()
+                    .ok()
+                    .filter(|tolerance| *tolerance >= 0.0)?
+            }
+            "--chunk-size" if parsed.command == Command::Split => {
+                parsed.chunk_size = args.next()?.parse().ok().filter(|&size| size > 0)?
+            }
+            "--out-dir" if parsed.command == Command::Split => parsed.out_dir = args.next()?,
+            "--by-key" if parsed.command == Command::Split => parsed.by_key = true,
+            "--data" if parsed.command == Command::Template => parsed.data = args.next()?,
+            "--diff-format" if parsed.command == Command::Diff => {
+                parsed.diff_format = match args.next()?.as_str() {
+                    "text" => DiffFormat::Text,
+                    "json" => DiffFormat::Json,
+                    _ => return None,
+                }
+            }
+            "--diagnostics" => {
+                parsed.diagnostics = match args.next()?.as_str() {
+                    "text" => Diagnostics::Text,
+                    "lsp" => Diagnostics::Lsp,
+                    _ => return None,
+                }
+            }
+            "--report" => match args.next()?.as_str() {
+                "sarif" => parsed.sarif = Some(args.next()?),
+                _ => return None,
+            },
+            "--annotate" => match args.next()?.as_str() {
+                "github" => parsed.github_annotations = true,
+                _ => return None,
+            },
+            "--include-keys" if parsed.command == Command::Grep => parsed.grep_keys = true,
+            "-i" | "--ignore-case" if parsed.command == Command::Grep => {
+                parsed.grep_ignore_case = true
+            }
+            "--arrays" if parsed.command == Command::Merge => {
+                parsed.arrays = MergeStrategy::from_name(&args.next()?)?
+            }
+            "--max-memory" => parsed.options.limits.max_memory = Some(args.next()?.parse().ok()?),
+            "--max-nodes" => parsed.options.limits.max_nodes = Some(args.next()?.parse().ok()?),
+            "--max-string-len" => {
+                parsed.options.limits.max_string_len = Some(args.next()?.parse().ok()?)
+            }
+            "--max-members" => parsed.options.limits.max_members = Some(args.next()?.parse().ok()?),
+            "--max-array-len" => {
+                parsed.options.limits.max_array_len = Some(args.next()?.parse().ok()?)
+            }
+            _ if arg.starts_with("--") => return None,
+            _ => parsed.files.push(arg),
+        }
+    }
+
+    for path in get {
+        parsed.get.push(Path::parse_with_vars(&path, &vars).ok()?);
+    }
+    for list in pick {
+        parsed.pick.extend(parse_path_list(&list, &vars)?);
+    }
+    for list in omit {
+        parsed.omit.extend(parse_path_list(&list, &vars)?);
+    }
+    if let Some(path) = sort_array_by {
+        parsed.sort_array_by = Some(Path::parse_with_vars(&path, &vars).ok()?);
+    }
+
+    if parsed.deterministic {
+        parsed.style.sort_keys = true;
+        parsed.style.color = false;
+    }
+    if parsed.stream
+        && (parsed.to != Format::Json
+            || parsed.from != Format::Json
+            || parsed.slurp
+            || parsed.options.lossy
+            || parsed.raw_output
+            || parsed.date_keys_pattern.is_some()
+            || !parsed.format_patterns.is_empty()
+            || parsed.style.escape_unicode
+            || parsed.style.escape_slash
+            || parsed.style.uppercase_hex)
+    {
+        return None;
+    }
+    if (parsed.all_nodes || parsed.pointer_paths) && !parsed.paths {
+        return None;
+    }
+    if parsed.seed.is_some() && !parsed.anonymize {
+        return None;
+    }
+    if parsed.allow_missing && !parsed.substitute_env {
+        return None;
+    }
+    if (parsed.strip_comments || parsed.find_duplicates) && (parsed.slurp || !parsed.get.is_empty())
+    {
+        return None;
+    }
+    if parsed.slurp && (parsed.stream || !parsed.get.is_empty()) {
+        return None;
+    }
+    if parsed.command == Command::Diff && parsed.files.len() != 2 {
+        return None;
+    }
+    if parsed.command == Command::Grep {
+        if parsed.files.len() < 2 {
+            return None;
+        }
+        parsed.grep_pattern = Some(parsed.files.remove(0));
+    }
+    if parsed.command == Command::Split
+        && (parsed.files.len() != 1
+            || parsed.by_key == (parsed.chunk_size > 0)
+            || parsed.out_dir.is_empty())
+    {
+        return None;
+    }
+
+    if parsed.command == Command::Template && (parsed.files.len() != 1 || parsed.data.is_empty()) {
+        return None;
+    }
+    if parsed.command == Command::Browse && parsed.files.len() != 1 {
+        return None;
+    }
+
+    // Stdin can only be read once.
+    if parsed
+        .files
+        .iter()
+        .chain([&parsed.data])
+        .filter(|&file| file == "-")
+        .count()
+        > 1
+    {
+        return None;
+    }
+    if parsed.files.is_empty() && (!parsed.decompress || parsed.command != Command::Validate) {
         None
     } else {
-        Some(files)
+        Some(parsed)
+    }
+}
+
+/// Splits `a,b.c,["x,y"]` into paths at the commas outside quoted keys.
+fn parse_path_list(list: &str, vars: &HashMap<String, JSONValue>) -> Option<Vec<Path>> {
+    let mut paths = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (idx, ch) in list.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                paths.push(Path::parse_with_vars(&list[start..idx], vars).ok()?);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    paths.push(Path::parse_with_vars(&list[start..], vars).ok()?);
+    Some(paths)
+}
+
+/// Parses an `--argjson` value, which unlike a document may be a scalar.
+fn parse_scalar(text: &str) -> Option<JSONValue> {
+    let wrapped = format!("[{}]", text);
+    match JSON::parse_from_string(&wrapped, &ParseOptions::default()).ok()? {
+        JSONValue::Array(mut values) if values.len() == 1 => values.pop(),
+        _ => None,
+    }
+}
+
+/// Matches `path` against a glob supporting `*`, `?` and `**`. Patterns
+/// without a `/` are matched against the file name alone.
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    let target = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    glob_matches_from(&pattern, &target)
+}
+
+fn glob_matches_from(pattern: &[char], target: &[char]) -> bool {
+    match pattern.first() {
+        None => target.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => match pattern[2..].strip_prefix(&['/']) {
+            Some(rest) => (0..=target.len())
+                .filter(|&idx| idx == 0 || target[idx - 1] == '/')
+                .any(|idx| glob_matches_from(rest, &target[idx..])),
+            None => (0..=target.len()).any(|idx| glob_matches_from(&pattern[2..], &target[idx..])),
+        },
+        Some('*') => (0..=target.len())
+            .take_while(|&idx| idx == 0 || target[idx - 1] != '/')
+            .any(|idx| glob_matches_from(&pattern[1..], &target[idx..])),
+        Some('?') => {
+            !target.is_empty() && target[0] != '/' && glob_matches_from(&pattern[1..], &target[1..])
+        }
+        Some(ch) => target.first() == Some(ch) && glob_matches_from(&pattern[1..], &target[1..]),
     }
 }
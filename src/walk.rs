@@ -0,0 +1,70 @@
+use crate::{
+    parser::JSONValue,
+    path::{Path, Segment},
+};
+
+/// Every value in a document, the root included, with its path, in
+/// document order: a value comes before its children. Built by
+/// `JSONValue::walk`. Uses its own stack rather than recursion, so the
+/// deepest document a parser accepts can be walked.
+pub struct Walk<'a> {
+    stack: Vec<(Path, &'a JSONValue)>,
+}
+
+impl JSONValue {
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(Path::default(), self)],
+        }
+    }
+
+    /// The path of every leaf, meaning every value other than a non-empty
+    /// object or array, or with `all_nodes` of every value below the root.
+    /// Keys sort as text and indices as numbers, so `a[2]` comes before
+    /// `a[10]`.
+    pub fn paths(&self, all_nodes: bool) -> Vec<Path> {
+        let mut paths: Vec<Path> = self
+            .walk()
+            .filter(|(path, value)| {
+                let leaf = match value {
+                    JSONValue::Array(values) => values.is_empty(),
+                    JSONValue::Object(json) => json.object.is_empty(),
+                    _ => true,
+                };
+                !path.segments().is_empty() && (all_nodes || leaf)
+            })
+            .map(|(path, _)| path)
+            .collect();
+        paths.sort_by(|a, b| a.segments().cmp(b.segments()));
+        paths
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (Path, &'a JSONValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+        let child = |segment| {
+            let mut path = path.clone();
+            path.push(segment);
+            path
+        };
+        match value {
+            JSONValue::Array(values) => self.stack.extend(
+                values
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(idx, value)| (child(Segment::Index(idx)), value)),
+            ),
+            JSONValue::Object(json) => self.stack.extend(
+                json.object
+                    .iter()
+                    .map(|(key, value)| (child(Segment::Key(key.to_string())), value)),
+            ),
+            _ => {}
+        }
+        Some((path, value))
+    }
+}
@@ -0,0 +1,139 @@
+use crate::parser::{ArgsParseError, JSONValue, ParseOptions, Parser, JSON};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub parse: ParseOptions,
+    /// How often the file is looked at.
+    pub poll_interval: Duration,
+    /// How long the file must stay unchanged before it is parsed, so a
+    /// write in several steps is read once, when it is done.
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            parse: ParseOptions::default(),
+            poll_interval: Duration::from_millis(250),
+            debounce: Duration::from_millis(100),
+        }
+    }
+}
+
+/// What is known of the file without reading it, `None` while it does not
+/// exist, as between an editor removing it and renaming a new one into place.
+/// A rewrite that keeps the length within the filesystem's timestamp
+/// resolution goes unnoticed.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// A file being watched by `JSON::watch`. Dropping it stops the watch.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Stops the watch, waiting for a callback in progress to return.
+    pub fn stop(self) {}
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl JSON {
+    /// Re-parses the file at `path` on a background thread each time it is
+    /// modified, passing `callback` the new document or why it failed to
+    /// parse. The file as it is now is not reported, only later changes, so
+    /// callers load it first themselves. Fails if the file cannot be found.
+    pub fn watch(
+        path: impl AsRef<Path>,
+        callback: impl FnMut(Result<JSONValue, ArgsParseError>) + Send + 'static,
+    ) -> Result<Watcher, ArgsParseError> {
+        JSON::watch_with(path, WatchOptions::default(), callback)
+    }
+
+    pub fn watch_with(
+        path: impl AsRef<Path>,
+        options: WatchOptions,
+        mut callback: impl FnMut(Result<JSONValue, ArgsParseError>) + Send + 'static,
+    ) -> Result<Watcher, ArgsParseError> {
+        let path = path.as_ref().to_path_buf();
+        if let Err(err) = fs::metadata(&path) {
+            return Err(ArgsParseError(format!("{}: {}", path.display(), err)));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::spawn(move || {
+            let mut seen = stamp(&path);
+            while let Some(changed) = next_change(&path, &options, &mut seen, &stopped) {
+                // A file removed and not replaced has nothing to parse.
+                if changed.is_some() {
+                    callback(Parser::default().parse_from_file(&path, &options.parse));
+                }
+            }
+        });
+
+        Ok(Watcher {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Waits for the file to change from `seen` and then settle for the
+/// debounce period, returning what it settled on. `None` once stopped.
+fn next_change(
+    path: &Path,
+    options: &WatchOptions,
+    seen: &mut Stamp,
+    stopped: &AtomicBool,
+) -> Option<Stamp> {
+    let mut settling: Option<(Stamp, Instant)> = None;
+    loop {
+        let wait = match settling {
+            Some(_) => options.poll_interval.min(options.debounce),
+            None => options.poll_interval,
+        };
+        thread::park_timeout(wait);
+        if stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let current = stamp(path);
+        settling = match settling {
+            None if current == *seen => None,
+            Some((last, since)) if current == last => {
+                if since.elapsed() < options.debounce {
+                    Some((last, since))
+                } else {
+                    *seen = current;
+                    return Some(current);
+                }
+            }
+            _ => Some((current, Instant::now())),
+        };
+    }
+}
@@ -0,0 +1,412 @@
+//! Conversion between documents and XML.
+//!
+//! Conventions, in both directions:
+//! - The document is the content of a `<root>` element.
+//! - Each object member becomes a child element named after its key. Characters
+//!   not allowed in XML names are replaced with `_` when writing.
+//! - An array becomes one element per item, all with the array's key, so
+//!   `"tags": ["a", "b"]` is `<tags>a</tags><tags>b</tags>`. Items of a root or
+//!   nested array are `<item>` elements. Reading, repeated elements become an
+//!   array.
+//! - Members whose key starts with `@` are attributes and a `#text` member is
+//!   the element's text, so `{"a": {"@id": "1", "#text": "x"}}` is
+//!   `<a id="1">x</a>`.
+//! - `null` is an empty element. Reading, an empty element is `null` and any
+//!   other text is a string, since XML does not say whether `1` is a number.
+
+use crate::{
+    intern::Interner,
+    parser::{JSONValue, JSON, MAX_DEPTH},
+};
+use std::fmt::{Display, Write};
+
+pub struct XmlError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for XmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl JSONValue {
+    /// Writes the value as an XML document, indenting nested elements by
+    /// `indent` spaces per level.
+    pub fn to_xml(&self, indent: usize) -> String {
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        write_element(&mut output, "root", self, indent, 0);
+        output
+    }
+}
+
+impl JSON {
+    /// Reads an XML document into an object holding the root element's
+    /// content.
+    pub fn from_xml(content: &str) -> Result<JSON, XmlError> {
+        let mut reader = Reader {
+            content,
+            pos: 0,
+            interner: Interner::default(),
+            depth: 0,
+        };
+        reader.skip_prolog()?;
+        let (_, value) = reader.element()?;
+        reader.skip_misc();
+        if reader.pos < content.len() {
+            return Err(reader.error("content after the root element"));
+        }
+
+        match value {
+            JSONValue::Object(json) => Ok(json),
+            JSONValue::Null => Ok(JSON::new()),
+            value => {
+                let mut json = JSON::new();
+                json.insert("#text", value);
+                Ok(json)
+            }
+        }
+    }
+}
+
+fn write_element(output: &mut String, name: &str, value: &JSONValue, indent: usize, depth: usize) {
+    let padding = " ".repeat(indent * depth);
+    let name = element_name(name);
+    match value {
+        JSONValue::Array(values) => {
+            let _ = writeln!(output, "{}<{}>", padding, name);
+            for value in values {
+                write_element(output, "item", value, indent, depth + 1);
+            }
+            let _ = writeln!(output, "{}</{}>", padding, name);
+        }
+        JSONValue::Null => {
+            let _ = writeln!(output, "{}<{}/>", padding, name);
+        }
+        JSONValue::Object(json) => {
+            let mut keys: Vec<_> = json.object.keys().collect();
+            keys.sort();
+
+            let _ = write!(output, "{}<{}", padding, name);
+            for key in keys.iter().filter(|key| key.starts_with('@')) {
+                let text = scalar_text(&json.object[*key]);
+                let _ = write!(
+                    output,
+                    " {}=\"{}\"",
+                    element_name(&key[1..]),
+                    escape(&text, true)
+                );
+            }
+
+            let text = json.object.get("#text").map(scalar_text);
+            let children: Vec<_> = keys
+                .iter()
+                .filter(|key| !key.starts_with('@') && &key[..] != "#text")
+                .collect();
+            match (text, children.is_empty()) {
+                (None, true) => output.push_str("/>\n"),
+                (Some(text), true) => {
+                    let _ = writeln!(output, ">{}</{}>", escape(&text, false), name);
+                }
+                (text, false) => {
+                    output.push_str(">\n");
+                    if let Some(text) = text {
+                        let _ = writeln!(
+                            output,
+                            "{}{}",
+                            " ".repeat(indent * (depth + 1)),
+                            escape(&text, false)
+                        );
+                    }
+                    for key in children {
+                        let value = &json.object[*key];
+                        if let JSONValue::Array(values) = value {
+                            for value in values {
+                                write_element(output, key, value, indent, depth + 1);
+                            }
+                        } else {
+                            write_element(output, key, value, indent, depth + 1);
+                        }
+                    }
+                    let _ = writeln!(output, "{}</{}>", padding, name);
+                }
+            }
+        }
+        value => {
+            let _ = writeln!(
+                output,
+                "{}<{}>{}</{}>",
+                padding,
+                name,
+                escape(&scalar_text(value), false),
+                name
+            );
+        }
+    }
+}
+
+fn scalar_text(value: &JSONValue) -> String {
+    match value {
+        JSONValue::String(val) => val.clone(),
+        JSONValue::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn element_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+struct Reader<'a> {
+    content: &'a str,
+    pos: usize,
+    interner: Interner,
+    /// Elements currently open below the root.
+    depth: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> XmlError {
+        XmlError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.content[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips past the next `end`, returning the text before it.
+    fn skip_past(&mut self, end: &str) -> Result<&str, XmlError> {
+        match self.rest().find(end) {
+            Some(idx) => {
+                let text = &self.content[self.pos..self.pos + idx];
+                self.pos += idx + end.len();
+                Ok(text)
+            }
+            None => Err(self.error(&format!("missing `{}`", end))),
+        }
+    }
+
+    /// Skips whitespace, comments and processing instructions.
+    fn skip_misc(&mut self) {
+        loop {
+            self.skip_whitespace();
+            let end = if self.rest().starts_with("<!--") {
+                "-->"
+            } else if self.rest().starts_with("<?") {
+                "?>"
+            } else {
+                return;
+            };
+            if self.skip_past(end).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn skip_prolog(&mut self) -> Result<(), XmlError> {
+        self.skip_misc();
+        if self.rest().starts_with("<!DOCTYPE") {
+            self.skip_past(">")?;
+            self.skip_misc();
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, XmlError> {
+        let len = self
+            .rest()
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '/' | '>' | '='))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    /// Reads an element starting at its `<`, returning its name and value.
+    fn element(&mut self) -> Result<(String, JSONValue), XmlError> {
+        if !self.rest().starts_with('<') {
+            return Err(self.error("expected an element"));
+        }
+        self.pos += 1;
+        let name = self.name()?;
+
+        let mut json = JSON::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok((name, element_value(json, String::new())));
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("expected `=`"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.pos += 1;
+            let start = self.pos;
+            let value = self.skip_past(&quote.to_string())?.to_string();
+            let value = unescape(&value).map_err(|message| XmlError {
+                offset: start,
+                message,
+            })?;
+            json.insert(&format!("@{}", attribute), value);
+        }
+
+        let mut text = String::new();
+        loop {
+            let start = self.pos;
+            let len = self.rest().find('<').unwrap_or(self.rest().len());
+            let raw = &self.content[start..start + len];
+            text.push_str(&unescape(raw).map_err(|message| XmlError {
+                offset: start,
+                message,
+            })?);
+            self.pos += len;
+
+            if self.rest().is_empty() {
+                return Err(self.error(&format!("missing `</{}>`", name)));
+            } else if self.rest().starts_with("</") {
+                self.pos += 2;
+                if self.name()? != name {
+                    return Err(self.error(&format!("expected `</{}>`", name)));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("expected `>`"));
+                }
+                self.pos += 1;
+                return Ok((name, element_value(json, text)));
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                text.push_str(self.skip_past("]]>")?);
+            } else if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else {
+                if self.depth + 1 >= MAX_DEPTH {
+                    return Err(self.error("elements are nested too deeply"));
+                }
+                self.depth += 1;
+                let (child, value) = self.element()?;
+                self.depth -= 1;
+                let key = self.interner.intern(&child);
+                match json.object.get_mut(&key) {
+                    Some(JSONValue::Array(values)) => values.push(value),
+                    Some(existing) => {
+                        let first = std::mem::replace(existing, JSONValue::Null);
+                        *existing = JSONValue::Array(vec![first, value]);
+                    }
+                    None => {
+                        json.object.insert(key, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An element with only text is a string (or `null` when empty); anything
+/// else is an object, with non-blank text kept under `#text`.
+fn element_value(mut json: JSON, text: String) -> JSONValue {
+    if json.object.is_empty() {
+        return if text.is_empty() {
+            JSONValue::Null
+        } else {
+            JSONValue::String(text)
+        };
+    }
+
+    if !text.trim().is_empty() {
+        json.insert("#text", text.trim());
+    }
+    JSONValue::Object(json)
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        output.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => return Err(String::from("unterminated entity")),
+        };
+        let entity = &rest[1..end];
+        let ch = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|dec| dec.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        match ch {
+            Some(ch) => output.push(ch),
+            None => return Err(format!("unknown entity `&{};`", entity)),
+        }
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
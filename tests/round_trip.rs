@@ -0,0 +1,393 @@
+use json_parser::{
+    parser::{JSONValue, JSON},
+    testing::{assert_round_trip, check_round_trips, Gen},
+};
+
+#[test]
+fn generated_documents_round_trip() {
+    for seed in 0..20 {
+        check_round_trips(seed, 500);
+    }
+}
+
+#[test]
+fn deep_documents_round_trip() {
+    let mut gen = Gen::new(7);
+    gen.max_depth = 40;
+    gen.max_len = 2;
+    for _ in 0..200 {
+        assert_round_trip(&JSONValue::Object(gen.object()));
+    }
+}
+
+#[test]
+fn awkward_keys_and_strings_round_trip() {
+    let mut json = JSON::new();
+    for text in [
+        "",
+        "\"",
+        "\\",
+        "a b",
+        "\u{0}\u{1f}",
+        "\n\r\t",
+        "é😀",
+        "\u{2028}",
+    ] {
+        json.insert(text, text);
+    }
+    assert_round_trip(&JSONValue::Object(json));
+}
+
+#[test]
+fn scalar_roots_round_trip() {
+    for value in [
+        JSONValue::Null,
+        JSONValue::Bool(true),
+        JSONValue::Number(i32::MIN),
+        JSONValue::Number(i32::MAX),
+        JSONValue::String(String::from("\"quoted\"")),
+    ] {
+        assert_round_trip(&value);
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn decimals_print_as_written() {
+    let options = json_parser::parser::ParseOptions::default();
+    for text in [
+        "[0.1]",
+        "[1.50]",
+        "[-2.5e-7]",
+        "[1e+400]",
+        "[12345678901234567890]",
+    ] {
+        let value = JSON::parse_from_string(text, &options).unwrap();
+        assert_eq!(value.to_string(), text);
+        assert_round_trip(&value);
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn canonical_numbers_follow_ecmascript() {
+    let options = json_parser::parser::ParseOptions::default();
+    let canonical = |text: &str| {
+        let value = JSON::parse_from_string(text, &options).unwrap();
+        value.to_canonical_string()
+    };
+    let cases = [
+        (r#"{"n":1.0}"#, r#"{"n":1}"#),
+        (r#"{"n":1E0}"#, r#"{"n":1}"#),
+        (r#"{"n":-0.0}"#, r#"{"n":0}"#),
+        (r#"{"n":1.50}"#, r#"{"n":1.5}"#),
+        (r#"{"n":0.000001}"#, r#"{"n":0.000001}"#),
+        (r#"{"n":1E-7}"#, r#"{"n":1e-7}"#),
+        (r#"{"n":1e21}"#, r#"{"n":1e+21}"#),
+        (r#"{"n":123e18}"#, r#"{"n":123000000000000000000}"#),
+        (
+            r#"{"n":12345678901234567890}"#,
+            r#"{"n":12345678901234567000}"#,
+        ),
+        (r#"{"n":-2.5e-7}"#, r#"{"n":-2.5e-7}"#),
+        (r#"{"n":4.35}"#, r#"{"n":4.35}"#),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(canonical(text).unwrap(), expected, "{}", text);
+    }
+    assert!(canonical(r#"{"n":1e400}"#).is_err());
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn floats_convert_through_decimals() {
+    use json_parser::convert::{FromJson, ToJson};
+
+    let options = json_parser::parser::ParseOptions::default();
+    let value = JSON::parse_from_string("[1.5, 0.1, -3, 1e300]", &options).unwrap();
+    let floats = Vec::<f64>::from_json(&value).unwrap();
+    assert_eq!(floats, [1.5, 0.1, -3.0, 1e300]);
+    assert_eq!(floats.to_json().unwrap().to_string(), value.to_string());
+    assert_eq!(JSONValue::try_from(1.5).unwrap().to_string(), "1.5");
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn non_finite_floats_follow_the_style() {
+    use json_parser::{
+        convert::FromJson,
+        parser::ParseOptions,
+        printer::{NonFinitePolicy, Style},
+    };
+
+    let value = JSONValue::from(vec![
+        JSONValue::try_from(f64::NAN).unwrap(),
+        JSONValue::try_from(f64::INFINITY).unwrap(),
+        JSONValue::try_from(f64::NEG_INFINITY).unwrap(),
+    ]);
+    let write = |non_finite| {
+        value.try_to_styled_string(&Style {
+            non_finite,
+            ..Style::compact()
+        })
+    };
+    assert!(write(NonFinitePolicy::Error).is_err());
+    assert!(value.serialized_len(&Style::compact()).is_err());
+    assert!(value.to_canonical_string().is_err());
+    assert_eq!(write(NonFinitePolicy::Null).unwrap(), "[null,null,null]");
+    assert_eq!(
+        write(NonFinitePolicy::String).unwrap(),
+        r#"["NaN","Infinity","-Infinity"]"#
+    );
+    let literal = write(NonFinitePolicy::Literal).unwrap();
+    assert_eq!(literal, "[NaN,Infinity,-Infinity]");
+
+    assert!(JSON::parse_from_string(&literal, &ParseOptions::default()).is_err());
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let parsed = JSON::parse_from_string(&literal, &options).unwrap();
+    let floats = Vec::<f64>::from_json(&parsed).unwrap();
+    assert!(floats[0].is_nan());
+    assert_eq!(floats[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+}
+
+#[cfg(feature = "i128")]
+#[test]
+fn wide_integers_round_trip() {
+    for value in [
+        JSONValue::from(i128::MIN),
+        JSONValue::from(i128::MAX),
+        JSONValue::from(u128::MAX),
+        JSONValue::from(i64::MAX as i128),
+    ] {
+        assert_round_trip(&value);
+    }
+}
+
+#[test]
+fn every_entry_point_applies_limits_and_lenient() {
+    use json_parser::{limits::Limits, parser::ParseOptions};
+
+    let text = "[[[1,2,3,4]]]";
+    for limits in [
+        Limits {
+            max_depth: Some(1),
+            ..Limits::default()
+        },
+        Limits {
+            max_nodes: Some(2),
+            ..Limits::default()
+        },
+        Limits {
+            max_memory: Some(10),
+            ..Limits::default()
+        },
+        Limits {
+            max_array_len: Some(3),
+            ..Limits::default()
+        },
+    ] {
+        let options = ParseOptions {
+            limits,
+            ..ParseOptions::default()
+        };
+        let err = JSON::parse_from_string(text, &options)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("limit exceeded"), "{}", err);
+        assert!(err.starts_with("1:"), "{}", err);
+    }
+
+    let options = ParseOptions {
+        limits: Limits {
+            max_string_len: Some(3),
+            ..Limits::default()
+        },
+        ..ParseOptions::default()
+    };
+    let err = JSON::parse_from_string("{\"a\": [\"abcd\"]}", &options).unwrap_err();
+    assert!(err.to_string().starts_with("1:8: "), "{}", err);
+
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    for (text, expected) in [("[1,]", "[1]"), ("{\"a\": [1, ], }", "{\"a\":[1]}")] {
+        assert_eq!(
+            JSON::parse_from_string(text, &options).unwrap().to_string(),
+            expected
+        );
+        assert!(JSON::parse_from_string(text, &ParseOptions::default()).is_err());
+    }
+}
+
+#[test]
+fn chunked_documents_parse_as_a_whole() {
+    use json_parser::{chunked::ChunkedParser, limits::Limits, parser::ParseOptions};
+
+    let stream = b"{\"a\": [1, \"two\"]}[3]\n  [{\"b\": null}]";
+    let whole: Vec<String> = ChunkedParser::new(ParseOptions::default())
+        .feed_all(stream)
+        .into_iter()
+        .map(|document| document.unwrap().to_string())
+        .collect();
+    assert_eq!(whole, [r#"{"a":[1,"two"]}"#, "[3]", r#"[{"b":null}]"#]);
+
+    // Every split, even inside strings and numbers, gives the same documents.
+    for size in 1..stream.len() {
+        let mut parser = ChunkedParser::new(ParseOptions::default());
+        let mut documents = vec![];
+        for chunk in stream.chunks(size) {
+            documents.extend(parser.feed_all(chunk));
+        }
+        assert!(parser.finish().is_ok());
+        let documents: Vec<String> = documents
+            .into_iter()
+            .map(|document| document.unwrap().to_string())
+            .collect();
+        assert_eq!(documents, whole, "chunks of {}", size);
+    }
+
+    // `feed` stops at the end of the first document it completes.
+    let mut parser = ChunkedParser::new(ParseOptions::default());
+    let fed = parser.feed(b"[1][2");
+    assert_eq!(fed.consumed, 3);
+    assert_eq!(fed.document.unwrap().unwrap().to_string(), "[1]");
+    let fed = parser.feed(b"[2");
+    assert_eq!(fed.consumed, 2);
+    assert!(fed.document.is_none());
+    assert!(parser.is_pending());
+    assert!(parser.finish().is_err());
+    let fed = parser.feed(b"] ");
+    assert_eq!(fed.consumed, 1);
+    assert_eq!(fed.document.unwrap().unwrap().to_string(), "[2]");
+
+    let options = ParseOptions {
+        limits: Limits {
+            max_nodes: Some(2),
+            max_string_len: Some(1),
+            ..Limits::default()
+        },
+        ..ParseOptions::default()
+    };
+    let mut parser = ChunkedParser::new(options);
+    let documents = parser.feed_all(b"[1] [1,2,3,4,\"abcdef\"]");
+    assert!(documents[0].is_ok());
+    let err = documents[1].as_ref().unwrap_err().to_string();
+    assert!(err.starts_with("1:8: limit exceeded"), "{}", err);
+}
+
+#[test]
+fn repeated_subtrees_are_shared() {
+    use json_parser::parser::ParseOptions;
+
+    let text = r#"[
+        {"tags": ["a", "b"], "meta": {"v": 1}},
+        {"tags": ["a", "b"], "meta": {"v": 1}},
+        {"tags": ["a", "b"], "meta": {"v": 2}}
+    ]"#;
+    let value = JSON::parse_from_string(text, &ParseOptions::default()).unwrap();
+    let stats = value.dedup_stats();
+    let (shared, shared_stats) = JSON::parse_shared(text, &ParseOptions::default()).unwrap();
+    assert_eq!(shared_stats, stats);
+    assert_eq!((stats.subtrees, stats.duplicates), (10, 2));
+    assert!(stats.duplicate_bytes > 0);
+    assert_eq!(shared.to_value(), value);
+
+    let first = shared.index(0).unwrap();
+    let second = shared.index(1).unwrap();
+    let third = shared.index(2).unwrap();
+    assert!(first.ptr_eq(second));
+    assert!(!first.ptr_eq(third));
+    assert!(first
+        .get("tags")
+        .unwrap()
+        .ptr_eq(third.get("tags").unwrap()));
+    assert!(third.get("missing").is_none());
+}
+
+#[test]
+fn stream_validation_agrees_with_the_parser() {
+    use json_parser::{
+        control::ControlPolicy, limits::Limits, parser::ParseOptions, stream::validate_stream,
+    };
+
+    let lenient = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let reject = ParseOptions {
+        control: ControlPolicy::Reject,
+        ..ParseOptions::default()
+    };
+    let limited = ParseOptions {
+        limits: Limits {
+            max_depth: Some(3),
+            max_nodes: Some(8),
+            max_string_len: Some(4),
+            max_members: Some(2),
+            max_array_len: Some(3),
+            max_memory: Some(1000),
+            ..Limits::default()
+        },
+        ..ParseOptions::default()
+    };
+
+    let mut documents: Vec<Vec<u8>> = std::fs::read_dir("test")
+        .unwrap()
+        .map(|entry| std::fs::read(entry.unwrap().path()).unwrap())
+        .collect();
+    documents.extend(
+        [
+            "1",
+            "\"text\"",
+            "[1.5]",
+            "[99999999999]",
+            "[340282366920938463463374607431768211456]",
+            "[-0, 0, -2147483648, 2147483647]",
+            "[00]",
+            "[-01]",
+            "[-]",
+            "[1,]",
+            "{\"a\": 1,}",
+            "[1,,]",
+            "[NaN, Infinity, -Infinity]",
+            "[\"\\u0001\"]",
+            "[\"\\t\\b\"]",
+            "{\"\u{7f}\": 1}",
+            "[\"\u{85}\"]",
+            "[[[[1]]]]",
+            "[1, 2, 3, 4]",
+            "{\"a\": 1, \"b\": 2, \"c\": 3}",
+            "[\"abcde\"]",
+            "{\"abcde\": 1}",
+            "[[], [], [], {}, {}]",
+        ]
+        .map(|text| text.as_bytes().to_vec()),
+    );
+    documents.push(format!("{}{}", "[".repeat(600), "]".repeat(600)).into_bytes());
+    documents.push(b"[\"\xed\xa0\x80\"]".to_vec());
+    documents.push(b"[\"\xc0\xaf\"]".to_vec());
+
+    for options in [ParseOptions::default(), lenient, reject, limited] {
+        for document in &documents {
+            let parsed = std::str::from_utf8(document)
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    JSON::parse_from_string(text, &options).map_err(|err| err.to_string())
+                });
+            let streamed = validate_stream(document.as_slice(), &options);
+            assert_eq!(
+                parsed.is_ok(),
+                streamed.is_ok(),
+                "{:?} with {:?}: {:?} / {:?}",
+                String::from_utf8_lossy(document),
+                options,
+                parsed.err(),
+                streamed.err(),
+            );
+        }
+    }
+}